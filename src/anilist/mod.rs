@@ -1,3 +1,6 @@
+use crate::cache::{CacheKind, MetadataCache};
+use crate::config::RetryConfig;
+use crate::retry::{self, Attempt, RetryPolicy};
 use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -21,6 +24,8 @@ struct GraphQLResponse {
 #[derive(Debug, Deserialize)]
 struct GraphQLError {
     message: String,
+    #[serde(default)]
+    status: Option<u16>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,15 +70,29 @@ pub struct FuzzyDate {
 
 pub struct AniListClient {
     client: reqwest::Client,
+    cache: MetadataCache,
+    retry: RetryPolicy,
 }
 
 impl AniListClient {
     pub fn new() -> Self {
         Self {
             client: build_http_client(),
+            cache: MetadataCache::disabled(),
+            retry: RetryPolicy::new("AniList", &RetryConfig::default()),
         }
     }
 
+    pub fn with_cache(mut self, cache: MetadataCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_retry(mut self, config: &RetryConfig) -> Self {
+        self.retry = RetryPolicy::new("AniList", config);
+        self
+    }
+
     pub async fn search_anime(&self, query: &str) -> Result<Vec<Media>> {
         let graphql_query = r#"
             query ($search: String) {
@@ -101,24 +120,7 @@ impl AniListClient {
             "search": query
         });
 
-        let request = GraphQLRequest {
-            query: graphql_query.to_string(),
-            variables,
-        };
-
-        let response = self
-            .client
-            .post(API_URL)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .context("AniList 请求发送失败")?
-            .error_for_status()
-            .context("AniList 请求返回错误状态")?;
-
-        let graphql_response = response.json().await.context("AniList 响应解析失败")?;
+        let graphql_response = self.post_graphql(graphql_query, variables).await?;
 
         let data = extract_graphql_data(graphql_response)?;
 
@@ -154,26 +156,77 @@ impl AniListClient {
             "id": id
         });
 
+        let graphql_response = self.post_graphql(graphql_query, variables).await?;
+
+        Ok(extract_graphql_data(graphql_response)?.and_then(|d| d.media))
+    }
+
+    /// 发送 GraphQL 请求；只缓存没有错误的响应
+    async fn post_graphql(
+        &self,
+        graphql_query: &str,
+        variables: serde_json::Value,
+    ) -> Result<GraphQLResponse> {
         let request = GraphQLRequest {
             query: graphql_query.to_string(),
             variables,
         };
+        let key = serde_json::to_string(&request).context("AniList 请求序列化失败")?;
 
-        let response = self
-            .client
-            .post(API_URL)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .context("AniList 请求发送失败")?
-            .error_for_status()
-            .context("AniList 请求返回错误状态")?;
+        if let Some(body) = self.cache.get(CacheKind::AniList, &key)
+            && let Ok(response) = serde_json::from_value(body)
+        {
+            return Ok(response);
+        }
+        if self.cache.is_offline() {
+            bail!(
+                "离线模式下没有缓存的 AniList 查询结果: {}",
+                request.variables
+            );
+        }
+
+        // 限流时 AniList 也可能以 GraphQL 错误的形式返回 "Too Many Requests."，与 HTTP 重试共用尝试次数
+        let (body, response) = self
+            .retry
+            .run(|| async {
+                let sent = self
+                    .client
+                    .post(API_URL)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json")
+                    .json(&request)
+                    .send()
+                    .await;
+                match retry::classify(sent) {
+                    Attempt::Retry {
+                        result,
+                        reason,
+                        retry_after,
+                    } => Attempt::Retry {
+                        result: read_graphql_response(result).await,
+                        reason,
+                        retry_after,
+                    },
+                    Attempt::Done(result) => {
+                        let result = read_graphql_response(result).await;
+                        match &result {
+                            Ok((_, response)) if is_rate_limited(response) => Attempt::Retry {
+                                result,
+                                reason: "Too Many Requests".to_string(),
+                                retry_after: None,
+                            },
+                            _ => Attempt::Done(result),
+                        }
+                    }
+                }
+            })
+            .await?;
 
-        let graphql_response = response.json().await.context("AniList 响应解析失败")?;
+        if response.errors.is_empty() {
+            self.cache.put(CacheKind::AniList, &key, &body);
+        }
 
-        Ok(extract_graphql_data(graphql_response)?.and_then(|d| d.media))
+        Ok(response)
     }
 }
 
@@ -184,6 +237,28 @@ fn build_http_client() -> reqwest::Client {
         .expect("创建 AniList HTTP 客户端失败")
 }
 
+/// 读取 GraphQL 响应，同时保留原始 JSON 用于写入缓存
+async fn read_graphql_response(
+    result: reqwest::Result<reqwest::Response>,
+) -> Result<(serde_json::Value, GraphQLResponse)> {
+    let body: serde_json::Value = result
+        .context("AniList 请求发送失败")?
+        .error_for_status()
+        .context("AniList 请求返回错误状态")?
+        .json()
+        .await
+        .context("AniList 响应解析失败")?;
+    let response = serde_json::from_value(body.clone()).context("AniList 响应解析失败")?;
+    Ok((body, response))
+}
+
+fn is_rate_limited(response: &GraphQLResponse) -> bool {
+    response
+        .errors
+        .iter()
+        .any(|error| error.status == Some(429) || error.message.starts_with("Too Many Requests"))
+}
+
 fn extract_graphql_data(response: GraphQLResponse) -> Result<Option<Data>> {
     if !response.errors.is_empty() {
         let message = response
@@ -331,6 +406,7 @@ mod tests {
             data: None,
             errors: vec![GraphQLError {
                 message: "Too Many Requests.".to_string(),
+                status: None,
             }],
         };
 
@@ -338,4 +414,21 @@ mod tests {
         assert!(err.to_string().contains("AniList GraphQL 错误"));
         assert!(err.to_string().contains("Too Many Requests."));
     }
+
+    #[test]
+    fn test_is_rate_limited_detects_status_or_message() {
+        let parse =
+            |json: serde_json::Value| -> GraphQLResponse { serde_json::from_value(json).unwrap() };
+
+        assert!(is_rate_limited(&parse(serde_json::json!({
+            "data": null,
+            "errors": [{"message": "Too Many Requests.", "status": 429}]
+        }))));
+        assert!(is_rate_limited(&parse(serde_json::json!({
+            "errors": [{"message": "Too Many Requests."}]
+        }))));
+        assert!(!is_rate_limited(&parse(serde_json::json!({
+            "errors": [{"message": "Not Found.", "status": 404}]
+        }))));
+    }
 }
@@ -0,0 +1,284 @@
+use crate::config::CacheConfig;
+use crate::operations::journal::resolve_data_dir;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CACHE_DIR_NAME: &str = "cache";
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+/// 元数据请求的类别，各自使用独立的有效期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    Search,
+    Show,
+    Season,
+    Episode,
+    AniList,
+}
+
+impl CacheKind {
+    fn dir_name(self) -> &'static str {
+        match self {
+            CacheKind::Search => "search",
+            CacheKind::Show => "show",
+            CacheKind::Season => "season",
+            CacheKind::Episode => "episode",
+            CacheKind::AniList => "anilist",
+        }
+    }
+
+    fn ttl_hours(self, config: &CacheConfig) -> u64 {
+        match self {
+            CacheKind::Search => config.search_ttl_hours,
+            CacheKind::Show => config.show_ttl_hours,
+            CacheKind::Season => config.season_ttl_hours,
+            CacheKind::Episode => config.episode_ttl_hours,
+            CacheKind::AniList => config.anilist_ttl_hours,
+        }
+    }
+}
+
+/// 缓存的使用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    /// 读取未过期的缓存，未命中时请求网络
+    #[default]
+    Normal,
+    /// `--refresh`：忽略已有缓存，但仍写入新结果
+    Refresh,
+    /// `--offline`：只读缓存（包括已过期的），从不请求网络
+    Offline,
+}
+
+impl CacheMode {
+    pub fn from_flags(refresh: bool, offline: bool) -> Self {
+        match (refresh, offline) {
+            (_, true) => Self::Offline,
+            (true, false) => Self::Refresh,
+            (false, false) => Self::Normal,
+        }
+    }
+}
+
+/// 缓存文件内容；保存完整键，哈希碰撞时视为未命中
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    fetched_at: u64,
+    body: serde_json::Value,
+}
+
+/// 保存在数据目录下的 TMDB / AniList 响应缓存，按请求路径、参数与语言区分
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    /// 为 None 时不读写缓存
+    dir: Option<PathBuf>,
+    config: CacheConfig,
+    mode: CacheMode,
+}
+
+impl MetadataCache {
+    pub fn disabled() -> Self {
+        Self {
+            dir: None,
+            config: CacheConfig::default(),
+            mode: CacheMode::Normal,
+        }
+    }
+
+    pub fn new(dir: PathBuf, config: &CacheConfig, mode: CacheMode) -> Self {
+        Self {
+            dir: config.enabled.then_some(dir),
+            config: config.clone(),
+            mode,
+        }
+    }
+
+    /// 使用 `ANIME_RENAMER_DATA_DIR`（或系统数据目录）下的 `cache` 目录
+    pub fn open_default(config: &CacheConfig, mode: CacheMode) -> Result<Self> {
+        if !config.enabled {
+            if mode == CacheMode::Offline {
+                bail!("离线模式需要元数据缓存，请在配置文件中设置 cache.enabled = true");
+            }
+            return Ok(Self::disabled());
+        }
+        Ok(Self::new(
+            resolve_data_dir()?.join(CACHE_DIR_NAME),
+            config,
+            mode,
+        ))
+    }
+
+    /// 离线模式下调用方不应请求网络
+    pub fn is_offline(&self) -> bool {
+        self.mode == CacheMode::Offline
+    }
+
+    /// 读取未过期的缓存；离线模式下过期的缓存同样可用
+    pub fn get(&self, kind: CacheKind, key: &str) -> Option<serde_json::Value> {
+        if self.mode == CacheMode::Refresh {
+            return None;
+        }
+        let ttl = kind.ttl_hours(&self.config) * SECONDS_PER_HOUR;
+        let path = self.entry_path(kind, key)?;
+        let entry: CacheEntry = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+
+        let fresh = now_seconds().saturating_sub(entry.fetched_at) < ttl;
+        (entry.key == key && (fresh || self.is_offline())).then_some(entry.body)
+    }
+
+    /// 写入缓存；失败时只是下次重新请求，不影响本次运行
+    pub fn put(&self, kind: CacheKind, key: &str, body: &serde_json::Value) {
+        if kind.ttl_hours(&self.config) == 0 {
+            return;
+        }
+        let Some(path) = self.entry_path(kind, key) else {
+            return;
+        };
+        let entry = CacheEntry {
+            key: key.to_string(),
+            fetched_at: now_seconds(),
+            body: body.clone(),
+        };
+        let Ok(content) = serde_json::to_vec(&entry) else {
+            return;
+        };
+        if let Some(parent) = path.parent()
+            && fs::create_dir_all(parent).is_ok()
+        {
+            let temp = path.with_extension("tmp");
+            if fs::write(&temp, content).is_ok() {
+                let _ = fs::rename(&temp, &path);
+            }
+        }
+    }
+
+    fn entry_path(&self, kind: CacheKind, key: &str) -> Option<PathBuf> {
+        Some(
+            self.dir
+                .as_ref()?
+                .join(kind.dir_name())
+                .join(format!("{:016x}.json", fnv1a(key))),
+        )
+    }
+}
+
+/// 64 位 FNV-1a 哈希，结果不随 Rust 版本变化，升级后缓存文件名保持一致
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDir;
+    use serde_json::json;
+
+    #[test]
+    fn test_cache_round_trips_and_respects_refresh() {
+        let dir = TestDir::new("metadata_cache_round_trip");
+        let config = CacheConfig::default();
+        let cache = MetadataCache::new(dir.path().to_path_buf(), &config, CacheMode::Normal);
+
+        assert_eq!(
+            cache.get(CacheKind::Show, "tmdb:/tv/1?language=zh-CN"),
+            None
+        );
+        cache.put(
+            CacheKind::Show,
+            "tmdb:/tv/1?language=zh-CN",
+            &json!({"id": 1}),
+        );
+        let cached = cache.get(CacheKind::Show, "tmdb:/tv/1?language=zh-CN");
+        let other_language = cache.get(CacheKind::Show, "tmdb:/tv/1?language=ja-JP");
+        let refreshed = MetadataCache::new(dir.path().to_path_buf(), &config, CacheMode::Refresh)
+            .get(CacheKind::Show, "tmdb:/tv/1?language=zh-CN");
+
+        assert_eq!(cached, Some(json!({"id": 1})));
+        assert_eq!(other_language, None);
+        assert_eq!(refreshed, None);
+    }
+
+    #[test]
+    fn test_cache_skips_expired_and_disabled_kinds() {
+        let dir = TestDir::new("metadata_cache_expired");
+        let config = CacheConfig {
+            season_ttl_hours: 0,
+            ..CacheConfig::default()
+        };
+        let cache = MetadataCache::new(dir.path().to_path_buf(), &config, CacheMode::Normal);
+
+        cache.put(CacheKind::Season, "season", &json!(1));
+        let path = cache.entry_path(CacheKind::Show, "show").unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let stale = CacheEntry {
+            key: "show".to_string(),
+            fetched_at: now_seconds() - 25 * SECONDS_PER_HOUR,
+            body: json!(2),
+        };
+        fs::write(&path, serde_json::to_vec(&stale).unwrap()).unwrap();
+
+        let season = cache.get(CacheKind::Season, "season");
+        let show = cache.get(CacheKind::Show, "show");
+        let offline_show =
+            MetadataCache::new(dir.path().to_path_buf(), &config, CacheMode::Offline)
+                .get(CacheKind::Show, "show");
+
+        assert_eq!(season, None);
+        assert_eq!(show, None);
+        assert_eq!(offline_show, Some(json!(2)));
+    }
+
+    #[test]
+    fn test_entry_path_uses_stable_hash() {
+        let cache = MetadataCache::new(
+            PathBuf::from("/cache"),
+            &CacheConfig::default(),
+            CacheMode::Normal,
+        );
+
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            cache.entry_path(CacheKind::Show, "a"),
+            Some(PathBuf::from(format!(
+                "/cache/{}/af63dc4c8601ec8c.json",
+                CacheKind::Show.dir_name()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_cache_mode_prefers_offline_over_refresh() {
+        assert_eq!(CacheMode::from_flags(false, false), CacheMode::Normal);
+        assert_eq!(CacheMode::from_flags(true, false), CacheMode::Refresh);
+        assert_eq!(CacheMode::from_flags(true, true), CacheMode::Offline);
+    }
+
+    #[test]
+    fn test_disabled_cache_never_stores() {
+        let dir = TestDir::new("metadata_cache_disabled");
+        let config = CacheConfig {
+            enabled: false,
+            ..CacheConfig::default()
+        };
+        let cache_dir = dir.path().join("cache");
+        let cache = MetadataCache::new(cache_dir.clone(), &config, CacheMode::Normal);
+
+        cache.put(CacheKind::Search, "search", &json!([]));
+
+        assert_eq!(cache.get(CacheKind::Search, "search"), None);
+        assert!(!cache_dir.exists());
+    }
+}
@@ -0,0 +1,82 @@
+use crc32fast::Hasher;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 流式计算 CRC32，返回（字节数, 校验值）
+pub fn crc32_reader(mut reader: impl Read) -> io::Result<(u64, u32)> {
+    let mut hasher = Hasher::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        total += read as u64;
+    }
+
+    Ok((total, hasher.finalize()))
+}
+
+pub fn crc32_file(path: &Path) -> io::Result<(u64, u32)> {
+    crc32_reader(File::open(path)?)
+}
+
+/// 流式复制文件并在复制过程中计算源数据的 CRC32，返回（字节数, 校验值）
+pub fn copy_with_crc32(from: &Path, to: &Path) -> io::Result<(u64, u32)> {
+    let mut reader = File::open(from)?;
+    let mut writer = File::create(to)?;
+    let mut hasher = Hasher::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        total += read as u64;
+    }
+
+    writer.sync_all()?;
+    if let Ok(metadata) = reader.metadata() {
+        let _ = writer.set_permissions(metadata.permissions());
+    }
+
+    Ok((total, hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDir;
+
+    #[test]
+    fn test_crc32_reader_matches_known_value() {
+        let (size, crc) = crc32_reader("123456789".as_bytes()).unwrap();
+
+        assert_eq!(size, 9);
+        assert_eq!(crc, 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_copy_with_crc32_reports_source_checksum() {
+        let dir = TestDir::new("checksum_copy");
+        let source = dir.path().join("source.mkv");
+        let target = dir.path().join("target.mkv");
+        std::fs::write(&source, b"123456789").unwrap();
+
+        let copied = copy_with_crc32(&source, &target).unwrap();
+        let verified = crc32_file(&target).unwrap();
+
+        assert_eq!(copied, (9, 0xCBF4_3926));
+        assert_eq!(verified, copied);
+    }
+}
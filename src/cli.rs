@@ -1,10 +1,21 @@
-use anyhow::{Context, Result};
+use crate::cache::CacheMode;
+use crate::operations::RenameMode;
+use crate::output::OutputFormat;
+use crate::parser::TagKind;
+use crate::tmdb::EpisodeGroupSelector;
+use anyhow::{Context, Result, bail};
 use clap::{Args, Parser as ClapParser, Subcommand};
+use std::path::PathBuf;
 
 const ROOT_HELP: &str = "\
 用法:
   anime_renamer [OPTIONS] [PATH]
   anime_renamer nfo [OPTIONS] <PATH>
+  anime_renamer undo [OPTIONS]
+  anime_renamer plan [OPTIONS] <PATH>
+  anime_renamer apply [OPTIONS] <PLAN>
+  anime_renamer verify [OPTIONS] <PATH>
+  anime_renamer parse [OPTIONS] <FILENAME>...
 
 参数:
   [PATH]  要扫描的目录路径
@@ -14,17 +25,36 @@ const ROOT_HELP: &str = "\
   -n, --dry-run              预览模式（不实际重命名）
       --name <NAME>          指定番剧名称（跳过自动识别）
   -l, --language <LANGUAGE>  语言偏好 [默认: zh-CN]
-      --keep-tags            保留所有标签
+      --keep-tags[=<KINDS>]  保留标签，可用逗号指定类型：group、resolution、video-codec、bit-depth、
+                             audio-codec、source、subtitles、crc32、container；省略类型时保留所有标签
       --season-folders       为每一季创建单独的文件夹（Season 1, Season 2, ...）
+  -t, --template <TEMPLATE>  文件名模板（覆盖配置文件中的 rename.template）
+      --episode-titles       从 TMDB 获取单集标题并写入文件名
       --use-anilist          使用 AniList API 而不是 TMDB（更好的罗马音支持）
   -s, --season <SEASON>      手动指定季度（跳过自动映射）
   -o, --offset <OFFSET>      集数偏移量 [默认: 0]
   -i, --tmdb-id <TMDB_ID>    直接指定 TMDB ID
+      --episode-group <GROUP>
+                             按 TMDB 剧集组映射季度与集数：剧集组 ID，或类型 absolute、dvd、story-arc、
+                             digital、production、tv、original（同类型取集数最多的一组）
+  -y, --yes                  跳过所有交互确认（AniList 使用第一个标题，TMDB 匹配度不足时视为未匹配）
+      --output <FORMAT>      输出格式：text 或 json（json 时 stdout 只输出重命名计划）[默认: text]
+      --mode <MODE>          生成目标文件的方式：rename、move、copy、hardlink 或 symlink [默认: rename]
+      --output-dir <DIR>     媒体库根目录，目标路径相对于该目录生成（默认为视频所在目录）
+      --verify-crc           重命名前按文件名中的 CRC32 校验文件并报告损坏的文件
+      --skip-corrupted       校验 CRC32 并跳过损坏的文件（隐含 --verify-crc）
+      --refresh              忽略本地元数据缓存，重新请求 TMDB / AniList
+      --offline              只使用本地元数据缓存，不访问网络
   -h, --help                 显示帮助信息
   -V, --version              显示版本信息
 
 子命令:
   nfo                        导出 Kodi / Jellyfin NFO 与图片元数据
+  undo                       撤销已执行的重命名批次
+  plan                       生成重命名计划文件（不修改任何文件）
+  apply                      执行重命名计划文件
+  verify                     按文件名中的 CRC32 校验视频文件
+  parse                      显示文件名的解析过程（用于调试和反馈问题）
 ";
 
 const NFO_HELP: &str = "\
@@ -39,7 +69,96 @@ const NFO_HELP: &str = "\
   -n, --dry-run              预览模式（不实际写入）
   -l, --language <LANGUAGE>  语言偏好 [默认: zh-CN]
   -i, --tmdb-id <TMDB_ID>    直接指定 TMDB ID
+      --episode-group <GROUP>
+                             按 TMDB 剧集组查找单集元数据：剧集组 ID，或类型 absolute、dvd、story-arc、
+                             digital、production、tv、original（同类型取集数最多的一组）
       --force                覆盖已有 NFO 文件
+      --refresh              忽略本地元数据缓存，重新请求 TMDB
+      --offline              只使用本地元数据缓存，不访问网络（已存在的图片不会重新下载）
+  -h, --help                 显示帮助信息
+";
+
+const UNDO_HELP: &str = "\
+用法:
+  anime_renamer undo [OPTIONS]
+
+选项:
+      --last                 撤销最近一次重命名（默认）
+      --id <ID>              撤销指定 ID 的重命名批次
+      --list                 列出可撤销的重命名记录
+  -n, --dry-run              预览模式（不实际撤销）
+  -y, --yes                  跳过确认
+  -h, --help                 显示帮助信息
+";
+
+const PLAN_HELP: &str = "\
+用法:
+  anime_renamer plan [OPTIONS] <PATH>
+
+参数:
+  <PATH>  要扫描的目录路径
+
+选项:
+      --out <FILE>           计划文件输出路径（也可用 -o），省略时输出到 stdout
+  -r, --recursive            是否递归扫描子目录
+      --name <NAME>          指定番剧名称（跳过自动识别）
+  -l, --language <LANGUAGE>  语言偏好 [默认: zh-CN]
+      --keep-tags[=<KINDS>]  保留标签，可用逗号指定类型：group、resolution、video-codec、bit-depth、
+                             audio-codec、source、subtitles、crc32、container；省略类型时保留所有标签
+      --season-folders       为每一季创建单独的文件夹（Season 1, Season 2, ...）
+  -t, --template <TEMPLATE>  文件名模板（覆盖配置文件中的 rename.template）
+      --episode-titles       从 TMDB 获取单集标题并写入文件名
+      --use-anilist          使用 AniList API 而不是 TMDB（更好的罗马音支持）
+  -s, --season <SEASON>      手动指定季度（跳过自动映射）
+      --offset <OFFSET>      集数偏移量 [默认: 0]
+  -i, --tmdb-id <TMDB_ID>    直接指定 TMDB ID
+      --episode-group <GROUP>
+                             按 TMDB 剧集组映射季度与集数：剧集组 ID，或类型 absolute、dvd、story-arc、
+                             digital、production、tv、original（同类型取集数最多的一组）
+  -y, --yes                  跳过所有交互确认（AniList 使用第一个标题，TMDB 匹配度不足时视为未匹配）
+      --mode <MODE>          生成目标文件的方式：rename、move、copy、hardlink 或 symlink [默认: rename]
+      --output-dir <DIR>     媒体库根目录，目标路径相对于该目录生成（默认为视频所在目录）
+      --verify-crc           重命名前按文件名中的 CRC32 校验文件并报告损坏的文件
+      --skip-corrupted       校验 CRC32 并跳过损坏的文件（隐含 --verify-crc）
+      --refresh              忽略本地元数据缓存，重新请求 TMDB / AniList
+      --offline              只使用本地元数据缓存，不访问网络
+  -h, --help                 显示帮助信息
+";
+
+const APPLY_HELP: &str = "\
+用法:
+  anime_renamer apply [OPTIONS] <PLAN>
+
+参数:
+  <PLAN>  plan 子命令生成的计划文件
+
+选项:
+  -n, --dry-run              预览模式（只校验，不实际重命名）
+  -y, --yes                  跳过确认
+  -h, --help                 显示帮助信息
+";
+
+const VERIFY_HELP: &str = "\
+用法:
+  anime_renamer verify [OPTIONS] <PATH>
+
+参数:
+  <PATH>  要扫描的目录路径
+
+选项:
+  -r, --recursive            是否递归扫描子目录
+  -h, --help                 显示帮助信息
+";
+
+const PARSE_HELP: &str = "\
+用法:
+  anime_renamer parse [OPTIONS] <FILENAME>...
+
+参数:
+  <FILENAME>...  要解析的文件名（可带目录，会按目录补充番剧名、季度与年份；不需要文件存在）
+
+选项:
+      --output <FORMAT>      输出格式：text 或 json [默认: text]
   -h, --help                 显示帮助信息
 ";
 
@@ -63,6 +182,16 @@ pub(crate) struct Cli {
 pub(crate) enum Command {
     #[command(override_help = NFO_HELP, about = "导出 Kodi / Jellyfin NFO 与图片元数据")]
     Nfo(NfoArgs),
+    #[command(override_help = UNDO_HELP, about = "撤销已执行的重命名批次")]
+    Undo(UndoArgs),
+    #[command(override_help = PLAN_HELP, about = "生成重命名计划文件（不修改任何文件）")]
+    Plan(PlanArgs),
+    #[command(override_help = APPLY_HELP, about = "执行重命名计划文件")]
+    Apply(ApplyArgs),
+    #[command(override_help = VERIFY_HELP, about = "按文件名中的 CRC32 校验视频文件")]
+    Verify(VerifyArgs),
+    #[command(override_help = PARSE_HELP, about = "显示文件名的解析过程")]
+    Parse(ParseArgs),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -87,14 +216,30 @@ pub(crate) struct RenameCliArgs {
     #[arg(short, long, default_value = "zh-CN")]
     pub(crate) language: String,
 
-    /// 保留所有标签
-    #[arg(long)]
-    pub(crate) keep_tags: bool,
+    /// 保留标签，可指定类型（逗号分隔），省略时保留所有标签
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KINDS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ',',
+        default_missing_value = "all"
+    )]
+    pub(crate) keep_tags: Option<Vec<TagKind>>,
 
     /// 为每一季创建单独的文件夹（Season 1, Season 2, ...）
     #[arg(long)]
     pub(crate) season_folders: bool,
 
+    /// 文件名模板（覆盖配置文件中的 rename.template）
+    #[arg(short = 't', long)]
+    pub(crate) template: Option<String>,
+
+    /// 从 TMDB 获取单集标题并写入文件名
+    #[arg(long)]
+    pub(crate) episode_titles: bool,
+
     /// 使用 AniList API 而不是 TMDB（更好的罗马音支持）
     #[arg(long)]
     pub(crate) use_anilist: bool,
@@ -110,6 +255,42 @@ pub(crate) struct RenameCliArgs {
     /// 直接指定 TMDB ID
     #[arg(short = 'i', long)]
     pub(crate) tmdb_id: Option<u32>,
+
+    /// 按 TMDB 剧集组映射季度与集数（剧集组 ID 或类型）
+    #[arg(long, value_name = "GROUP")]
+    pub(crate) episode_group: Option<EpisodeGroupSelector>,
+
+    /// 跳过所有交互确认（AniList 使用第一个标题，TMDB 匹配度不足时视为未匹配）
+    #[arg(short = 'y', long)]
+    pub(crate) yes: bool,
+
+    /// 输出格式，json 时 stdout 只输出重命名计划
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) output: OutputFormat,
+
+    /// 生成目标文件的方式
+    #[arg(long, value_enum, default_value_t = RenameMode::Rename)]
+    pub(crate) mode: RenameMode,
+
+    /// 媒体库根目录，目标路径相对于该目录生成（默认为视频所在目录）
+    #[arg(long, value_name = "DIR")]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    /// 重命名前按文件名中的 CRC32 校验文件并报告损坏的文件
+    #[arg(long)]
+    pub(crate) verify_crc: bool,
+
+    /// 校验 CRC32 并跳过损坏的文件（隐含 --verify-crc）
+    #[arg(long)]
+    pub(crate) skip_corrupted: bool,
+
+    /// 忽略本地元数据缓存，重新请求 TMDB / AniList
+    #[arg(long)]
+    pub(crate) refresh: bool,
+
+    /// 只使用本地元数据缓存，不访问网络
+    #[arg(long, conflicts_with = "refresh")]
+    pub(crate) offline: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -119,18 +300,36 @@ pub(crate) struct RenameArgs {
     pub(crate) dry_run: bool,
     pub(crate) name: Option<String>,
     pub(crate) language: String,
-    pub(crate) keep_tags: bool,
+    /// 要保留的标签类型，`None` 表示不保留
+    pub(crate) keep_tags: Option<Vec<TagKind>>,
     pub(crate) season_folders: bool,
+    pub(crate) template: Option<String>,
+    pub(crate) episode_titles: bool,
     pub(crate) use_anilist: bool,
     pub(crate) season: Option<u32>,
     pub(crate) offset: i32,
     pub(crate) tmdb_id: Option<u32>,
+    pub(crate) episode_group: Option<EpisodeGroupSelector>,
+    pub(crate) yes: bool,
+    pub(crate) output: OutputFormat,
+    pub(crate) mode: RenameMode,
+    pub(crate) output_dir: Option<PathBuf>,
+    /// 重命名前校验 CRC32
+    pub(crate) verify_crc: bool,
+    /// 跳过 CRC32 校验失败的文件
+    pub(crate) skip_corrupted: bool,
+    /// 元数据缓存的使用方式
+    pub(crate) cache_mode: CacheMode,
 }
 
 impl TryFrom<RenameCliArgs> for RenameArgs {
     type Error = anyhow::Error;
 
     fn try_from(value: RenameCliArgs) -> Result<Self> {
+        if value.mode == RenameMode::Move && value.output_dir.is_none() {
+            bail!("--mode move 需要同时指定 --output-dir");
+        }
+
         Ok(Self {
             path: value.path.context("缺少要扫描的目录路径")?,
             recursive: value.recursive,
@@ -139,10 +338,20 @@ impl TryFrom<RenameCliArgs> for RenameArgs {
             language: value.language,
             keep_tags: value.keep_tags,
             season_folders: value.season_folders,
+            template: value.template,
+            episode_titles: value.episode_titles,
             use_anilist: value.use_anilist,
             season: value.season,
             offset: value.offset,
             tmdb_id: value.tmdb_id,
+            episode_group: value.episode_group,
+            yes: value.yes,
+            output: value.output,
+            mode: value.mode,
+            output_dir: value.output_dir,
+            verify_crc: value.verify_crc || value.skip_corrupted,
+            skip_corrupted: value.skip_corrupted,
+            cache_mode: CacheMode::from_flags(value.refresh, value.offline),
         })
     }
 }
@@ -168,9 +377,90 @@ pub(crate) struct NfoArgs {
     #[arg(short = 'i', long)]
     pub(crate) tmdb_id: Option<u32>,
 
+    /// 按 TMDB 剧集组查找单集元数据（剧集组 ID 或类型）
+    #[arg(long, value_name = "GROUP")]
+    pub(crate) episode_group: Option<EpisodeGroupSelector>,
+
     /// 覆盖已有 NFO 文件
     #[arg(long)]
     pub(crate) force: bool,
+
+    /// 忽略本地元数据缓存，重新请求 TMDB
+    #[arg(long)]
+    pub(crate) refresh: bool,
+
+    /// 只使用本地元数据缓存，不访问网络（已存在的图片不会重新下载）
+    #[arg(long, conflicts_with = "refresh")]
+    pub(crate) offline: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct UndoArgs {
+    /// 撤销最近一次重命名（默认）
+    #[arg(long, conflicts_with_all = ["id", "list"])]
+    pub(crate) last: bool,
+
+    /// 撤销指定 ID 的重命名批次
+    #[arg(long, conflicts_with = "list")]
+    pub(crate) id: Option<String>,
+
+    /// 列出可撤销的重命名记录
+    #[arg(long)]
+    pub(crate) list: bool,
+
+    /// 预览模式（不实际撤销）
+    #[arg(short = 'n', long)]
+    pub(crate) dry_run: bool,
+
+    /// 跳过确认
+    #[arg(short = 'y', long)]
+    pub(crate) yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(mut_arg("offset", |arg| arg.short(None)))]
+pub(crate) struct PlanArgs {
+    #[command(flatten)]
+    pub(crate) rename: RenameCliArgs,
+
+    /// 计划文件输出路径，省略时输出到 stdout
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    pub(crate) out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ApplyArgs {
+    /// plan 子命令生成的计划文件
+    pub(crate) plan: PathBuf,
+
+    /// 预览模式（只校验，不实际重命名）
+    #[arg(short = 'n', long)]
+    pub(crate) dry_run: bool,
+
+    /// 跳过确认
+    #[arg(short = 'y', long)]
+    pub(crate) yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct VerifyArgs {
+    /// 要扫描的目录路径
+    pub(crate) path: String,
+
+    /// 是否递归扫描子目录
+    #[arg(short, long)]
+    pub(crate) recursive: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ParseArgs {
+    /// 要解析的文件名
+    #[arg(required = true, value_name = "FILENAME")]
+    pub(crate) filenames: Vec<String>,
+
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) output: OutputFormat,
 }
 
 #[cfg(test)]
@@ -186,6 +476,136 @@ mod tests {
         assert_eq!(cli.rename.path.as_deref(), Some("/tmp/show"));
     }
 
+    #[test]
+    fn test_cli_parses_template_option() {
+        let cli = Cli::try_parse_from([
+            "anime_renamer",
+            "/tmp/show",
+            "--template",
+            "{show}/{show} - {episode:02}",
+        ])
+        .unwrap();
+        let args = RenameArgs::try_from(cli.rename).unwrap();
+
+        assert_eq!(
+            args.template.as_deref(),
+            Some("{show}/{show} - {episode:02}")
+        );
+    }
+
+    #[test]
+    fn test_cli_parses_keep_tags_kinds() {
+        let parse = |extra: &[&str]| {
+            let cli =
+                Cli::try_parse_from(["anime_renamer", "/tmp/show"].iter().chain(extra)).unwrap();
+            RenameArgs::try_from(cli.rename).unwrap().keep_tags
+        };
+
+        assert_eq!(parse(&[]), None);
+        assert_eq!(parse(&["--keep-tags"]), Some(vec![TagKind::All]));
+        assert_eq!(
+            parse(&["--keep-tags=resolution,group"]),
+            Some(vec![TagKind::Resolution, TagKind::Group])
+        );
+        assert!(Cli::try_parse_from(["anime_renamer", "/tmp/show", "--keep-tags=codec"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parses_non_interactive_json_output() {
+        let cli =
+            Cli::try_parse_from(["anime_renamer", "/tmp/show", "-y", "--output", "json"]).unwrap();
+        let args = RenameArgs::try_from(cli.rename).unwrap();
+
+        assert!(args.yes);
+        assert_eq!(args.output, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_cli_parses_plan_output_file_and_long_offset() {
+        let cli = Cli::try_parse_from([
+            "anime_renamer",
+            "plan",
+            "/tmp/show",
+            "-o",
+            "plan.json",
+            "--offset",
+            "-12",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Plan(args)) => {
+                assert_eq!(args.out, Some(PathBuf::from("plan.json")));
+                assert_eq!(args.rename.offset, -12);
+                assert_eq!(args.rename.path.as_deref(), Some("/tmp/show"));
+            }
+            _ => panic!("应当解析为 plan 子命令"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parses_parse_subcommand_with_multiple_files() {
+        let cli = Cli::try_parse_from([
+            "anime_renamer",
+            "parse",
+            "Show - 01.mkv",
+            "/media/Show - 02.mkv",
+            "--output",
+            "json",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Parse(args)) => {
+                assert_eq!(
+                    args.filenames,
+                    vec!["Show - 01.mkv", "/media/Show - 02.mkv"]
+                );
+                assert_eq!(args.output, OutputFormat::Json);
+            }
+            _ => panic!("应当解析为 parse 子命令"),
+        }
+        assert!(Cli::try_parse_from(["anime_renamer", "parse"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parses_apply_subcommand() {
+        let cli = Cli::try_parse_from(["anime_renamer", "apply", "plan.json", "-y"]).unwrap();
+
+        match cli.command {
+            Some(Command::Apply(args)) => {
+                assert_eq!(args.plan, PathBuf::from("plan.json"));
+                assert!(args.yes);
+                assert!(!args.dry_run);
+            }
+            _ => panic!("应当解析为 apply 子命令"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parses_hardlink_mode_with_output_dir() {
+        let cli = Cli::try_parse_from([
+            "anime_renamer",
+            "/downloads",
+            "--mode",
+            "hardlink",
+            "--output-dir",
+            "/library/anime",
+        ])
+        .unwrap();
+        let args = RenameArgs::try_from(cli.rename).unwrap();
+
+        assert_eq!(args.mode, RenameMode::Hardlink);
+        assert_eq!(args.output_dir, Some(PathBuf::from("/library/anime")));
+    }
+
+    #[test]
+    fn test_cli_rejects_move_mode_without_output_dir() {
+        let cli = Cli::try_parse_from(["anime_renamer", "/downloads", "--mode", "move"]).unwrap();
+
+        assert!(RenameArgs::try_from(cli.rename).is_err());
+    }
+
     #[test]
     fn test_cli_parses_nfo_subcommand() {
         let cli = Cli::try_parse_from(["anime_renamer", "nfo", "/tmp/show", "--force"]).unwrap();
@@ -195,10 +615,32 @@ mod tests {
                 assert_eq!(args.path, "/tmp/show");
                 assert!(args.force);
             }
-            None => panic!("应当解析为 nfo 子命令"),
+            _ => panic!("应当解析为 nfo 子命令"),
         }
     }
 
+    #[test]
+    fn test_cli_parses_undo_subcommand_with_id() {
+        let cli =
+            Cli::try_parse_from(["anime_renamer", "undo", "--id", "1700000000000", "-y"]).unwrap();
+
+        match cli.command {
+            Some(Command::Undo(args)) => {
+                assert_eq!(args.id.as_deref(), Some("1700000000000"));
+                assert!(!args.last);
+                assert!(args.yes);
+            }
+            _ => panic!("应当解析为 undo 子命令"),
+        }
+    }
+
+    #[test]
+    fn test_cli_rejects_undo_last_with_id() {
+        let result = Cli::try_parse_from(["anime_renamer", "undo", "--last", "--id", "1"]);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_cli_help_lists_nfo_subcommand() {
         let help = Cli::command().render_long_help().to_string();
@@ -0,0 +1,84 @@
+use super::record_journal;
+use crate::cli::ApplyArgs;
+use crate::error::FailureKind;
+use crate::operations::plan::PlanFile;
+use crate::operations::{RenameKind, execute_rename_operations, validate_rename_operations};
+use crate::output::{self, status};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn print_apply_preview(plan: &PlanFile) {
+    println!("执行计划预览 (目录: {}):\n", plan.root.display());
+
+    for (i, planned) in plan.operations.iter().enumerate() {
+        let operation = &planned.operation;
+        let label = match operation.kind {
+            RenameKind::Video => "视频",
+            RenameKind::Subtitle => "字幕",
+        };
+        println!("[{}] {label} ({})", i + 1, operation.mode.label());
+        println!("  原文件: {}", display_path(&operation.source, &plan.root));
+        println!("  新路径: {}", display_path(&operation.target, &plan.root));
+        println!();
+    }
+}
+
+pub(crate) fn run(args: &ApplyArgs) -> Result<()> {
+    let plan = PlanFile::load(&args.plan)?;
+
+    if plan.operations.is_empty() {
+        println!("计划中没有需要执行的操作");
+        return Ok(());
+    }
+
+    print_apply_preview(&plan);
+
+    plan.verify_sources()
+        .context(FailureKind::Conflict)
+        .context("无法执行计划，源文件状态已发生变化")?;
+    let operations = plan.rename_operations();
+    validate_rename_operations(&operations).context(FailureKind::Conflict)?;
+
+    if args.dry_run {
+        println!("预览模式，校验通过，未实际重命名");
+        return Ok(());
+    }
+
+    if !args.yes {
+        let input = output::prompt("继续执行计划？[Y/n] ")?;
+        if !input.is_empty() && !input.eq_ignore_ascii_case("y") {
+            status!("已取消");
+            return Ok(());
+        }
+    }
+
+    let summary = execute_rename_operations(&operations).context(FailureKind::Rollback)?;
+
+    println!("\n成功处理 {} 个视频文件", summary.video_success);
+    if summary.subtitle_success > 0 {
+        println!("成功处理 {} 个字幕文件", summary.subtitle_success);
+    }
+    if summary.hardlink_fallbacks > 0 {
+        println!(
+            "{} 个文件与目标不在同一文件系统，已改为复制",
+            summary.hardlink_fallbacks
+        );
+    }
+    if summary.cross_device_moves > 0 {
+        println!(
+            "{} 个文件跨文件系统移动，已校验复制结果并删除源文件",
+            summary.cross_device_moves
+        );
+    }
+
+    record_journal(&plan.root, &operations, &summary.created_dirs);
+
+    Ok(())
+}
@@ -1,2 +1,392 @@
+pub(crate) mod apply;
 pub(crate) mod nfo;
+pub(crate) mod parse;
+pub(crate) mod plan;
 pub(crate) mod rename;
+pub(crate) mod undo;
+pub(crate) mod verify;
+
+use crate::error::FailureKind;
+use crate::operations::RenameOperation;
+use crate::operations::journal::JournalStore;
+use crate::output::{self, status};
+use crate::parser::{ParsedFile, season_directory};
+use crate::tmdb::{
+    EpisodeGroupDetails, EpisodeGroupSelector, EpisodeGroupType, RankedCandidate, SeasonDetails,
+    TmdbClient, TvDetails, is_confident, rank_candidates, select_episode_group,
+};
+use anyhow::{Context, Result, bail};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use tokio::task::JoinSet;
+
+/// 需要确认时最多列出的 TMDB 候选数量
+const MAX_PROMPT_CANDIDATES: usize = 5;
+
+pub(crate) type ParsedEntry = (PathBuf, ParsedFile);
+
+/// 用于分组的番剧名：忽略大小写、空白与标点
+fn normalize_show_name(name: &str) -> String {
+    name.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 番剧所在目录，`Season N` 之类的季度目录归到上一级
+pub(crate) fn show_directory(dir: &Path) -> PathBuf {
+    let is_season_dir = dir
+        .file_name()
+        .is_some_and(|name| season_directory(&name.to_string_lossy()).is_some());
+
+    match dir.parent() {
+        Some(parent) if is_season_dir => parent.to_path_buf(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// 番剧的分组键：规范化后的番剧名，`by_directory` 时还包括番剧所在目录
+pub(crate) fn show_key(entry: &ParsedEntry, by_directory: bool) -> (Option<PathBuf>, String) {
+    let directory = by_directory
+        .then(|| entry.0.parent().map(show_directory))
+        .flatten();
+    (directory, normalize_show_name(&entry.1.anime_name))
+}
+
+/// 按番剧名（递归扫描时还按所在目录）分组，保持文件首次出现的顺序
+pub(crate) fn group_by_show(
+    parsed_files: Vec<ParsedEntry>,
+    by_directory: bool,
+) -> Vec<Vec<ParsedEntry>> {
+    let mut groups: Vec<Vec<ParsedEntry>> = Vec::new();
+    let mut index: HashMap<(Option<PathBuf>, String), usize> = HashMap::new();
+
+    for entry in parsed_files {
+        let key = show_key(&entry, by_directory);
+        match index.get(&key) {
+            Some(&position) => groups[position].push(entry),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(vec![entry]);
+            }
+        }
+    }
+
+    groups
+}
+
+pub(crate) async fn fetch_season_details_map(
+    client: &TmdbClient,
+    tv_id: u32,
+    seasons: &BTreeSet<u32>,
+    language: &str,
+) -> Result<HashMap<u32, SeasonDetails>> {
+    let language = language.to_string();
+    let mut season_details = HashMap::new();
+    let mut failed_seasons = Vec::new();
+    let mut tasks = JoinSet::new();
+
+    for season in seasons {
+        let client = client.clone();
+        let language = language.clone();
+        let season_number = *season;
+        tasks.spawn(async move {
+            (
+                season_number,
+                client
+                    .get_season_details(tv_id, season_number, &language)
+                    .await,
+            )
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        let (season, details_result) = result.context("季度详情任务执行失败")?;
+        match details_result {
+            Ok(details) => {
+                season_details.insert(details.season_number, details);
+            }
+            Err(error) => {
+                status!("跳过第 {season} 季元数据: {error}");
+                failed_seasons.push(season);
+            }
+        }
+    }
+
+    if season_details.is_empty() {
+        if failed_seasons.is_empty() {
+            bail!("未获取到任何季度详情");
+        }
+        bail!(
+            "所有请求季度的详情都获取失败: {}",
+            failed_seasons
+                .iter()
+                .map(|season| season.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    Ok(season_details)
+}
+
+/// 获取 `--episode-group` 指定的剧集组；按类型选择时找不到会列出该番剧已有的剧集组
+pub(crate) async fn resolve_episode_group(
+    client: &TmdbClient,
+    tv_id: u32,
+    selector: &EpisodeGroupSelector,
+    language: &str,
+) -> Result<EpisodeGroupDetails> {
+    let group_id = match selector {
+        EpisodeGroupSelector::Id(id) => id.clone(),
+        EpisodeGroupSelector::Type(group_type) => {
+            let groups = client
+                .get_episode_groups(tv_id, language)
+                .await
+                .context("获取剧集组列表失败")?;
+            let Some(group) = select_episode_group(&groups, *group_type) else {
+                let available = groups
+                    .iter()
+                    .map(|group| {
+                        let group_type = EpisodeGroupType::from_code(group.group_type)
+                            .map_or("unknown", EpisodeGroupType::keyword);
+                        format!("{} [{group_type}] {}", group.id, group.name)
+                    })
+                    .collect::<Vec<_>>();
+                if available.is_empty() {
+                    bail!("TMDB ID {tv_id} 没有任何剧集组");
+                }
+                bail!(
+                    "TMDB ID {tv_id} 没有 {selector} 类型的剧集组，可用 --episode-group <ID> 指定: {}",
+                    available.join("；")
+                );
+            };
+            group.id.clone()
+        }
+    };
+
+    let group = client
+        .get_episode_group(&group_id, language)
+        .await
+        .with_context(|| format!("获取剧集组失败: {group_id}"))?;
+    status!(
+        "使用剧集组: {} ({}，共 {} 组)",
+        group.name,
+        group.id,
+        group.groups.len()
+    );
+
+    Ok(group)
+}
+
+/// 搜索 TMDB 并按标题相似度与年份选择番剧。最佳候选足够可信时直接采用，
+/// 否则列出候选供用户选择；`assume_yes` 时不询问，直接按未匹配处理。
+/// 没有任何搜索结果时返回 None
+pub(crate) async fn select_tmdb_show(
+    client: &TmdbClient,
+    anime_name: &str,
+    year: Option<u32>,
+    language: &str,
+    assume_yes: bool,
+) -> Result<Option<TvDetails>> {
+    let results = client
+        .search_tv(anime_name, language)
+        .await
+        .context("搜索 TMDB 失败")?;
+    let ranked = rank_candidates(&results, anime_name, year);
+    let Some(best) = ranked.first() else {
+        return Ok(None);
+    };
+
+    if is_confident(&ranked) {
+        status!(
+            "找到匹配: {} ({})",
+            best.show.name,
+            best.show.first_air_date.as_deref().unwrap_or("未知")
+        );
+        let details = client
+            .get_tv_details(best.show.id, language)
+            .await
+            .context("获取详情失败")?;
+        return Ok(Some(details));
+    }
+
+    if assume_yes {
+        return Err(anyhow::Error::new(FailureKind::NoMatch).context(format!(
+            "TMDB 搜索结果匹配度不足（最接近: {}），请使用 --tmdb-id 指定",
+            best.show.name
+        )));
+    }
+
+    let candidates = &ranked[..ranked.len().min(MAX_PROMPT_CANDIDATES)];
+    let mut details = fetch_candidate_details(client, candidates, language).await?;
+
+    status!("\nTMDB 找到多个可能的番剧，请选择:");
+    for (index, (candidate, details)) in candidates.iter().zip(&details).enumerate() {
+        let show = candidate.show;
+        let original_name = if show.original_name != show.name {
+            format!(" / {}", show.original_name)
+        } else {
+            String::new()
+        };
+        let seasons = details
+            .as_ref()
+            .map(|details| format!("{} 季", details.number_of_seasons))
+            .unwrap_or_else(|| "季数未知".to_string());
+        status!(
+            "  [{}] {}{original_name}  首播: {}  {seasons}",
+            index + 1,
+            show.name,
+            show.first_air_date.as_deref().unwrap_or("未知")
+        );
+    }
+
+    let choice = loop {
+        let input = output::prompt(&format!(
+            "\n请输入数字选择番剧 [1-{}]，直接回车选择第一个，输入 0 放弃: ",
+            candidates.len()
+        ))?;
+        match parse_candidate_choice(&input, candidates.len()) {
+            CandidateChoice::Pick(choice) => break choice,
+            CandidateChoice::Abort => {
+                return Err(
+                    anyhow::Error::new(FailureKind::NoMatch).context("已放弃选择 TMDB 番剧")
+                );
+            }
+            CandidateChoice::Invalid => status!("无效选择: {input}"),
+        }
+    };
+
+    match details[choice].take() {
+        Some(details) => Ok(Some(details)),
+        None => client
+            .get_tv_details(candidates[choice].show.id, language)
+            .await
+            .context("获取详情失败")
+            .map(Some),
+    }
+}
+
+/// 并发获取候选番剧详情（用于显示季数），单个失败时对应位置为 None
+async fn fetch_candidate_details(
+    client: &TmdbClient,
+    candidates: &[RankedCandidate<'_>],
+    language: &str,
+) -> Result<Vec<Option<TvDetails>>> {
+    let mut details = Vec::new();
+    details.resize_with(candidates.len(), || None);
+    let mut tasks = JoinSet::new();
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        let client = client.clone();
+        let language = language.to_string();
+        let tv_id = candidate.show.id;
+        tasks.spawn(async move { (index, client.get_tv_details(tv_id, &language).await) });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        let (index, details_result) = result.context("番剧详情任务执行失败")?;
+        details[index] = details_result.ok();
+    }
+
+    Ok(details)
+}
+
+/// 用户对候选番剧的选择
+#[derive(Debug, PartialEq)]
+enum CandidateChoice {
+    Pick(usize),
+    Abort,
+    Invalid,
+}
+
+/// 解析候选选择：空输入选择第一个，0 表示放弃，超出范围或非数字为无效输入（需重新输入）
+fn parse_candidate_choice(input: &str, count: usize) -> CandidateChoice {
+    if input.is_empty() {
+        return CandidateChoice::Pick(0);
+    }
+
+    match input.parse::<usize>() {
+        Ok(0) => CandidateChoice::Abort,
+        Ok(choice) if choice <= count => CandidateChoice::Pick(choice - 1),
+        _ => CandidateChoice::Invalid,
+    }
+}
+
+/// 记录已执行的重命名批次，写入失败只输出警告
+pub(crate) fn record_journal(
+    root: &Path,
+    operations: &[RenameOperation],
+    created_dirs: &[PathBuf],
+) {
+    let result =
+        JournalStore::open_default().and_then(|store| store.record(root, operations, created_dirs));
+
+    match result {
+        Ok(entry) => status!(
+            "已记录本次重命名 (ID: {})，可使用 `anime_renamer undo --id {}` 撤销",
+            entry.id,
+            entry.id
+        ),
+        Err(error) => status!("警告: 写入重命名日志失败，本次操作无法撤销: {error:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::FileParser;
+
+    fn parse(path: &str) -> ParsedEntry {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        (path, FileParser::new().parse(&name).unwrap())
+    }
+
+    #[test]
+    fn test_group_by_show_separates_shows_in_one_directory() {
+        let files = vec![
+            parse("/downloads/[Group] Frieren - 01 [1080p].mkv"),
+            parse("/downloads/[Group] Oshi no Ko - 01 [1080p].mkv"),
+            parse("/downloads/[Other] frieren - 02 [720p].mkv"),
+        ];
+
+        let groups = group_by_show(files, false);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+        assert_eq!(groups[0][1].1.episode_number, 2);
+        assert_eq!(groups[1].len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_show_splits_same_name_by_directory_when_recursive() {
+        let files = vec![
+            parse("/anime/A/[Group] Show - 01.mkv"),
+            parse("/anime/B/[Group] Show - 01.mkv"),
+        ];
+
+        assert_eq!(group_by_show(files.clone(), true).len(), 2);
+        assert_eq!(group_by_show(files, false).len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_show_keeps_season_directories_together() {
+        let files = vec![
+            parse("/anime/Show/Season 1/[Group] Show - 01.mkv"),
+            parse("/anime/Show/Season 2/[Group] Show - 13.mkv"),
+            parse("/anime/Show/第三季/[Group] Show - 25.mkv"),
+        ];
+
+        assert_eq!(group_by_show(files, true).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_candidate_choice() {
+        assert_eq!(parse_candidate_choice("", 3), CandidateChoice::Pick(0));
+        assert_eq!(parse_candidate_choice("2", 3), CandidateChoice::Pick(1));
+        assert_eq!(parse_candidate_choice("0", 3), CandidateChoice::Abort);
+        assert_eq!(parse_candidate_choice("9", 3), CandidateChoice::Invalid);
+        assert_eq!(parse_candidate_choice("abc", 3), CandidateChoice::Invalid);
+    }
+}
@@ -1,20 +1,26 @@
+use super::{
+    ParsedEntry, fetch_season_details_map, group_by_show, resolve_episode_group, select_tmdb_show,
+    show_directory,
+};
+use crate::cache::{CacheMode, MetadataCache};
 use crate::cli::NfoArgs;
+use crate::config::Config;
+use crate::error::FailureKind;
 use crate::nfo::{
     ActorNfo, EpisodeNfo, NfoWriter, PersonNfo, Rating, SeasonNfo, TvShowNfo, UniqueId,
     WriteAction, episode_nfo_path, episode_thumb_image_path, season_nfo_path,
     season_primary_image_path, tvshow_backdrop_image_path, tvshow_primary_image_path,
 };
-use crate::parser::{EpisodeType, FileParser, ParsedFile, extract_tmdb_id};
+use crate::parser::{EpisodeType, FileParser, ParsedFile, folder_tmdb_id};
 use crate::scanner::FileScanner;
 use crate::tmdb::{
     self, Episode, EpisodeCredits, EpisodeExternalIds, SeasonDetails, TmdbClient, TvDetails,
 };
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result};
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tokio::task::JoinSet;
 
-type ParsedEntry = (PathBuf, ParsedFile);
 const MAX_CONCURRENT_EPISODE_EXPORTS: usize = tmdb::MAX_CONCURRENT_REQUESTS;
 
 fn file_name_lossy(path: &Path) -> Option<String> {
@@ -31,7 +37,7 @@ fn collect_nfo_candidates(files: &[PathBuf], parser: &FileParser) -> Vec<ParsedE
             continue;
         };
 
-        match parser.parse(&filename) {
+        match parser.parse_path(file) {
             Some(parsed) if !parsed.is_already_formatted => {
                 println!("跳过非规范命名文件: {filename}");
             }
@@ -52,11 +58,11 @@ fn collect_nfo_candidates(files: &[PathBuf], parser: &FileParser) -> Vec<ParsedE
 async fn resolve_tmdb_details(
     client: &TmdbClient,
     explicit_tmdb_id: Option<u32>,
-    path: &str,
     anime_name: &str,
+    year: Option<u32>,
     language: &str,
 ) -> Result<(u32, TvDetails)> {
-    if let Some(id) = explicit_tmdb_id.or_else(|| extract_tmdb_id(path)) {
+    if let Some(id) = explicit_tmdb_id {
         let details = client
             .get_tv_details(id, language)
             .await
@@ -64,21 +70,11 @@ async fn resolve_tmdb_details(
         return Ok((id, details));
     }
 
-    let results = client
-        .search_tv(anime_name, language)
-        .await
-        .context("搜索 TMDB 失败")?;
-
-    let Some(first) = results.first() else {
-        bail!("TMDB 未找到匹配的番剧");
+    let Some(details) = select_tmdb_show(client, anime_name, year, language, false).await? else {
+        return Err(anyhow::Error::new(FailureKind::NoMatch).context("TMDB 未找到匹配的番剧"));
     };
 
-    let details = client
-        .get_tv_details(first.id, language)
-        .await
-        .context("获取详情失败")?;
-
-    Ok((first.id, details))
+    Ok((details.id, details))
 }
 
 fn required_seasons_for_nfo(parsed_files: &[ParsedEntry]) -> BTreeSet<u32> {
@@ -122,61 +118,6 @@ fn season_image_targets(parsed_files: &[ParsedEntry]) -> HashMap<u32, Vec<PathBu
     targets
 }
 
-async fn fetch_season_details_map(
-    client: &TmdbClient,
-    tv_id: u32,
-    seasons: &BTreeSet<u32>,
-    language: &str,
-) -> Result<HashMap<u32, SeasonDetails>> {
-    let language = language.to_string();
-    let mut season_details = HashMap::new();
-    let mut failed_seasons = Vec::new();
-    let mut tasks = JoinSet::new();
-
-    for season in seasons {
-        let client = client.clone();
-        let language = language.clone();
-        let season_number = *season;
-        tasks.spawn(async move {
-            (
-                season_number,
-                client
-                    .get_season_details(tv_id, season_number, &language)
-                    .await,
-            )
-        });
-    }
-
-    while let Some(result) = tasks.join_next().await {
-        let (season, details_result) = result.context("季度详情任务执行失败")?;
-        match details_result {
-            Ok(details) => {
-                season_details.insert(details.season_number, details);
-            }
-            Err(error) => {
-                println!("跳过第 {season} 季元数据: {error}");
-                failed_seasons.push(season);
-            }
-        }
-    }
-
-    if season_details.is_empty() {
-        if failed_seasons.is_empty() {
-            bail!("未获取到任何季度详情");
-        }
-        bail!(
-            "所有请求季度的详情都获取失败: {}",
-            failed_seasons
-                .iter()
-                .map(|season| season.to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
-    }
-
-    Ok(season_details)
-}
-
 fn build_episode_lookup(
     season_details_map: &HashMap<u32, SeasonDetails>,
 ) -> HashMap<(u32, u32), Episode> {
@@ -186,7 +127,10 @@ fn build_episode_lookup(
         for episode in &details.episodes {
             episodes.insert(
                 (details.season_number, episode.episode_number),
-                episode.clone(),
+                Episode {
+                    season_number: details.season_number,
+                    ..episode.clone()
+                },
             );
         }
     }
@@ -194,6 +138,25 @@ fn build_episode_lookup(
     episodes
 }
 
+/// 文件包含的各集（文件中的集数，TMDB 元数据），多集文件按集数顺序，TMDB 缺少的集数会被忽略
+fn lookup_episodes(
+    episode_lookup: &HashMap<(u32, u32), Episode>,
+    parsed: &ParsedFile,
+) -> Vec<(u32, Episode)> {
+    let season = parsed
+        .season_number
+        .expect("collect_nfo_candidates ensures season");
+
+    parsed
+        .episode_numbers()
+        .filter_map(|episode| {
+            episode_lookup
+                .get(&(season, episode))
+                .map(|metadata| (episode, metadata.clone()))
+        })
+        .collect()
+}
+
 fn record_write_action(action: WriteAction, written: &mut usize, skipped_existing: &mut usize) {
     match action {
         WriteAction::WouldWrite | WriteAction::Written => *written += 1,
@@ -205,6 +168,11 @@ fn should_write_path(path: &Path, force: bool) -> bool {
     force || !path.exists()
 }
 
+/// 离线模式下已下载的图片视为缓存命中，即使指定了 `--force` 也保留
+fn should_download_image(client: &TmdbClient, path: &Path, force: bool) -> bool {
+    should_write_path(path, force && !client.is_offline())
+}
+
 fn print_skipped_existing(path: &Path, written: &mut usize, skipped_existing: &mut usize) {
     print_nfo_outcome(path, WriteAction::SkippedExisting);
     record_write_action(WriteAction::SkippedExisting, written, skipped_existing);
@@ -493,7 +461,8 @@ struct EpisodeExportJob {
     show_title: String,
     video_path: PathBuf,
     parsed: ParsedFile,
-    episode: Option<Episode>,
+    /// 文件包含的各集（文件中的集数，TMDB 元数据），多集文件会有多项
+    episodes: Vec<(u32, Episode)>,
 }
 
 #[derive(Clone)]
@@ -517,7 +486,7 @@ impl EpisodeExportJob {
             show_title,
             video_path,
             parsed,
-            episode,
+            episodes,
         } = self;
 
         let mut stats = EpisodeExportStats::default();
@@ -525,7 +494,7 @@ impl EpisodeExportJob {
             .season_number
             .expect("collect_nfo_candidates ensures season");
 
-        let Some(episode) = episode else {
+        let Some((_, first_episode)) = episodes.first() else {
             println!(
                 "跳过缺少 TMDB 剧集元数据的文件: {}",
                 video_path.file_name().unwrap().to_string_lossy()
@@ -536,48 +505,60 @@ impl EpisodeExportJob {
 
         let episode_nfo_target = episode_nfo_path(&video_path);
         if should_write_path(&episode_nfo_target, force) {
-            // Keep one TMDB request in flight per episode job. Running both calls at once causes
-            // the shared request semaphore to complete work in small bursts (for example 4 episodes
-            // at a time with an 8-request limit), which makes NFO output look "stuck" between batches.
-            let external_ids = match client
-                .get_episode_external_ids(show_id, season, parsed.episode_number)
-                .await
-            {
-                Ok(value) => Some(value),
-                Err(error) => {
-                    println!(
-                        "跳过单集外部 ID 增强: {} ({error})",
-                        video_path.file_name().unwrap().to_string_lossy()
-                    );
-                    stats.metadata_enrichment_failures += 1;
-                    None
-                }
-            };
-
-            let credits = match client
-                .get_episode_credits(show_id, season, parsed.episode_number, &language)
-                .await
-            {
-                Ok(value) => Some(value),
-                Err(error) => {
-                    println!(
-                        "跳过单集演职员增强: {} ({error})",
-                        video_path.file_name().unwrap().to_string_lossy()
-                    );
-                    stats.metadata_enrichment_failures += 1;
-                    None
-                }
-            };
-
-            let episode_nfo = build_episode_nfo(
-                &show_title,
-                season,
-                parsed.episode_number,
-                &episode,
-                external_ids.as_ref(),
-                credits.as_ref(),
-            );
-            let outcome = writer.write_episode(&video_path, &episode_nfo)?;
+            let mut episode_nfos = Vec::with_capacity(episodes.len());
+            for (episode_number, episode) in &episodes {
+                // Keep one TMDB request in flight per episode job. Running both calls at once causes
+                // the shared request semaphore to complete work in small bursts (for example 4 episodes
+                // at a time with an 8-request limit), which makes NFO output look "stuck" between batches.
+                let external_ids = match client
+                    .get_episode_external_ids(
+                        show_id,
+                        episode.season_number,
+                        episode.episode_number,
+                    )
+                    .await
+                {
+                    Ok(value) => Some(value),
+                    Err(error) => {
+                        println!(
+                            "跳过单集外部 ID 增强: {} ({error})",
+                            video_path.file_name().unwrap().to_string_lossy()
+                        );
+                        stats.metadata_enrichment_failures += 1;
+                        None
+                    }
+                };
+
+                let credits = match client
+                    .get_episode_credits(
+                        show_id,
+                        episode.season_number,
+                        episode.episode_number,
+                        &language,
+                    )
+                    .await
+                {
+                    Ok(value) => Some(value),
+                    Err(error) => {
+                        println!(
+                            "跳过单集演职员增强: {} ({error})",
+                            video_path.file_name().unwrap().to_string_lossy()
+                        );
+                        stats.metadata_enrichment_failures += 1;
+                        None
+                    }
+                };
+
+                episode_nfos.push(build_episode_nfo(
+                    &show_title,
+                    season,
+                    *episode_number,
+                    episode,
+                    external_ids.as_ref(),
+                    credits.as_ref(),
+                ));
+            }
+            let outcome = writer.write_episode(&video_path, &episode_nfos)?;
             print_nfo_outcome(&outcome.path, outcome.action);
             stats.record_nfo_action(outcome.action);
         } else {
@@ -585,10 +566,11 @@ impl EpisodeExportJob {
             stats.record_nfo_action(WriteAction::SkippedExisting);
         }
 
-        if let Some(still_path) = episode.still_path.as_deref() {
+        // 多集文件使用第一集的缩略图
+        if let Some(still_path) = first_episode.still_path.as_deref() {
             let extension = tmdb::image_extension(still_path);
             let target_path = episode_thumb_image_path(&video_path, extension);
-            if should_write_path(&target_path, force) {
+            if should_download_image(&client, &target_path, force) {
                 match client.download_image(still_path).await {
                     Ok(bytes) => {
                         let outcome =
@@ -621,7 +603,7 @@ fn spawn_episode_export_job(
     shared: &EpisodeExportShared,
     video_path: &Path,
     parsed: &ParsedFile,
-    episode: Option<Episode>,
+    episodes: Vec<(u32, Episode)>,
 ) {
     let shared = shared.clone();
     let video_path = video_path.to_path_buf();
@@ -637,97 +619,45 @@ fn spawn_episode_export_job(
             show_title: shared.show_title,
             video_path,
             parsed,
-            episode,
+            episodes,
         }
         .run()
         .await
     });
 }
 
-pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
-    println!("扫描目录: {}", args.path);
-
-    let scanner = FileScanner::new(args.recursive);
-    let files = scanner.scan(&args.path);
-
-    if files.is_empty() {
-        println!("未找到视频文件");
-        return Ok(());
-    }
-
-    println!("找到 {} 个视频文件\n", files.len());
-
-    let parser = FileParser::new();
-    let parsed_files = collect_nfo_candidates(&files, &parser);
-
-    if parsed_files.is_empty() {
-        println!("没有可用于导出 NFO 的规范化文件");
-        return Ok(());
-    }
-
-    let anime_name = parsed_files[0].1.anime_name.clone();
-    println!("检测到番剧: {anime_name}");
-
-    let client = TmdbClient::new();
-    let (show_id, details) = resolve_tmdb_details(
-        &client,
-        args.tmdb_id,
-        &args.path,
-        &anime_name,
-        &args.language,
-    )
-    .await?;
-
-    println!("找到匹配: {} (TMDB ID: {})", details.name, show_id);
-
-    let seasons = required_seasons_for_nfo(&parsed_files);
-    let season_details_map =
-        fetch_season_details_map(&client, show_id, &seasons, &args.language).await?;
-    let episode_lookup = build_episode_lookup(&season_details_map);
-    let season_targets = season_image_targets(&parsed_files);
-    let writer = NfoWriter::new(args.dry_run, args.force);
-    let shared = EpisodeExportShared {
-        client: client.clone(),
-        writer,
-        force: args.force,
-        language: args.language.clone(),
-        show_id,
-        show_title: details.name.clone(),
-    };
-
-    let mut nfo_written = 0;
-    let mut nfo_skipped_existing = 0;
-    let root = Path::new(&args.path);
+/// 写入番剧根目录的 tvshow.nfo、海报与背景图
+async fn export_show_metadata(
+    args: &NfoArgs,
+    client: &TmdbClient,
+    shared: &EpisodeExportShared,
+    details: &TvDetails,
+    root: &Path,
+    stats: &mut EpisodeExportStats,
+) -> Result<()> {
     let tvshow_nfo_path = root.join("tvshow.nfo");
     if should_write_path(&tvshow_nfo_path, args.force) {
         let tvshow_outcome = shared
             .writer
-            .write_tvshow(root, &build_tvshow_nfo(&details))?;
+            .write_tvshow(root, &build_tvshow_nfo(details))?;
         print_nfo_outcome(&tvshow_outcome.path, tvshow_outcome.action);
         record_write_action(
             tvshow_outcome.action,
-            &mut nfo_written,
-            &mut nfo_skipped_existing,
+            &mut stats.nfo_written,
+            &mut stats.nfo_skipped_existing,
         );
     } else {
         print_skipped_existing(
             &tvshow_nfo_path,
-            &mut nfo_written,
-            &mut nfo_skipped_existing,
+            &mut stats.nfo_written,
+            &mut stats.nfo_skipped_existing,
         );
     }
 
-    let mut image_written = 0;
-    let mut image_skipped_existing = 0;
-    let mut missing_images = 0;
-    let mut image_failures = 0;
-    let mut missing_metadata = 0;
-    let mut metadata_enrichment_failures = 0;
-
     if let Some(poster_path) = details.poster_path.as_deref() {
         let extension = tmdb::image_extension(poster_path);
         let target_path = tvshow_primary_image_path(root, extension);
-        if should_write_path(&target_path, args.force) {
+        if should_download_image(client, &target_path, args.force) {
             match client.download_image(poster_path).await {
                 Ok(bytes) => {
                     let outcome = shared
@@ -736,30 +666,30 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
                     print_nfo_outcome(&outcome.path, outcome.action);
                     record_write_action(
                         outcome.action,
-                        &mut image_written,
-                        &mut image_skipped_existing,
+                        &mut stats.image_written,
+                        &mut stats.image_skipped_existing,
                     );
                 }
                 Err(error) => {
                     println!("跳过剧集海报下载失败: {error}");
-                    image_failures += 1;
+                    stats.image_failures += 1;
                 }
             }
         } else {
             print_skipped_existing(
                 &target_path,
-                &mut image_written,
-                &mut image_skipped_existing,
+                &mut stats.image_written,
+                &mut stats.image_skipped_existing,
             );
         }
     } else {
-        missing_images += 1;
+        stats.missing_images += 1;
     }
 
     if let Some(backdrop_path) = details.backdrop_path.as_deref() {
         let extension = tmdb::image_extension(backdrop_path);
         let target_path = tvshow_backdrop_image_path(root, extension);
-        if should_write_path(&target_path, args.force) {
+        if should_download_image(client, &target_path, args.force) {
             match client.download_image(backdrop_path).await {
                 Ok(bytes) => {
                     let outcome = shared
@@ -768,24 +698,87 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
                     print_nfo_outcome(&outcome.path, outcome.action);
                     record_write_action(
                         outcome.action,
-                        &mut image_written,
-                        &mut image_skipped_existing,
+                        &mut stats.image_written,
+                        &mut stats.image_skipped_existing,
                     );
                 }
                 Err(error) => {
                     println!("跳过剧集背景图下载失败: {error}");
-                    image_failures += 1;
+                    stats.image_failures += 1;
                 }
             }
         } else {
             print_skipped_existing(
                 &target_path,
-                &mut image_written,
-                &mut image_skipped_existing,
+                &mut stats.image_written,
+                &mut stats.image_skipped_existing,
             );
         }
     } else {
-        missing_images += 1;
+        stats.missing_images += 1;
+    }
+
+    Ok(())
+}
+
+/// 为一部番剧匹配 TMDB 并导出元数据；`show_root` 为 None 时跳过番剧级文件
+async fn export_show(
+    args: &NfoArgs,
+    client: &TmdbClient,
+    parsed_files: &[ParsedEntry],
+    show_root: Option<&Path>,
+    stats: &mut EpisodeExportStats,
+) -> Result<()> {
+    let anime_name = parsed_files[0].1.anime_name.clone();
+    println!("检测到番剧: {anime_name}");
+
+    // 文件所在路径包含扫描目录，因此也会识别扫描目录上的 [tmdbid=...]
+    let path_tmdb_id = parsed_files[0].0.parent().and_then(folder_tmdb_id);
+    let (show_id, details) = resolve_tmdb_details(
+        client,
+        args.tmdb_id.or(path_tmdb_id),
+        &anime_name,
+        parsed_files.iter().find_map(|(_, parsed)| parsed.year),
+        &args.language,
+    )
+    .await?;
+
+    println!("找到匹配: {} (TMDB ID: {})", details.name, show_id);
+
+    let seasons = required_seasons_for_nfo(parsed_files);
+    // 剧集组的季与 TMDB 的季不对应，此时不写入 season.nfo 与季海报
+    let (season_details_map, episode_lookup) = match &args.episode_group {
+        Some(selector) => {
+            let group = resolve_episode_group(client, show_id, selector, &args.language).await?;
+            println!("使用剧集组时跳过 season.nfo 与季海报");
+            (HashMap::new(), group.episode_map())
+        }
+        None => {
+            let season_details_map =
+                fetch_season_details_map(client, show_id, &seasons, &args.language).await?;
+            let episode_lookup = build_episode_lookup(&season_details_map);
+            (season_details_map, episode_lookup)
+        }
+    };
+    let season_targets = if args.episode_group.is_some() {
+        HashMap::new()
+    } else {
+        season_image_targets(parsed_files)
+    };
+    let writer = NfoWriter::new(args.dry_run, args.force);
+    let shared = EpisodeExportShared {
+        client: client.clone(),
+        writer,
+        force: args.force,
+        language: args.language.clone(),
+        show_id,
+        show_title: details.name.clone(),
+    };
+
+    if let Some(root) = show_root {
+        export_show_metadata(args, client, &shared, &details, root, stats).await?;
+    } else {
+        println!("多部番剧位于同一目录，跳过 tvshow.nfo 与剧集图片");
     }
 
     for season in &seasons {
@@ -803,14 +796,14 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
                     print_nfo_outcome(&outcome.path, outcome.action);
                     record_write_action(
                         outcome.action,
-                        &mut nfo_written,
-                        &mut nfo_skipped_existing,
+                        &mut stats.nfo_written,
+                        &mut stats.nfo_skipped_existing,
                     );
                 } else {
                     print_skipped_existing(
                         &nfo_target,
-                        &mut nfo_written,
-                        &mut nfo_skipped_existing,
+                        &mut stats.nfo_written,
+                        &mut stats.nfo_skipped_existing,
                     );
                 }
             }
@@ -818,7 +811,7 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
 
         let Some(poster_path) = resolve_season_poster_path(*season, &season_details_map, &details)
         else {
-            missing_images += target_dirs.len();
+            stats.missing_images += target_dirs.len();
             continue;
         };
 
@@ -826,13 +819,13 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
         let mut pending_dirs = Vec::new();
         for target_dir in target_dirs {
             let target_path = season_primary_image_path(target_dir, extension);
-            if should_write_path(&target_path, args.force) {
+            if should_download_image(client, &target_path, args.force) {
                 pending_dirs.push(target_dir);
             } else {
                 print_skipped_existing(
                     &target_path,
-                    &mut image_written,
-                    &mut image_skipped_existing,
+                    &mut stats.image_written,
+                    &mut stats.image_skipped_existing,
                 );
             }
         }
@@ -850,14 +843,14 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
                     print_nfo_outcome(&outcome.path, outcome.action);
                     record_write_action(
                         outcome.action,
-                        &mut image_written,
-                        &mut image_skipped_existing,
+                        &mut stats.image_written,
+                        &mut stats.image_skipped_existing,
                     );
                 }
             }
             Err(error) => {
                 println!("跳过第 {season} 季海报下载失败: {error}");
-                image_failures += pending_dirs.len();
+                stats.image_failures += pending_dirs.len();
             }
         }
     }
@@ -869,57 +862,163 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
         let Some((video_path, parsed)) = parsed_iter.next() else {
             break;
         };
-        let season = parsed
-            .season_number
-            .expect("collect_nfo_candidates ensures season");
-        let episode = episode_lookup
-            .get(&(season, parsed.episode_number))
-            .cloned();
-        spawn_episode_export_job(&mut episode_tasks, &shared, video_path, parsed, episode);
+        let episodes = lookup_episodes(&episode_lookup, parsed);
+        spawn_episode_export_job(&mut episode_tasks, &shared, video_path, parsed, episodes);
     }
 
     while let Some(result) = episode_tasks.join_next().await {
-        let stats = result.context("单集导出任务执行失败")??;
-        nfo_written += stats.nfo_written;
-        nfo_skipped_existing += stats.nfo_skipped_existing;
-        image_written += stats.image_written;
-        image_skipped_existing += stats.image_skipped_existing;
-        missing_images += stats.missing_images;
-        image_failures += stats.image_failures;
-        missing_metadata += stats.missing_metadata;
-        metadata_enrichment_failures += stats.metadata_enrichment_failures;
+        let episode_stats = result.context("单集导出任务执行失败")??;
+        stats.nfo_written += episode_stats.nfo_written;
+        stats.nfo_skipped_existing += episode_stats.nfo_skipped_existing;
+        stats.image_written += episode_stats.image_written;
+        stats.image_skipped_existing += episode_stats.image_skipped_existing;
+        stats.missing_images += episode_stats.missing_images;
+        stats.image_failures += episode_stats.image_failures;
+        stats.missing_metadata += episode_stats.missing_metadata;
+        stats.metadata_enrichment_failures += episode_stats.metadata_enrichment_failures;
 
         if let Some((video_path, parsed)) = parsed_iter.next() {
-            let season = parsed
-                .season_number
-                .expect("collect_nfo_candidates ensures season");
-            let episode = episode_lookup
-                .get(&(season, parsed.episode_number))
-                .cloned();
-            spawn_episode_export_job(&mut episode_tasks, &shared, video_path, parsed, episode);
+            let episodes = lookup_episodes(&episode_lookup, parsed);
+            spawn_episode_export_job(&mut episode_tasks, &shared, video_path, parsed, episodes);
+        }
+    }
+
+    Ok(())
+}
+
+/// 各分组的番剧根目录（写入 tvshow.nfo 与剧集图片的位置）；
+/// 目录内还包含其他番剧时返回 None
+fn show_roots(root: &Path, groups: &[Vec<ParsedEntry>]) -> Vec<Option<PathBuf>> {
+    if groups.len() == 1 {
+        return vec![Some(root.to_path_buf())];
+    }
+
+    let candidates: Vec<PathBuf> = groups
+        .iter()
+        .map(|group| {
+            let dirs = group
+                .iter()
+                .filter_map(|(path, _)| path.parent().map(show_directory));
+            let common = dirs
+                .reduce(|common, dir| common_ancestor(&common, &dir))
+                .unwrap_or_else(|| root.to_path_buf());
+            if common.starts_with(root) {
+                common
+            } else {
+                root.to_path_buf()
+            }
+        })
+        .collect();
+
+    candidates
+        .iter()
+        .map(|candidate| {
+            let shared = candidates
+                .iter()
+                .filter(|other| other.starts_with(candidate))
+                .count()
+                > 1;
+            (!shared).then(|| candidate.clone())
+        })
+        .collect()
+}
+
+fn common_ancestor(left: &Path, right: &Path) -> PathBuf {
+    left.components()
+        .zip(right.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
+    println!("扫描目录: {}", args.path);
+
+    let scanner = FileScanner::new(args.recursive);
+    let files = scanner.scan(&args.path);
+
+    if files.is_empty() {
+        println!("未找到视频文件");
+        return Ok(());
+    }
+
+    println!("找到 {} 个视频文件\n", files.len());
+
+    let config = Config::load()?;
+    let parser = FileParser::with_config(&config.parser)?;
+    let parsed_files = collect_nfo_candidates(&files, &parser);
+
+    if parsed_files.is_empty() {
+        println!("没有可用于导出 NFO 的规范化文件");
+        return Ok(());
+    }
+
+    let root = Path::new(&args.path);
+    // 手动指定 TMDB ID 时，所有文件视为同一部番剧
+    let groups = if args.tmdb_id.is_some() {
+        vec![parsed_files]
+    } else {
+        group_by_show(parsed_files, args.recursive)
+    };
+    let show_roots = show_roots(root, &groups);
+    if groups.len() > 1 {
+        println!("检测到 {} 部番剧，将分别导出\n", groups.len());
+    }
+
+    let cache = MetadataCache::open_default(
+        &config.cache,
+        CacheMode::from_flags(args.refresh, args.offline),
+    )?;
+    let client = TmdbClient::new()
+        .with_cache(cache)
+        .with_retry(&config.retry);
+    let mut stats = EpisodeExportStats::default();
+    let mut failed_shows = 0;
+
+    for (group, show_root) in groups.iter().zip(&show_roots) {
+        let result = export_show(args, &client, group, show_root.as_deref(), &mut stats).await;
+        match result {
+            Ok(()) => {}
+            Err(error) if groups.len() > 1 => {
+                println!("跳过番剧 {}: {error:#}", group[0].1.anime_name);
+                failed_shows += 1;
+            }
+            Err(error) => return Err(error),
+        }
+        if groups.len() > 1 {
+            println!();
         }
     }
 
     println!("\nNFO 导出摘要:");
-    println!("  NFO 计划/成功写入: {nfo_written}");
-    if nfo_skipped_existing > 0 {
-        println!("  NFO 已跳过已有文件: {nfo_skipped_existing}");
+    println!("  NFO 计划/成功写入: {}", stats.nfo_written);
+    if stats.nfo_skipped_existing > 0 {
+        println!("  NFO 已跳过已有文件: {}", stats.nfo_skipped_existing);
     }
-    println!("  图片计划/成功写入: {image_written}");
-    if image_skipped_existing > 0 {
-        println!("  图片已跳过已有文件: {image_skipped_existing}");
+    println!("  图片计划/成功写入: {}", stats.image_written);
+    if stats.image_skipped_existing > 0 {
+        println!("  图片已跳过已有文件: {}", stats.image_skipped_existing);
     }
-    if missing_metadata > 0 {
-        println!("  缺少剧集元数据: {missing_metadata}");
+    if stats.missing_metadata > 0 {
+        println!("  缺少剧集元数据: {}", stats.missing_metadata);
     }
-    if missing_images > 0 {
-        println!("  缺少图片源数据: {missing_images}");
+    if stats.missing_images > 0 {
+        println!("  缺少图片源数据: {}", stats.missing_images);
     }
-    if image_failures > 0 {
-        println!("  图片下载失败: {image_failures}");
+    if stats.image_failures > 0 {
+        println!("  图片下载失败: {}", stats.image_failures);
     }
-    if metadata_enrichment_failures > 0 {
-        println!("  单集增强信息获取失败: {metadata_enrichment_failures}");
+    if client.retry_count() > 0 {
+        println!("  TMDB 请求重试: {}", client.retry_count());
+    }
+    if stats.metadata_enrichment_failures > 0 {
+        println!(
+            "  单集增强信息获取失败: {}",
+            stats.metadata_enrichment_failures
+        );
+    }
+    if failed_shows > 0 {
+        println!("  跳过的番剧: {failed_shows}");
     }
     if args.dry_run {
         println!("  当前为预览模式，未实际写入文件");
@@ -931,43 +1030,9 @@ pub(crate) async fn run(args: &NfoArgs) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ReleaseInfo;
+    use crate::test_support::TestDir;
     use std::fs;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    static TEST_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
-
-    struct TestDir {
-        path: PathBuf,
-    }
-
-    impl TestDir {
-        fn new(prefix: &str) -> Self {
-            let unique = format!(
-                "{}_{}_{}_{}",
-                prefix,
-                std::process::id(),
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos(),
-                TEST_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
-            );
-            let path = std::env::temp_dir().join(unique);
-            fs::create_dir_all(&path).unwrap();
-            Self { path }
-        }
-
-        fn path(&self) -> &Path {
-            &self.path
-        }
-    }
-
-    impl Drop for TestDir {
-        fn drop(&mut self) {
-            let _ = fs::remove_dir_all(&self.path);
-        }
-    }
 
     fn make_season(season_number: u32, episode_count: u32) -> tmdb::Season {
         tmdb::Season {
@@ -1022,6 +1087,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_offline_keeps_existing_images_even_with_force() {
+        let test_dir = TestDir::new("nfo_offline_images");
+        let existing = test_dir.path().join("poster.jpg");
+        fs::write(&existing, b"image").unwrap();
+        let missing = test_dir.path().join("fanart.jpg");
+        let online = TmdbClient::new();
+        let offline = TmdbClient::new().with_cache(MetadataCache::new(
+            test_dir.path().join("cache"),
+            &Default::default(),
+            CacheMode::Offline,
+        ));
+
+        assert!(should_download_image(&online, &existing, true));
+        assert!(!should_download_image(&offline, &existing, true));
+        assert!(should_download_image(&offline, &missing, true));
+    }
+
     #[test]
     fn test_collect_nfo_candidates_only_keeps_formatted_files() {
         let parser = FileParser::new();
@@ -1049,6 +1132,53 @@ mod tests {
         assert_eq!(parsed[0].1.episode_number, 120);
     }
 
+    #[test]
+    fn test_lookup_episodes_collects_every_episode_of_multi_episode_file() {
+        let parser = FileParser::new();
+        let files = vec![PathBuf::from("/tmp/Show S01E01-E02.mkv")];
+        let parsed = collect_nfo_candidates(&files, &parser);
+        let mut season = make_season_details(1, None);
+        season.episodes = (1..=3)
+            .map(|episode_number| Episode {
+                id: episode_number,
+                season_number: 0,
+                episode_number,
+                name: format!("Episode {episode_number}"),
+                still_path: None,
+                air_date: None,
+                overview: None,
+                vote_average: 0.0,
+                vote_count: 0,
+            })
+            .collect();
+        let lookup = build_episode_lookup(&HashMap::from([(1, season)]));
+
+        let episodes = lookup_episodes(&lookup, &parsed[0].1);
+
+        assert_eq!(
+            episodes
+                .iter()
+                .map(|(number, episode)| (*number, episode.season_number, episode.episode_number))
+                .collect::<Vec<_>>(),
+            vec![(1, 1, 1), (2, 1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_collect_nfo_candidates_reads_folder_hints() {
+        let parser = FileParser::new();
+        let files = vec![PathBuf::from(
+            "/anime/Frieren (2023) [tmdbid=209867]/Season 1/Frieren S01E03.mkv",
+        )];
+
+        let parsed = collect_nfo_candidates(&files, &parser);
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].1.anime_name, "Frieren");
+        assert_eq!(parsed[0].1.season_number, Some(1));
+        assert_eq!(parsed[0].1.year, Some(2023));
+    }
+
     #[test]
     fn test_collect_nfo_candidates_keeps_special_season_zero() {
         let parser = FileParser::new();
@@ -1119,6 +1249,7 @@ mod tests {
     fn test_build_episode_nfo_uses_tmdb_episode_metadata() {
         let episode = Episode {
             id: 999,
+            season_number: 1,
             episode_number: 3,
             name: "Episode 3".to_string(),
             still_path: Some("/still.jpg".to_string()),
@@ -1170,9 +1301,9 @@ mod tests {
         assert_eq!(nfo.episode, 3);
         assert_eq!(nfo.premiered.as_deref(), Some("2024-01-15"));
         assert_eq!(nfo.unique_ids[0].id_type, "tmdb");
-        assert_eq!(nfo.unique_ids[0].is_default, false);
+        assert!(!nfo.unique_ids[0].is_default);
         assert_eq!(nfo.unique_ids[1].value, "tt1234567");
-        assert_eq!(nfo.unique_ids[1].is_default, true);
+        assert!(nfo.unique_ids[1].is_default);
         assert_eq!(nfo.unique_ids[2].value, "42");
         assert_eq!(nfo.credits[0].name, "Writer");
         assert_eq!(nfo.directors[0].name, "Director");
@@ -1212,9 +1343,16 @@ mod tests {
                 ParsedFile {
                     anime_name: "Show".to_string(),
                     episode_number: 1,
+                    episode_end: None,
+                    episode_fraction: None,
+                    version: None,
                     season_number: Some(1),
+                    part: None,
+                    final_season: false,
                     episode_type: EpisodeType::Normal,
                     tags: Vec::new(),
+                    release: ReleaseInfo::default(),
+                    year: None,
                     extension: "mkv".to_string(),
                     is_already_formatted: true,
                 },
@@ -1224,9 +1362,16 @@ mod tests {
                 ParsedFile {
                     anime_name: "Show".to_string(),
                     episode_number: 1,
+                    episode_end: None,
+                    episode_fraction: None,
+                    version: None,
                     season_number: Some(2),
+                    part: None,
+                    final_season: false,
                     episode_type: EpisodeType::Normal,
                     tags: Vec::new(),
+                    release: ReleaseInfo::default(),
+                    year: None,
                     extension: "mkv".to_string(),
                     is_already_formatted: true,
                 },
@@ -1236,9 +1381,16 @@ mod tests {
                 ParsedFile {
                     anime_name: "Show".to_string(),
                     episode_number: 2,
+                    episode_end: None,
+                    episode_fraction: None,
+                    version: None,
                     season_number: Some(1),
+                    part: None,
+                    final_season: false,
                     episode_type: EpisodeType::Normal,
                     tags: Vec::new(),
+                    release: ReleaseInfo::default(),
+                    year: None,
                     extension: "mkv".to_string(),
                     is_already_formatted: true,
                 },
@@ -1248,9 +1400,16 @@ mod tests {
                 ParsedFile {
                     anime_name: "Show".to_string(),
                     episode_number: 2,
+                    episode_end: None,
+                    episode_fraction: None,
+                    version: None,
                     season_number: Some(2),
+                    part: None,
+                    final_season: false,
                     episode_type: EpisodeType::Normal,
                     tags: Vec::new(),
+                    release: ReleaseInfo::default(),
+                    year: None,
                     extension: "mkv".to_string(),
                     is_already_formatted: true,
                 },
@@ -1268,4 +1427,28 @@ mod tests {
             &vec![PathBuf::from("/tmp/Show/Season 02")]
         );
     }
+
+    #[test]
+    fn test_show_roots_uses_each_show_directory_and_skips_shared_root() {
+        let parse = |path: &str| -> ParsedEntry {
+            let path = PathBuf::from(path);
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (path, FileParser::new().parse(&name).unwrap())
+        };
+        let groups = vec![
+            vec![
+                parse("/anime/Frieren/Season 1/[Group] Frieren - 01.mkv"),
+                parse("/anime/Frieren/Season 2/[Group] Frieren - 01.mkv"),
+            ],
+            vec![parse("/anime/[Group] Oshi no Ko - 01.mkv")],
+        ];
+
+        let roots = show_roots(Path::new("/anime"), &groups);
+
+        assert_eq!(roots, vec![Some(PathBuf::from("/anime/Frieren")), None]);
+        assert_eq!(
+            show_roots(Path::new("/anime"), &groups[..1]),
+            vec![Some(PathBuf::from("/anime"))]
+        );
+    }
 }
@@ -0,0 +1,125 @@
+use crate::cli::ParseArgs;
+use crate::config::Config;
+use crate::output::{self, OutputFormat};
+use crate::parser::{FileParser, MatchAttempt, ParseExplanation, ParsedFile};
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct ExplainedFile {
+    file: String,
+    #[serde(flatten)]
+    explanation: ParseExplanation,
+}
+
+fn print_attempts(title: &str, attempts: &[MatchAttempt]) {
+    println!("{title}:");
+    for (i, attempt) in attempts.iter().enumerate() {
+        let outcome = match (attempt.span, attempt.selected, attempt.excluded) {
+            (None, _, _) => "未匹配",
+            (Some(_), true, _) => "采用",
+            (Some(_), _, true) => "与排除区间重叠，继续查找",
+            (Some(_), _, _) => "未采用",
+        };
+        let span = match (attempt.span, &attempt.matched_text) {
+            (Some((start, end)), Some(text)) => format!("  位置 {start}..{end}  {text:?}"),
+            _ => String::new(),
+        };
+        println!(
+            "  [{}] {}  优先级 {}{span}  {outcome}",
+            i + 1,
+            attempt.matcher,
+            attempt.priority
+        );
+    }
+}
+
+fn print_parsed(parsed: &ParsedFile) {
+    let optional = |value: Option<u32>| value.map_or("-".to_string(), |value| value.to_string());
+    let release = &parsed.release;
+
+    println!("结果:");
+    println!("  番剧名: {}", parsed.anime_name);
+    println!("  类型: {:?}", parsed.episode_type);
+    println!("  季度: {}", optional(parsed.season_number));
+    if parsed.final_season {
+        println!("  最终季: 是");
+    }
+    println!("  分段: {}", optional(parsed.part));
+    println!("  集数: {}", parsed.episode_number);
+    println!("  最后一集: {}", optional(parsed.episode_end));
+    println!("  小数部分: {}", optional(parsed.episode_fraction));
+    println!("  版本: {}", optional(parsed.version));
+    println!("  年份: {}", optional(parsed.year));
+    println!("  字幕组: {}", release.group.as_deref().unwrap_or("-"));
+    println!("  分辨率: {}", release.resolution.as_deref().unwrap_or("-"));
+    println!(
+        "  视频编码: {}",
+        release.video_codec.as_deref().unwrap_or("-")
+    );
+    println!("  色深: {}", release.bit_depth.as_deref().unwrap_or("-"));
+    println!(
+        "  音频编码: {}",
+        release.audio_codec.as_deref().unwrap_or("-")
+    );
+    println!("  片源: {}", release.source.as_deref().unwrap_or("-"));
+    if !release.subtitle_languages.is_empty() {
+        println!("  字幕语言: {}", release.subtitle_languages.join(", "));
+    }
+    println!("  CRC32: {}", release.crc32.as_deref().unwrap_or("-"));
+    println!("  标签: {}", parsed.tags.join(" | "));
+    println!(
+        "  已规范化: {}",
+        if parsed.is_already_formatted {
+            "是"
+        } else {
+            "否"
+        }
+    );
+}
+
+fn print_explained(explained: &ExplainedFile) {
+    let explanation = &explained.explanation;
+
+    println!("文件: {}", explained.file);
+    print_attempts("季度匹配", &explanation.season);
+    print_attempts("分段匹配", &explanation.part);
+    let exclusions = explanation
+        .episode_exclusions
+        .iter()
+        .map(|(start, end)| format!("{start}..{end}"))
+        .collect::<Vec<_>>();
+    println!(
+        "集数排除区间: {}",
+        if exclusions.is_empty() {
+            "无".to_string()
+        } else {
+            exclusions.join(", ")
+        }
+    );
+    print_attempts("集数匹配", &explanation.episode);
+    match &explanation.parsed {
+        Some(parsed) => print_parsed(parsed),
+        None => println!("结果: 无法解析"),
+    }
+    println!();
+}
+
+pub(crate) fn run(args: &ParseArgs) -> Result<()> {
+    let parser = FileParser::with_config(&Config::load()?.parser)?;
+    let explained: Vec<ExplainedFile> = args
+        .filenames
+        .iter()
+        .map(|input| ExplainedFile {
+            file: input.clone(),
+            explanation: parser.explain(input),
+        })
+        .collect();
+
+    match args.output {
+        OutputFormat::Json => output::print_json(&explained)?,
+        OutputFormat::Text => explained.iter().for_each(print_explained),
+    }
+
+    Ok(())
+}
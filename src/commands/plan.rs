@@ -0,0 +1,44 @@
+use super::rename::build_rename_batch;
+use crate::cli::{PlanArgs, RenameArgs};
+use crate::error::FailureKind;
+use crate::operations::plan::PlanFile;
+use crate::operations::validate_rename_operations;
+use crate::output::{self, OutputFormat, status};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+pub(crate) async fn run(args: &PlanArgs) -> Result<()> {
+    let mut rename_args = RenameArgs::try_from(args.rename.clone())?;
+    // 未指定输出文件时计划写到 stdout，其余信息改走 stderr
+    rename_args.output = match args.out {
+        Some(_) => OutputFormat::Text,
+        None => OutputFormat::Json,
+    };
+    output::set_format(rename_args.output);
+
+    let batch = build_rename_batch(&rename_args).await?;
+    batch.print_preview();
+
+    let operations = batch.rename_operations();
+    validate_rename_operations(&operations).context(FailureKind::Conflict)?;
+
+    let plan = PlanFile::new(Path::new(&rename_args.path), &operations)?;
+    let content = plan.to_json()?;
+
+    match &args.out {
+        Some(path) => {
+            fs::write(path, format!("{content}\n"))
+                .with_context(|| format!("写入计划文件失败: {}", path.display()))?;
+            status!(
+                "已写入重命名计划 ({} 项): {}",
+                plan.operations.len(),
+                path.display()
+            );
+            status!("检查或修改后执行: anime_renamer apply {}", path.display());
+        }
+        None => println!("{content}"),
+    }
+
+    Ok(())
+}
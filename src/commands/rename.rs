@@ -1,65 +1,529 @@
+use super::verify::{CrcCheck, check_file};
+use super::{
+    ParsedEntry, fetch_season_details_map, group_by_show, record_journal, resolve_episode_group,
+    select_tmdb_show, show_directory, show_key,
+};
 use crate::anilist::{AniListClient, Media};
+use crate::cache::{CacheMode, MetadataCache};
 use crate::cli::RenameArgs;
-use crate::parser::{EpisodeType, FileParser, ParsedFile, extract_tmdb_id};
+use crate::config::Config;
+use crate::error::FailureKind;
+use crate::operations::{
+    RenameKind, RenameMode, RenameOperation, execute_rename_operations, validate_rename_operations,
+};
+use crate::output::{self, status};
+use crate::parser::{
+    Consensus, EXTRAS_FOLDERS, EpisodeType, FileParser, ParsedFile, SiblingParse, TagKind,
+    folder_tmdb_id,
+};
 use crate::scanner::FileScanner;
-use crate::tmdb::{Season, TmdbClient, TvDetails};
-use anyhow::{Context, Result, bail};
-use std::collections::{HashMap, HashSet};
+use crate::template::{
+    DEFAULT_TEMPLATE, SEASON_FOLDER_PREFIX, Template, TemplateValues, sanitize_component,
+};
+use crate::tmdb::{Episode, EpisodeGroupDetails, Season, SeasonDetails, TmdbClient, TvDetails};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-type ParsedEntry = (PathBuf, ParsedFile);
-type RenameEntry = (PathBuf, PathBuf, u32, u32);
-static TEMP_RENAME_COUNTER: AtomicUsize = AtomicUsize::new(0);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RenameEntry {
+    source: PathBuf,
+    target: PathBuf,
+    season: u32,
+    episode: u32,
+    /// 多集文件的最后一集
+    episode_end: Option<u32>,
+    /// 花絮类型，花絮不按单集命名
+    extra: Option<EpisodeType>,
+    /// 映射到的集在 TMDB 上尚未播出或没有播出日期
+    unaired: bool,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum RenameKind {
-    Video,
-    Subtitle,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SkipReason {
+    InvalidFileName,
+    ParseFailed,
+    AlreadyFormatted,
+    Movie,
+    UnmappedEpisode,
+    /// 同一集有更高版本（如 `05v2`）
+    SupersededVersion,
+    /// 文件内容与文件名中的 CRC32 不一致
+    ChecksumMismatch,
+    NoMatch,
 }
 
-#[derive(Debug, Clone)]
-struct RenameOperation {
-    source: PathBuf,
-    target: PathBuf,
-    kind: RenameKind,
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct SkippedFile {
+    path: PathBuf,
+    reason: SkipReason,
+}
+
+/// 与同目录文件比较后被修正或存在分歧的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ConsensusNote {
+    path: PathBuf,
+    #[serde(flatten)]
+    consensus: Consensus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MatchProvider {
+    Tmdb,
+    Anilist,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct MatchSource {
+    provider: MatchProvider,
+    id: u32,
+    name: String,
 }
 
-#[derive(Debug, Clone)]
-struct StagedRename {
+/// 带有季集信息的重命名操作，字幕沿用对应视频的季集
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct PlannedOperation {
+    #[serde(flatten)]
     operation: RenameOperation,
-    temp_path: PathBuf,
+    season: u32,
+    episode: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    episode_end: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extra: Option<EpisodeType>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    unaired: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ShowPlan<'a> {
+    #[serde(rename = "match")]
+    matched: &'a MatchSource,
+    operations: Vec<PlannedOperation>,
+}
+
+/// `--output json` 输出的完整重命名计划
+#[derive(Debug, Serialize)]
+struct RenamePlan<'a> {
+    dry_run: bool,
+    shows: Vec<ShowPlan<'a>>,
+    skipped: &'a [SkippedFile],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    consensus: &'a [ConsensusNote],
+}
+
+/// 扫描、解析并匹配后得到的一批重命名，每个番剧单独匹配
+pub(crate) struct RenameBatch {
+    mode: RenameMode,
+    shows: Vec<ResolvedRename>,
+    skipped: Vec<SkippedFile>,
+    consensus: Vec<ConsensusNote>,
+}
+
+impl RenameBatch {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.shows.iter().all(|show| show.entries.is_empty())
+    }
+
+    fn plan(&self, dry_run: bool) -> RenamePlan<'_> {
+        RenamePlan {
+            dry_run,
+            shows: self
+                .shows
+                .iter()
+                .map(|show| ShowPlan {
+                    matched: &show.matched,
+                    operations: build_rename_operations(&show.entries, self.mode),
+                })
+                .collect(),
+            skipped: &self.skipped,
+            consensus: &self.consensus,
+        }
+    }
+
+    pub(crate) fn rename_operations(&self) -> Vec<RenameOperation> {
+        self.shows
+            .iter()
+            .flat_map(|show| build_rename_operations(&show.entries, self.mode))
+            .map(|planned| planned.operation)
+            .collect()
+    }
+
+    pub(crate) fn print_preview(&self) {
+        if !self.is_empty() {
+            if self.mode != RenameMode::Rename {
+                status!(
+                    "模式: {}（保留源文件: {}）",
+                    self.mode.label(),
+                    if self.mode.moves_source() {
+                        "否"
+                    } else {
+                        "是"
+                    }
+                );
+            }
+            print_rename_preview(&self.shows, &self.consensus);
+        }
+    }
+}
+
+/// 匹配到的番剧与对应的重命名映射
+struct ResolvedRename {
+    matched: MatchSource,
+    entries: Vec<RenameEntry>,
 }
 
 fn apply_offset(episode: u32, offset: i32) -> u32 {
     (episode as i32 + offset).max(1) as u32
 }
 
+/// 分段之间的播出间隔超过该天数时视为新的一段（cour）
+const COUR_BREAK_DAYS: i64 = 42;
+
+/// TMDB 的季度结构：正片季度，以及按需从单集播出日期推断出的各季分段与已播出的集
+#[derive(Debug, Default)]
+struct SeasonLayout {
+    normal_seasons: Vec<Season>,
+    /// 季 → 各分段的 (第一集, 集数)
+    cours: HashMap<u32, Vec<(u32, u32)>>,
+    /// 季 → 已播出的集；只包含获取了单集播出日期的季
+    aired: HashMap<u32, BTreeSet<u32>>,
+}
+
+impl SeasonLayout {
+    fn new(details: &TvDetails) -> Self {
+        Self {
+            normal_seasons: normal_seasons(details),
+            cours: HashMap::new(),
+            aired: HashMap::new(),
+        }
+    }
+
+    /// 记录一季的单集播出日期：分段，以及播出日期不晚于 `today` 的集
+    fn record_air_dates(&mut self, season: &SeasonDetails, today: i64) {
+        self.cours.insert(season.season_number, split_cours(season));
+        let aired = season
+            .episodes
+            .iter()
+            .filter(|episode| {
+                episode
+                    .air_date
+                    .as_deref()
+                    .and_then(days_from_date)
+                    .is_some_and(|aired| aired <= today)
+            })
+            .map(|episode| episode.episode_number)
+            .collect();
+        self.aired.insert(season.season_number, aired);
+    }
+
+    /// 把绝对集数映射到季；之后还有已播出的季时，本季只按已播出的集依次编号，
+    /// 避免 TMDB 预先列出的未播出集占用编号
+    fn map_absolute(&self, episode: u32) -> Option<(u32, u32)> {
+        let aired_before_later = |index: usize| {
+            let continues = self.normal_seasons[index + 1..].iter().any(|later| {
+                self.aired
+                    .get(&later.season_number)
+                    .is_some_and(|aired| !aired.is_empty())
+            });
+            self.aired
+                .get(&self.normal_seasons[index].season_number)
+                .filter(|_| continues)
+        };
+        let seasons: Vec<Season> = self
+            .normal_seasons
+            .iter()
+            .enumerate()
+            .map(|(index, season)| match aired_before_later(index) {
+                Some(aired) => Season {
+                    episode_count: aired.len() as u32,
+                    ..season.clone()
+                },
+                None => season.clone(),
+            })
+            .collect();
+
+        let (season, season_episode) = map_episode_to_season(episode, &seasons)?;
+        let index = self
+            .normal_seasons
+            .iter()
+            .position(|s| s.season_number == season)?;
+        match aired_before_later(index) {
+            Some(aired) => Some((season, *aired.iter().nth(season_episode as usize - 1)?)),
+            None => Some((season, season_episode)),
+        }
+    }
+
+    /// 覆盖到第 `episode` 个绝对集数所需的正片季；已获取播出日期的季按已播出集数计算
+    fn seasons_covering(&self, episode: u32) -> BTreeSet<u32> {
+        let mut seasons = BTreeSet::new();
+        let mut accumulated = 0;
+        for season in &self.normal_seasons {
+            if accumulated >= episode {
+                break;
+            }
+            seasons.insert(season.season_number);
+            accumulated += self
+                .aired
+                .get(&season.season_number)
+                .map_or(season.episode_count, |aired| aired.len() as u32);
+        }
+        seasons
+    }
+
+    /// 获取了播出日期的季中，尚未播出或没有播出日期的集
+    fn is_unaired(&self, season: u32, episode: u32) -> bool {
+        self.aired
+            .get(&season)
+            .is_some_and(|aired| !aired.contains(&episode))
+    }
+
+    /// 按剧集组划分季度；剧集组已决定分段，不再按播出日期推断
+    fn from_episode_group(group: &EpisodeGroupDetails) -> Self {
+        Self {
+            normal_seasons: group
+                .seasons()
+                .into_iter()
+                .filter(|season| season.season_number > 0)
+                .collect(),
+            cours: HashMap::new(),
+            aired: HashMap::new(),
+        }
+    }
+
+    /// 最后一个有集数的正片季，用于 "Final Season"
+    fn final_season(&self) -> Option<u32> {
+        self.normal_seasons
+            .iter()
+            .filter(|s| s.episode_count > 0)
+            .map(|s| s.season_number)
+            .max()
+    }
+
+    fn has_season(&self, season: u32) -> bool {
+        self.normal_seasons
+            .iter()
+            .any(|s| s.season_number == season)
+    }
+
+    /// 将某季第 `part` 段的第 `episode` 集定位到 TMDB 的季和集数
+    fn locate_part(&self, season: u32, part: Option<u32>, episode: u32) -> (u32, u32) {
+        let part = match part {
+            Some(part) if part > 1 => part,
+            _ => return (season, episode),
+        };
+        let Some(cours) = self.cours.get(&season) else {
+            return (season, episode);
+        };
+
+        match cours.get(part as usize - 1) {
+            // 分段内从 1 重新计数的集数；已是连续编号时保持不变
+            Some(&(first, count)) if episode <= count => (season, first + episode - 1),
+            Some(_) => (season, episode),
+            // TMDB 把后续分段拆成了单独的季
+            None => {
+                let next = season + part - cours.len() as u32;
+                if self.has_season(next) {
+                    (next, episode)
+                } else {
+                    (season, episode)
+                }
+            }
+        }
+    }
+}
+
 fn compute_season_episode(
-    episode_type: &EpisodeType,
+    parsed: &ParsedFile,
     episode_number: u32,
-    season_number: Option<u32>,
     args_season: Option<u32>,
     offset: i32,
-    normal_seasons: &[Season],
+    layout: &SeasonLayout,
 ) -> Option<(u32, u32)> {
-    match episode_type {
-        EpisodeType::Normal => {
-            let ep = apply_offset(episode_number, offset);
-            if let Some(s) = args_season {
-                Some((s, ep))
-            } else if let Some(s) = season_number {
-                Some((s, ep))
-            } else {
-                map_episode_to_season(ep, normal_seasons)
+    let ep = apply_offset(episode_number, offset);
+    match parsed.episode_type {
+        EpisodeType::Movie => None,
+        _ if parsed.episode_type.extras_folder().is_some() => None,
+        // 13.5 之类的小数集通常是总集篇，归入特别篇
+        EpisodeType::Normal if parsed.episode_fraction.is_none() => {
+            let part = parsed.part;
+            match args_season.or_else(|| parsed_season(parsed, layout)) {
+                Some(s) => Some(layout.locate_part(s, part, ep)),
+                // 只有分段标记时按第一季的分段处理
+                None if part.is_some_and(|p| p > 1) => Some(layout.locate_part(1, part, ep)),
+                None => layout.map_absolute(ep),
             }
         }
-        EpisodeType::OVA | EpisodeType::Special | EpisodeType::OAD => {
-            let ep = apply_offset(episode_number, offset);
-            Some((0, ep))
+        _ => Some((0, ep)),
+    }
+}
+
+/// 文件名中的季度；"Final Season" 且没有明确季号时取 TMDB 的最后一季
+fn parsed_season(parsed: &ParsedFile, layout: &SeasonLayout) -> Option<u32> {
+    parsed.season_number.or_else(|| {
+        if parsed.final_season {
+            layout.final_season()
+        } else {
+            None
+        }
+    })
+}
+
+/// 按播出间隔把一季的单集切分为分段，返回各段的 (第一集, 集数)
+fn split_cours(season: &SeasonDetails) -> Vec<(u32, u32)> {
+    let mut episodes: Vec<&Episode> = season.episodes.iter().collect();
+    episodes.sort_by_key(|episode| episode.episode_number);
+
+    let mut cours: Vec<(u32, u32)> = Vec::new();
+    let mut last_aired: Option<i64> = None;
+    for episode in episodes {
+        let aired = episode.air_date.as_deref().and_then(days_from_date);
+        let is_break = matches!((last_aired, aired), (Some(last), Some(current)) if current - last > COUR_BREAK_DAYS);
+        match cours.last_mut() {
+            Some((_, count)) if !is_break => *count += 1,
+            _ => cours.push((episode.episode_number, 1)),
+        }
+        if aired.is_some() {
+            last_aired = aired;
+        }
+    }
+
+    cours
+}
+
+/// `YYYY-MM-DD` 距 1970-01-01 的天数
+fn days_from_date(date: &str) -> Option<i64> {
+    let mut fields = date.get(..10)?.split('-');
+    let year: i64 = fields.next()?.parse().ok()?;
+    let month: i64 = fields.next()?.parse().ok()?;
+    let day: i64 = fields.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// 按绝对集数映射到季的文件（没有季度与分段标记）
+fn uses_absolute_mapping(args: &RenameArgs, parsed: &ParsedFile, layout: &SeasonLayout) -> bool {
+    parsed.episode_type == EpisodeType::Normal
+        && parsed.episode_fraction.is_none()
+        && parsed.part.is_none_or(|part| part <= 1)
+        && args.season.is_none()
+        && parsed_season(parsed, layout).is_none()
+}
+
+/// 有文件带第二段及以后的分段标记，或按绝对集数映射时，获取相关季的单集播出日期，
+/// 用于推断分段以及排除尚未播出的集
+///
+/// 绝对集数只获取覆盖最大集数所需的季；已获取的季播出集数不足时再向后补充
+async fn fetch_season_layout(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    client: &TmdbClient,
+    details: &TvDetails,
+) -> SeasonLayout {
+    let mut layout = SeasonLayout::new(details);
+    let part_seasons: BTreeSet<u32> = parsed_files
+        .iter()
+        .filter(|(_, parsed)| parsed.part.is_some_and(|part| part > 1))
+        .map(|(_, parsed)| {
+            args.season
+                .or_else(|| parsed_season(parsed, &layout))
+                .unwrap_or(1)
+        })
+        .filter(|season| layout.has_season(*season))
+        .collect();
+    let max_absolute = parsed_files
+        .iter()
+        .filter(|(_, parsed)| uses_absolute_mapping(args, parsed, &layout))
+        .map(|(_, parsed)| {
+            apply_offset(
+                parsed.episode_end.unwrap_or(parsed.episode_number),
+                args.offset,
+            )
+        })
+        .max();
+
+    let mut fetched = BTreeSet::new();
+    let today = today_days();
+    loop {
+        let mut seasons = part_seasons.clone();
+        if let Some(episode) = max_absolute {
+            seasons.extend(layout.seasons_covering(episode));
+        }
+        let missing: BTreeSet<u32> = seasons.difference(&fetched).copied().collect();
+        if missing.is_empty() {
+            break;
+        }
+
+        if fetched.is_empty() {
+            status!("获取单集播出日期...");
+        }
+        match fetch_season_details_map(client, details.id, &missing, &args.language).await {
+            Ok(season_details_map) => {
+                for season_details in season_details_map.values() {
+                    layout.record_air_dates(season_details, today);
+                }
+            }
+            Err(error) => {
+                status!("获取单集播出日期失败，将直接按 TMDB 的集数换算: {error:#}");
+                break;
+            }
+        }
+        fetched.extend(missing);
+    }
+
+    layout
+}
+
+/// 今天距 1970-01-01 的天数（UTC）
+fn today_days() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| (duration.as_secs() / 86_400) as i64)
+}
+
+/// 多集文件最后一集在 `season` 中的集数；跨季或不晚于第一集时无法表示为范围，返回 None
+fn compute_episode_end(
+    parsed: &ParsedFile,
+    season: u32,
+    episode: u32,
+    args: &RenameArgs,
+    layout: &SeasonLayout,
+) -> Option<u32> {
+    let end = parsed.episode_end?;
+    let mapped = compute_season_episode(parsed, end, args.season, args.offset, layout);
+
+    match mapped {
+        Some((end_season, end_episode)) if end_season == season && end_episode > episode => {
+            Some(end_episode)
+        }
+        _ => {
+            status!(
+                "多集文件跨季，仅按第一集命名: S{season:02}E{episode:02} ({})",
+                parsed.anime_name
+            );
+            None
         }
-        EpisodeType::Movie => None,
+    }
+}
+
+fn episode_label(season: u32, episode: u32, episode_end: Option<u32>) -> String {
+    match episode_end {
+        Some(end) => format!("S{season:02}E{episode:02}-E{end:02}"),
+        None => format!("S{season:02}E{episode:02}"),
     }
 }
 
@@ -96,21 +560,61 @@ fn file_stem_lossy(path: &Path) -> Option<String> {
         .map(|value| value.to_string_lossy().into_owned())
 }
 
-fn print_rename_preview(rename_map: &[RenameEntry], season_folders: bool) {
-    println!("重命名预览:\n");
-    for (i, (old_path, new_path, season, episode)) in rename_map.iter().enumerate() {
-        println!("[{}] S{:02}E{:02}", i + 1, season, episode);
-        println!("  原文件: {}", display_file_name(old_path));
+fn print_show_header(matched: &MatchSource) {
+    let provider = match matched.provider {
+        MatchProvider::Tmdb => "TMDB",
+        MatchProvider::Anilist => "AniList",
+    };
+    status!("== {} ({provider} ID: {}) ==\n", matched.name, matched.id);
+}
+
+fn print_rename_preview(shows: &[ResolvedRename], consensus: &[ConsensusNote]) {
+    status!("重命名预览:\n");
+    let entries = shows.iter().flat_map(|show| {
+        show.entries
+            .iter()
+            .enumerate()
+            .map(move |(index, entry)| (show, index, entry))
+    });
+    for (i, (show, index_in_show, entry)) in entries.enumerate() {
+        if shows.len() > 1 && index_in_show == 0 {
+            print_show_header(&show.matched);
+        }
+        let (old_path, new_path) = (&entry.source, &entry.target);
+        let label = match &entry.extra {
+            Some(extra) => format!(
+                "{}{} → {}/",
+                extra.extra_label(),
+                entry.episode,
+                extra.extras_folder().unwrap_or_default()
+            ),
+            None => episode_label(entry.season, entry.episode, entry.episode_end),
+        };
+        status!("[{}] {label}", i + 1);
+        status!("  原文件: {}", display_file_name(old_path));
+        match consensus
+            .iter()
+            .find(|note| note.path == *old_path)
+            .map(|note| note.consensus)
+        {
+            Some(Consensus::Corrected { isolated_episode }) => status!(
+                "  已修正: 单独解析为第 {isolated_episode} 集，该数字在同目录文件中固定不变，视为标题的一部分"
+            ),
+            Some(Consensus::Disagrees) => {
+                status!("  注意: 集数位置与同目录其他文件不一致，请确认")
+            }
+            None => {}
+        }
+        if entry.unaired {
+            status!("  注意: 该集在 TMDB 上尚未播出或没有播出日期，请确认集数映射");
+        }
 
-        if season_folders {
-            if let Some(old_parent) = old_path.parent() {
+        match old_path.parent() {
+            Some(old_parent) if new_path.parent() != Some(old_parent) => {
                 let relative_path = new_path.strip_prefix(old_parent).unwrap_or(new_path);
-                println!("  新路径: {}", relative_path.display());
-            } else {
-                println!("  新文件: {}", display_file_name(new_path));
+                status!("  新路径: {}", relative_path.display());
             }
-        } else {
-            println!("  新文件: {}", display_file_name(new_path));
+            _ => status!("  新文件: {}", display_file_name(new_path)),
         }
 
         let subtitles = FileScanner::find_associated_subtitles(old_path);
@@ -123,22 +627,33 @@ fn print_rename_preview(rename_map: &[RenameEntry], season_folders: bool) {
                     Some(name.strip_prefix(&old_stem)?.to_string())
                 })
                 .collect();
-            println!("  字幕: {}", suffixes.join(", "));
+            status!("  字幕: {}", suffixes.join(", "));
         }
-        println!();
+        status!();
     }
 }
 
-fn build_rename_operations(rename_map: &[RenameEntry]) -> Vec<RenameOperation> {
+fn build_rename_operations(rename_map: &[RenameEntry], mode: RenameMode) -> Vec<PlannedOperation> {
     let mut operations = Vec::new();
 
-    for (old_path, new_path, _, _) in rename_map {
+    for entry in rename_map {
+        let (old_path, new_path) = (&entry.source, &entry.target);
+        let planned = |operation| PlannedOperation {
+            operation,
+            season: entry.season,
+            episode: entry.episode,
+            episode_end: entry.episode_end,
+            extra: entry.extra.clone(),
+            unaired: entry.unaired,
+        };
+
         if old_path != new_path {
-            operations.push(RenameOperation {
+            operations.push(planned(RenameOperation {
                 source: old_path.clone(),
                 target: new_path.clone(),
                 kind: RenameKind::Video,
-            });
+                mode,
+            }));
         }
 
         let old_video_stem = file_stem_lossy(old_path).unwrap_or_default();
@@ -151,11 +666,12 @@ fn build_rename_operations(rename_map: &[RenameEntry]) -> Vec<RenameOperation> {
             };
 
             if subtitle_path != new_subtitle_path {
-                operations.push(RenameOperation {
+                operations.push(planned(RenameOperation {
                     source: subtitle_path,
                     target: new_subtitle_path,
                     kind: RenameKind::Subtitle,
-                });
+                    mode,
+                }));
             }
         }
     }
@@ -163,343 +679,498 @@ fn build_rename_operations(rename_map: &[RenameEntry]) -> Vec<RenameOperation> {
     operations
 }
 
-fn validate_rename_operations(operations: &[RenameOperation]) -> Result<()> {
-    let mut targets: HashMap<&Path, Vec<&Path>> = HashMap::new();
-    let sources: HashSet<&Path> = operations
-        .iter()
-        .map(|operation| operation.source.as_path())
-        .collect();
-
-    for operation in operations {
-        targets
-            .entry(operation.target.as_path())
-            .or_default()
-            .push(operation.source.as_path());
+fn execute_rename(args: &RenameArgs, batch: &RenameBatch) -> Result<()> {
+    if output::is_json() {
+        output::print_json(&batch.plan(args.dry_run))?;
     }
 
-    for (target, sources_for_target) in targets {
-        if sources_for_target.len() > 1 {
-            let joined_sources = sources_for_target
-                .iter()
-                .map(|source| source.display().to_string())
-                .collect::<Vec<_>>()
-                .join(", ");
-            bail!(
-                "多个文件将重命名为同一目标 {}: {}",
-                target.display(),
-                joined_sources
-            );
-        }
+    let operations = batch.rename_operations();
+    validate_rename_operations(&operations).context(FailureKind::Conflict)?;
 
-        if target.exists() && !sources.contains(target) {
-            bail!("目标文件已存在: {}", target.display());
-        }
+    if args.dry_run {
+        status!("预览模式，未实际重命名");
+        return Ok(());
     }
 
-    for operation in operations {
-        if !operation.source.exists() {
-            bail!("源文件不存在: {}", operation.source.display());
-        }
+    if operations.is_empty() {
+        status!("没有需要重命名的文件");
+        return Ok(());
     }
 
-    Ok(())
-}
-
-fn temporary_rename_path(source: &Path, index: usize) -> PathBuf {
-    let unique = TEMP_RENAME_COUNTER.fetch_add(1, Ordering::Relaxed);
-    let nanos = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("system time should be after unix epoch")
-        .as_nanos();
-    let file_name = display_file_name(source);
-
-    source.with_file_name(format!(
-        ".anime-renamer-tmp-{nanos}-{unique}-{index}-{file_name}"
-    ))
-}
-
-fn rollback_rename_operations(
-    staged: &[StagedRename],
-    finalized_count: usize,
-    failed_index: usize,
-) -> Result<()> {
-    for staged_rename in staged[..finalized_count].iter().rev() {
-        if staged_rename.operation.target.exists() {
-            std::fs::rename(
-                &staged_rename.operation.target,
-                &staged_rename.operation.source,
-            )
-            .with_context(|| {
-                format!(
-                    "回滚失败: {} -> {}",
-                    staged_rename.operation.target.display(),
-                    staged_rename.operation.source.display()
-                )
-            })?;
+    if !args.yes {
+        let input = output::prompt(&format!("继续{}？[Y/n] ", args.mode.label()))?;
+        if !input.is_empty() && !input.eq_ignore_ascii_case("y") {
+            status!("已取消");
+            return Ok(());
         }
     }
 
-    for staged_rename in &staged[failed_index..] {
-        if staged_rename.temp_path.exists() {
-            std::fs::rename(&staged_rename.temp_path, &staged_rename.operation.source)
-                .with_context(|| {
-                    format!(
-                        "回滚失败: {} -> {}",
-                        staged_rename.temp_path.display(),
-                        staged_rename.operation.source.display()
-                    )
-                })?;
-        }
+    let summary = execute_rename_operations(&operations).context(FailureKind::Rollback)?;
+
+    let label = args.mode.label();
+    status!("\n成功{label} {} 个视频文件", summary.video_success);
+    if summary.subtitle_success > 0 {
+        status!("成功{label} {} 个字幕文件", summary.subtitle_success);
+    }
+    if summary.hardlink_fallbacks > 0 {
+        status!(
+            "{} 个文件与目标不在同一文件系统，已改为复制",
+            summary.hardlink_fallbacks
+        );
+    }
+    if summary.cross_device_moves > 0 {
+        status!(
+            "{} 个文件跨文件系统移动，已校验复制结果并删除源文件",
+            summary.cross_device_moves
+        );
     }
 
+    record_journal(Path::new(&args.path), &operations, &summary.created_dirs);
+
     Ok(())
 }
 
-fn execute_rename_operations(operations: &[RenameOperation]) -> Result<(usize, usize)> {
-    let mut staged = Vec::with_capacity(operations.len());
-
-    for (index, operation) in operations.iter().enumerate() {
-        let temp_path = temporary_rename_path(&operation.source, index);
-        if let Err(error) = std::fs::rename(&operation.source, &temp_path) {
-            rollback_rename_operations(&staged, 0, 0).context(format!(
-                "暂存重命名失败且回滚未完成: {} -> {} ({error})",
-                operation.source.display(),
-                temp_path.display()
-            ))?;
-            bail!(
-                "暂存重命名失败，已回滚: {} -> {} ({error})",
-                operation.source.display(),
-                temp_path.display()
-            );
+/// 按所在目录分批解析，同目录文件互相参照确定集数位置
+fn parse_by_directory(
+    files: &[PathBuf],
+    parser: &FileParser,
+) -> Vec<Option<(String, SiblingParse)>> {
+    let mut directories: HashMap<Option<&Path>, Vec<(usize, String)>> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        if let Some(filename) = file_name_lossy(file) {
+            directories
+                .entry(file.parent())
+                .or_default()
+                .push((index, filename));
         }
-        staged.push(StagedRename {
-            operation: operation.clone(),
-            temp_path,
-        });
     }
 
-    let mut video_success = 0;
-    let mut subtitle_success = 0;
-
-    for (index, staged_rename) in staged.iter().enumerate() {
-        if let Some(parent_dir) = staged_rename.operation.target.parent()
-            && let Err(error) = std::fs::create_dir_all(parent_dir)
+    let mut results = vec![None; files.len()];
+    for (dir, siblings) in directories {
+        let (indices, filenames): (Vec<usize>, Vec<String>) = siblings.into_iter().unzip();
+        let folder = dir.map(|dir| parser.folder_info(dir)).unwrap_or_default();
+        let parsed = parser.parse_siblings(&filenames, &folder);
+        for ((index, filename), (parsed, consensus)) in
+            indices.into_iter().zip(filenames).zip(parsed)
         {
-            rollback_rename_operations(&staged, index, index).context(format!(
-                "创建目录失败且回滚未完成: {} ({error})",
-                parent_dir.display()
-            ))?;
-            bail!("创建目录失败，已回滚: {} ({error})", parent_dir.display());
+            results[index] = Some((filename, (parsed, consensus)));
         }
+    }
+    results
+}
 
-        if staged_rename.operation.target.exists()
-            && let Err(error) = std::fs::remove_file(&staged_rename.operation.target)
-        {
-            rollback_rename_operations(&staged, index, index).context(format!(
-                "移除已存在目标失败且回滚未完成: {} ({error})",
-                staged_rename.operation.target.display()
-            ))?;
-            bail!(
-                "移除已存在目标失败，已回滚: {} ({error})",
-                staged_rename.operation.target.display()
-            );
-        }
+fn collect_rename_candidates(
+    files: &[PathBuf],
+    parser: &FileParser,
+    skipped: &mut Vec<SkippedFile>,
+    consensus: &mut Vec<ConsensusNote>,
+) -> Vec<ParsedEntry> {
+    let mut parsed_files = Vec::new();
+    let mut skipped_formatted = 0;
+    let mut skip = |path: &Path, reason| {
+        skipped.push(SkippedFile {
+            path: path.to_path_buf(),
+            reason,
+        })
+    };
 
-        if let Err(error) =
-            std::fs::rename(&staged_rename.temp_path, &staged_rename.operation.target)
-        {
-            rollback_rename_operations(&staged, index, index).context(format!(
-                "执行重命名失败且回滚未完成: {} -> {} ({error})",
-                staged_rename.temp_path.display(),
-                staged_rename.operation.target.display()
-            ))?;
-            bail!(
-                "执行重命名失败，已回滚: {} -> {} ({error})",
-                staged_rename.operation.source.display(),
-                staged_rename.operation.target.display()
-            );
-        }
+    for (file, result) in files.iter().zip(parse_by_directory(files, parser)) {
+        let Some((filename, (parsed, note))) = result else {
+            status!("无法获取文件名: {}", file.display());
+            skip(file, SkipReason::InvalidFileName);
+            continue;
+        };
 
-        match staged_rename.operation.kind {
-            RenameKind::Video => video_success += 1,
-            RenameKind::Subtitle => subtitle_success += 1,
+        if let Some(parsed) = parsed {
+            if parsed.is_already_formatted {
+                skipped_formatted += 1;
+                skip(file, SkipReason::AlreadyFormatted);
+                continue;
+            }
+            if let Some(note) = note {
+                consensus.push(ConsensusNote {
+                    path: file.clone(),
+                    consensus: note,
+                });
+            }
+            parsed_files.push((file.clone(), parsed));
+        } else {
+            status!("无法解析: {filename}");
+            skip(file, SkipReason::ParseFailed);
         }
     }
 
-    Ok((video_success, subtitle_success))
-}
+    if skipped_formatted > 0 {
+        status!("跳过 {skipped_formatted} 个已规范化的文件\n");
+    }
 
-fn execute_rename(rename_map: &[RenameEntry], dry_run: bool) -> Result<()> {
-    use std::io::{self, Write};
+    keep_latest_versions(parsed_files, skipped)
+}
 
-    let operations = build_rename_operations(rename_map);
-    validate_rename_operations(&operations)?;
+/// 同一集存在多个发布版本（`05`、`05v2`）时只保留版本最高的文件
+///
+/// 按 `group_by_show` 的方式区分番剧（番剧目录与规范化后的番剧名）；
+/// 非递归扫描时文件都在同一目录，按目录区分不影响结果
+fn keep_latest_versions(
+    parsed_files: Vec<ParsedEntry>,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<ParsedEntry> {
+    let episode_key = |entry: &ParsedEntry| {
+        let parsed = &entry.1;
+        (
+            show_key(entry, true),
+            parsed.season_number,
+            parsed.part,
+            parsed.episode_type.clone(),
+            parsed.episode_number,
+            parsed.episode_end,
+            parsed.episode_fraction,
+        )
+    };
+    let version = |parsed: &ParsedFile| parsed.version.unwrap_or(1);
 
-    if dry_run {
-        println!("预览模式，未实际重命名");
-        return Ok(());
+    let mut latest = HashMap::new();
+    for entry in &parsed_files {
+        let best = latest.entry(episode_key(entry)).or_insert(0);
+        *best = version(&entry.1).max(*best);
     }
 
-    print!("继续重命名？[Y/n] ");
-    io::stdout().flush()?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    parsed_files
+        .into_iter()
+        .filter(|entry| {
+            let (path, parsed) = entry;
+            let is_latest = version(parsed) >= latest[&episode_key(entry)];
+            if !is_latest {
+                status!("跳过旧版本: {}", display_file_name(path));
+                skipped.push(SkippedFile {
+                    path: path.clone(),
+                    reason: SkipReason::SupersededVersion,
+                });
+            }
+            is_latest
+        })
+        .collect()
+}
 
-    if !input.trim().is_empty() && !input.trim().eq_ignore_ascii_case("y") {
-        println!("已取消");
-        return Ok(());
+/// 按文件名中的 CRC32 校验文件；`--skip-corrupted` 时跳过校验失败的文件
+fn verify_checksums(
+    args: &RenameArgs,
+    parsed_files: Vec<ParsedEntry>,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<ParsedEntry> {
+    let tagged = parsed_files
+        .iter()
+        .filter(|(_, parsed)| parsed.release.crc32.is_some())
+        .count();
+    if tagged == 0 {
+        status!("没有带 CRC32 标签的文件，跳过校验\n");
+        return parsed_files;
     }
 
-    let (video_success, subtitle_success) = execute_rename_operations(&operations)?;
+    status!("校验 {tagged} 个文件的 CRC32...");
+    let mut corrupted = 0;
+    let kept = parsed_files
+        .into_iter()
+        .filter(|(path, _)| {
+            let failure = match check_file(path) {
+                Ok(CrcCheck::Mismatch { expected, actual }) => {
+                    format!("期望 {expected:08X}，实际 {actual:08X}")
+                }
+                Ok(_) => return true,
+                Err(error) => format!("{error:#}"),
+            };
+            corrupted += 1;
+            status!("CRC32 校验失败: {}（{failure}）", display_file_name(path));
+            if !args.skip_corrupted {
+                return true;
+            }
+            skipped.push(SkippedFile {
+                path: path.clone(),
+                reason: SkipReason::ChecksumMismatch,
+            });
+            false
+        })
+        .collect();
 
-    println!("\n成功重命名 {video_success} 个视频文件");
-    if subtitle_success > 0 {
-        println!("成功重命名 {subtitle_success} 个字幕文件");
+    if corrupted == 0 {
+        status!("CRC32 校验通过\n");
+    } else if args.skip_corrupted {
+        status!("已跳过 {corrupted} 个损坏的文件\n");
+    } else {
+        status!(
+            "{corrupted} 个文件 CRC32 校验失败，仍会重命名；使用 --skip-corrupted 可跳过这些文件\n"
+        );
     }
 
-    Ok(())
+    kept
 }
 
-fn collect_rename_candidates(files: &[PathBuf], parser: &FileParser) -> Vec<ParsedEntry> {
-    let mut parsed_files = Vec::new();
-    let mut skipped_formatted = 0;
+/// 同一番剧的所有文件共享的模板字段
+struct ShowInfo<'a> {
+    name: &'a str,
+    year: Option<u32>,
+    tmdb_id: Option<u32>,
+    /// 以 (季, 集) 为键的单集标题
+    episode_titles: HashMap<(u32, u32), String>,
+}
 
-    for file in files {
-        let Some(filename) = file_name_lossy(file) else {
-            println!("无法获取文件名: {}", file.display());
-            continue;
-        };
+/// TMDB 没有本地化标题时会返回 "Episode 3"、"第 3 集" 之类的占位名称
+fn is_placeholder_episode_title(title: &str) -> bool {
+    static PLACEHOLDER_RE: OnceLock<Regex> = OnceLock::new();
+    let regex = PLACEHOLDER_RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(?:(?:episode|ep\.?|épisode|episodio|epis[oó]dio|folge|capítulo|エピソード)\s*#?\s*\d+|第\s*\d+\s*[集話话]|\d+)$",
+        )
+        .unwrap()
+    });
+    regex.is_match(title.trim())
+}
 
-        if let Some(parsed) = parser.parse(&filename) {
-            if parsed.is_already_formatted {
-                skipped_formatted += 1;
-                continue;
+fn episode_title(episode: &Episode) -> Option<String> {
+    let title = episode.name.trim();
+    (!title.is_empty() && !is_placeholder_episode_title(title)).then(|| title.to_string())
+}
+
+fn build_episode_titles(
+    season_details_map: &HashMap<u32, SeasonDetails>,
+) -> HashMap<(u32, u32), String> {
+    let mut titles = HashMap::new();
+
+    for details in season_details_map.values() {
+        for episode in &details.episodes {
+            if let Some(title) = episode_title(episode) {
+                titles.insert((details.season_number, episode.episode_number), title);
             }
-            parsed_files.push((file.clone(), parsed));
-        } else {
-            println!("无法解析: {filename}");
         }
     }
 
-    if skipped_formatted > 0 {
-        println!("跳过 {skipped_formatted} 个已规范化的文件\n");
+    titles
+}
+
+/// 剧集组中各集的标题，按剧集组的季与集数索引
+fn build_group_episode_titles(group: &EpisodeGroupDetails) -> HashMap<(u32, u32), String> {
+    group
+        .episode_map()
+        .iter()
+        .filter_map(|(key, episode)| episode_title(episode).map(|title| (*key, title)))
+        .collect()
+}
+
+fn extract_year(date: Option<&str>) -> Option<u32> {
+    date.and_then(|value| value.get(..4)?.parse::<u32>().ok())
+}
+
+fn resolve_template(args: &RenameArgs, config: &Config) -> Result<Template> {
+    let source = args
+        .template
+        .as_deref()
+        .or(config.rename.template.as_deref())
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let template = Template::parse(source).context("文件名模板无效")?;
+
+    if args.season_folders && !template.has_directories() {
+        return Template::parse(&format!("{SEASON_FOLDER_PREFIX}{}", template.as_str()))
+            .context("文件名模板无效");
     }
 
-    parsed_files
+    Ok(template)
 }
 
-fn build_output_name(
-    show_name: &str,
+fn template_values(
+    show: &ShowInfo,
+    parsed: &ParsedFile,
     season: u32,
     episode: u32,
-    extension: &str,
-    keep_tags: bool,
-    tags: &[String],
-) -> String {
-    if keep_tags && !tags.is_empty() {
-        let tags_str = tags
-            .iter()
-            .map(|tag| format!("[{tag}]"))
-            .collect::<Vec<_>>()
-            .join("");
-        format!("{show_name} S{season:02}E{episode:02}{tags_str}.{extension}")
-    } else {
-        format!("{show_name} S{season:02}E{episode:02}.{extension}")
+    episode_end: Option<u32>,
+    keep_tags: Option<&[TagKind]>,
+    offset: i32,
+) -> TemplateValues {
+    TemplateValues {
+        show: show.name.to_string(),
+        season,
+        episode,
+        episode_end,
+        episode_title: show.episode_titles.get(&(season, episode)).cloned(),
+        absolute: Some(apply_offset(parsed.episode_number, offset)),
+        year: show.year,
+        tmdb_id: show.tmdb_id,
+        group: parsed.release.group.clone(),
+        resolution: parsed.release.resolution.clone(),
+        video_codec: parsed.release.video_codec.clone(),
+        bit_depth: parsed.release.bit_depth.clone(),
+        audio_codec: parsed.release.audio_codec.clone(),
+        source: parsed.release.source.clone(),
+        subtitles: parsed.release.subtitle_languages.clone(),
+        crc32: parsed.release.crc32.clone(),
+        tags: keep_tags
+            .map(|kinds| parsed.release.kept_tags(&parsed.tags, kinds))
+            .unwrap_or_default(),
     }
 }
 
-fn season_folder_name(season: u32) -> String {
-    if season == 0 {
-        "Season 0".to_string()
-    } else {
-        format!("Season {season}")
-    }
+/// 目标路径的基准目录：指定 `--output-dir` 时为媒体库根目录，否则为视频所在目录
+fn target_parent<'a>(args: &'a RenameArgs, file_path: &'a Path) -> &'a Path {
+    args.output_dir
+        .as_deref()
+        .or_else(|| file_path.parent())
+        .unwrap_or(Path::new(""))
 }
 
 fn build_rename_target(
     parent: &Path,
-    new_name: &str,
-    season: u32,
-    season_folders: bool,
+    template: &Template,
+    values: &TemplateValues,
+    extension: &str,
 ) -> PathBuf {
-    if season_folders {
-        parent.join(season_folder_name(season)).join(new_name)
-    } else {
-        parent.join(new_name)
+    let mut target = parent.join(template.render(values));
+    let mut file_name = target.file_name().unwrap_or_default().to_os_string();
+    if !extension.is_empty() {
+        file_name.push(".");
+        file_name.push(extension);
     }
+    target.set_file_name(file_name);
+    target
 }
 
-fn handle_anilist_renaming(
+/// 花絮（NCOP、PV、菜单等）不按单集命名，放入番剧目录下的 `Extras/`、`Trailers/`、`Other/`
+fn build_extra_entry(
     args: &RenameArgs,
-    parsed_files: &[ParsedEntry],
-    anime_name: &str,
-) -> Result<()> {
-    let mut rename_map = Vec::new();
+    file_path: &Path,
+    parsed: &ParsedFile,
+    show: &ShowInfo,
+    template: &Template,
+) -> RenameEntry {
+    let episode = parsed.episode_number;
+    let values = template_values(
+        show,
+        parsed,
+        0,
+        episode,
+        None,
+        args.keep_tags.as_deref(),
+        args.offset,
+    );
 
-    for (file_path, parsed) in parsed_files {
-        let parent = file_path.parent().unwrap();
-        let season = args
+    // 模板带目录时按渲染结果确定番剧目录，季度目录和已有的花絮目录归到上一级
+    let rendered = target_parent(args, file_path).join(template.render(&values));
+    let mut show_dir = rendered.parent().unwrap_or(Path::new("")).to_path_buf();
+    if show_dir.file_name().is_some_and(|name| {
+        EXTRAS_FOLDERS
+            .iter()
+            .any(|folder| name.eq_ignore_ascii_case(folder))
+    }) {
+        show_dir.pop();
+    }
+
+    let mut file_name = format!(
+        "{} - {}{episode}",
+        sanitize_component(show.name),
+        parsed.episode_type.extra_label()
+    );
+    if !parsed.extension.is_empty() {
+        file_name.push('.');
+        file_name.push_str(&parsed.extension);
+    }
+
+    RenameEntry {
+        source: file_path.to_path_buf(),
+        target: show_directory(&show_dir)
+            .join(parsed.episode_type.extras_folder().unwrap_or_default())
+            .join(file_name),
+        season: 0,
+        episode,
+        episode_end: None,
+        extra: Some(parsed.episode_type.clone()),
+        unaired: false,
+    }
+}
+
+fn build_anilist_rename_map(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    template: &Template,
+    show: &ShowInfo,
+) -> Vec<RenameEntry> {
+    let mut rename_map = Vec::new();
+
+    for (file_path, parsed) in parsed_files {
+        if parsed.episode_type.extras_folder().is_some() {
+            rename_map.push(build_extra_entry(args, file_path, parsed, show, template));
+            continue;
+        }
+
+        let parent = target_parent(args, file_path);
+        let season = args
             .season
             .unwrap_or_else(|| parsed.season_number.unwrap_or(1));
         let episode = apply_offset(parsed.episode_number, args.offset);
-        let new_name = build_output_name(
-            anime_name,
+        let episode_end = parsed
+            .episode_end
+            .map(|end| apply_offset(end, args.offset))
+            .filter(|end| *end > episode);
+        let values = template_values(
+            show,
+            parsed,
             season,
             episode,
-            &parsed.extension,
-            args.keep_tags,
-            &parsed.tags,
+            episode_end,
+            args.keep_tags.as_deref(),
+            args.offset,
         );
-        let new_path = build_rename_target(parent, &new_name, season, args.season_folders);
+        let new_path = build_rename_target(parent, template, &values, &parsed.extension);
 
-        rename_map.push((file_path.clone(), new_path, season, episode));
+        rename_map.push(RenameEntry {
+            source: file_path.clone(),
+            target: new_path,
+            season,
+            episode,
+            episode_end,
+            extra: None,
+            unaired: false,
+        });
     }
 
-    print_rename_preview(&rename_map, args.season_folders);
-    execute_rename(&rename_map, args.dry_run)
+    rename_map
 }
 
-async fn prompt_anilist_title(anime: &Media) -> Result<Option<String>> {
-    use std::io::{self, Write};
-
-    println!("\n找到番剧，请选择使用哪个标题:");
+fn prompt_anilist_title(anime: &Media, assume_yes: bool) -> Result<Option<String>> {
+    status!("\n找到番剧，请选择使用哪个标题:");
     let mut title_options = Vec::new();
 
     if let Some(ref native) = anime.title.native {
         title_options.push(native.clone());
-        println!("  [{}] {} (原语言)", title_options.len(), native);
+        status!("  [{}] {} (原语言)", title_options.len(), native);
     }
 
     if let Some(ref romaji) = anime.title.romaji {
         title_options.push(romaji.clone());
-        println!("  [{}] {} (罗马音)", title_options.len(), romaji);
+        status!("  [{}] {} (罗马音)", title_options.len(), romaji);
     }
 
     if let Some(ref english) = anime.title.english {
         title_options.push(english.clone());
-        println!("  [{}] {} (英文)", title_options.len(), english);
+        status!("  [{}] {} (英文)", title_options.len(), english);
     }
 
     if title_options.is_empty() {
         return Ok(None);
     }
 
-    print!(
+    if assume_yes {
+        return Ok(Some(title_options[0].clone()));
+    }
+
+    let input = output::prompt(&format!(
         "\n请输入数字选择标题 [1-{}]，或输入自定义名称: ",
         title_options.len()
-    );
-    io::stdout().flush()?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let input = input.trim();
+    ))?;
+    let input = input.as_str();
 
     let display_name = if let Ok(choice) = input.parse::<usize>() {
         if choice > 0 && choice <= title_options.len() {
             title_options[choice - 1].clone()
         } else {
-            println!("无效选择，使用第一个选项");
+            status!("无效选择，使用第一个选项");
             title_options[0].clone()
         }
     } else if !input.is_empty() {
@@ -511,183 +1182,417 @@ async fn prompt_anilist_title(anime: &Media) -> Result<Option<String>> {
     Ok(Some(display_name))
 }
 
-fn build_tmdb_rename_map(
-    args: &RenameArgs,
-    parsed_files: &[ParsedEntry],
-    details: &TvDetails,
-) -> Vec<RenameEntry> {
-    let normal_seasons: Vec<_> = details
+fn normal_seasons(details: &TvDetails) -> Vec<Season> {
+    details
         .seasons
         .iter()
         .filter(|s| s.season_number > 0)
         .cloned()
-        .collect();
+        .collect()
+}
 
+/// 返回重命名映射会用到的季度
+fn mapped_seasons(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    layout: &SeasonLayout,
+) -> BTreeSet<u32> {
+    parsed_files
+        .iter()
+        .filter_map(|(_, parsed)| {
+            compute_season_episode(
+                parsed,
+                parsed.episode_number,
+                args.season,
+                args.offset,
+                layout,
+            )
+        })
+        .map(|(season, _)| season)
+        .collect()
+}
+
+async fn fetch_episode_titles(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    client: &TmdbClient,
+    details: &TvDetails,
+    layout: &SeasonLayout,
+) -> HashMap<(u32, u32), String> {
+    let seasons = mapped_seasons(args, parsed_files, layout);
+    if seasons.is_empty() {
+        return HashMap::new();
+    }
+
+    status!("获取单集标题...");
+    match fetch_season_details_map(client, details.id, &seasons, &args.language).await {
+        Ok(season_details_map) => build_episode_titles(&season_details_map),
+        Err(error) => {
+            status!("获取单集标题失败，将省略标题: {error:#}");
+            HashMap::new()
+        }
+    }
+}
+
+fn build_tmdb_rename_map(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    details: &TvDetails,
+    layout: &SeasonLayout,
+    template: &Template,
+    episode_titles: HashMap<(u32, u32), String>,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<RenameEntry> {
+    let show = ShowInfo {
+        name: &details.name,
+        year: extract_year(details.first_air_date.as_deref()),
+        tmdb_id: Some(details.id),
+        episode_titles,
+    };
     let mut rename_map = Vec::new();
 
     for (file_path, parsed) in parsed_files {
-        let parent = file_path.parent().unwrap();
+        if parsed.episode_type.extras_folder().is_some() {
+            rename_map.push(build_extra_entry(args, file_path, parsed, &show, template));
+            continue;
+        }
+
+        let parent = target_parent(args, file_path);
         let (season, episode) = match compute_season_episode(
-            &parsed.episode_type,
+            parsed,
             parsed.episode_number,
-            parsed.season_number,
             args.season,
             args.offset,
-            &normal_seasons,
+            layout,
         ) {
             Some(result) => result,
             None => {
-                if parsed.episode_type == EpisodeType::Movie {
-                    println!("跳过剧场版: {}", display_file_name(file_path));
+                let reason = if parsed.episode_type == EpisodeType::Movie {
+                    status!("跳过剧场版: {}", display_file_name(file_path));
+                    SkipReason::Movie
                 } else {
                     let ep = apply_offset(parsed.episode_number, args.offset);
-                    println!("无法映射第 {ep} 集到任何季");
-                }
+                    status!("无法映射第 {ep} 集到任何季");
+                    SkipReason::UnmappedEpisode
+                };
+                skipped.push(SkippedFile {
+                    path: file_path.clone(),
+                    reason,
+                });
                 continue;
             }
         };
 
-        let new_name = build_output_name(
-            &details.name,
+        let episode_end = compute_episode_end(parsed, season, episode, args, layout);
+        let values = template_values(
+            &show,
+            parsed,
             season,
             episode,
-            &parsed.extension,
-            args.keep_tags,
-            &parsed.tags,
+            episode_end,
+            args.keep_tags.as_deref(),
+            args.offset,
         );
-        let new_path = build_rename_target(parent, &new_name, season, args.season_folders);
+        let new_path = build_rename_target(parent, template, &values, &parsed.extension);
 
-        rename_map.push((file_path.clone(), new_path, season, episode));
+        rename_map.push(RenameEntry {
+            source: file_path.clone(),
+            target: new_path,
+            season,
+            episode,
+            episode_end,
+            extra: None,
+            unaired: layout.is_unaired(season, episode),
+        });
     }
 
     rename_map
 }
 
-async fn rename_with_tmdb_id(
+/// 本次运行共用的元数据客户端，共享缓存与重试设置
+struct MetadataClients {
+    tmdb: TmdbClient,
+    anilist: AniListClient,
+}
+
+impl MetadataClients {
+    fn new(config: &Config, cache_mode: CacheMode) -> Result<Self> {
+        let cache = MetadataCache::open_default(&config.cache, cache_mode)?;
+        Ok(Self {
+            tmdb: TmdbClient::new()
+                .with_cache(cache.clone())
+                .with_retry(&config.retry),
+            anilist: AniListClient::new()
+                .with_cache(cache)
+                .with_retry(&config.retry),
+        })
+    }
+}
+
+async fn resolve_with_tmdb_id(
     args: &RenameArgs,
     parsed_files: &[ParsedEntry],
+    template: &Template,
+    skipped: &mut Vec<SkippedFile>,
+    client: &TmdbClient,
     tmdb_id: u32,
-) -> Result<()> {
-    println!("使用 TMDB ID: {tmdb_id}");
-    let client = TmdbClient::new();
+) -> Result<ResolvedRename> {
+    status!("使用 TMDB ID: {tmdb_id}");
 
     let details = client
         .get_tv_details(tmdb_id, &args.language)
         .await
         .context("通过 ID 获取详情失败")?;
 
-    println!("找到匹配: {} (TMDB ID: {})", details.name, tmdb_id);
-    println!("共 {} 季，开始分析集数映射...\n", details.number_of_seasons);
+    status!("找到匹配: {} (TMDB ID: {})", details.name, tmdb_id);
+
+    resolve_with_tmdb_details(args, parsed_files, template, skipped, client, &details).await
+}
+
+async fn resolve_with_tmdb_details(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    template: &Template,
+    skipped: &mut Vec<SkippedFile>,
+    client: &TmdbClient,
+    details: &TvDetails,
+) -> Result<ResolvedRename> {
+    status!("共 {} 季，开始分析集数映射...\n", details.number_of_seasons);
+
+    let (layout, episode_titles) = match &args.episode_group {
+        Some(selector) => {
+            let group = resolve_episode_group(client, details.id, selector, &args.language).await?;
+            let episode_titles = if args.episode_titles {
+                build_group_episode_titles(&group)
+            } else {
+                HashMap::new()
+            };
+            (SeasonLayout::from_episode_group(&group), episode_titles)
+        }
+        None => {
+            let layout = fetch_season_layout(args, parsed_files, client, details).await;
+            let episode_titles = if args.episode_titles {
+                fetch_episode_titles(args, parsed_files, client, details, &layout).await
+            } else {
+                HashMap::new()
+            };
+            (layout, episode_titles)
+        }
+    };
+
+    let entries = build_tmdb_rename_map(
+        args,
+        parsed_files,
+        details,
+        &layout,
+        template,
+        episode_titles,
+        skipped,
+    );
 
-    let rename_map = build_tmdb_rename_map(args, parsed_files, &details);
-    print_rename_preview(&rename_map, args.season_folders);
-    execute_rename(&rename_map, args.dry_run)
+    Ok(ResolvedRename {
+        matched: MatchSource {
+            provider: MatchProvider::Tmdb,
+            id: details.id,
+            name: details.name.clone(),
+        },
+        entries,
+    })
 }
 
-async fn rename_with_anilist(
+async fn resolve_with_anilist(
     args: &RenameArgs,
     parsed_files: &[ParsedEntry],
+    template: &Template,
+    anilist_client: &AniListClient,
     anime_name: &str,
-) -> Result<()> {
-    println!("按参数要求使用 AniList...");
+) -> Result<ResolvedRename> {
+    status!("按参数要求使用 AniList...");
 
-    let anilist_client = AniListClient::new();
     let anilist_results = anilist_client
         .search_anime(anime_name)
         .await
         .context("AniList 搜索失败")?;
 
-    if anilist_results.is_empty() {
-        println!("AniList 未找到匹配的番剧");
-        return Ok(());
-    }
+    let Some(anime) = anilist_results.first() else {
+        return Err(anyhow::Error::new(FailureKind::NoMatch))
+            .context(format!("AniList 未找到匹配的番剧: {anime_name}"));
+    };
 
-    let anime = &anilist_results[0];
-    let Some(display_name) = prompt_anilist_title(anime).await? else {
-        println!("未找到可用的标题");
-        return Ok(());
+    let Some(display_name) = prompt_anilist_title(anime, args.yes)? else {
+        return Err(anyhow::Error::new(FailureKind::NoMatch))
+            .context(format!("AniList 条目没有可用的标题 (ID: {})", anime.id));
+    };
+
+    status!("找到匹配: {} ({})", display_name, anime.format_date());
+    status!("\n注意: AniList 不提供季度信息，将使用文件名中的季度标记");
+    status!("如果文件名没有季度标记（如 'V', 'Season 5'），可能会映射错误\n");
+
+    let show = ShowInfo {
+        name: &display_name,
+        year: anime
+            .start_date
+            .as_ref()
+            .and_then(|date| date.year)
+            .and_then(|year| u32::try_from(year).ok()),
+        tmdb_id: None,
+        episode_titles: HashMap::new(),
     };
+    if args.episode_titles {
+        status!("AniList 不提供单集标题，将省略 {{episode_title}}");
+    }
+
+    Ok(ResolvedRename {
+        entries: build_anilist_rename_map(args, parsed_files, template, &show),
+        matched: MatchSource {
+            provider: MatchProvider::Anilist,
+            id: anime.id,
+            name: display_name,
+        },
+    })
+}
+
+async fn resolve_rename(
+    args: &RenameArgs,
+    parsed_files: &[ParsedEntry],
+    template: &Template,
+    skipped: &mut Vec<SkippedFile>,
+    clients: &MetadataClients,
+) -> Result<ResolvedRename> {
+    let anime_name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| parsed_files[0].1.anime_name.clone());
 
-    println!("找到匹配: {} ({})", display_name, anime.format_date());
-    println!("\n注意: AniList 不提供季度信息，将使用文件名中的季度标记");
-    println!("如果文件名没有季度标记（如 'V', 'Season 5'），可能会映射错误\n");
+    status!("检测到番剧: {anime_name}");
 
-    handle_anilist_renaming(args, parsed_files, &display_name)
+    // 文件所在路径包含扫描目录，因此也会识别扫描目录上的 [tmdbid=...]
+    let path_tmdb_id = parsed_files[0].0.parent().and_then(folder_tmdb_id);
+    if let Some(id) = args.tmdb_id.or(path_tmdb_id) {
+        return resolve_with_tmdb_id(args, parsed_files, template, skipped, &clients.tmdb, id)
+            .await;
+    }
+
+    if args.use_anilist {
+        return resolve_with_anilist(args, parsed_files, template, &clients.anilist, &anime_name)
+            .await;
+    }
+
+    let client = &clients.tmdb;
+    status!("搜索 TMDB...");
+
+    let year = parsed_files.iter().find_map(|(_, parsed)| parsed.year);
+    let Some(details) =
+        select_tmdb_show(client, &anime_name, year, &args.language, args.yes).await?
+    else {
+        status!("TMDB 未找到结果，尝试 AniList...");
+        return resolve_with_anilist(args, parsed_files, template, &clients.anilist, &anime_name)
+            .await;
+    };
+
+    resolve_with_tmdb_details(args, parsed_files, template, skipped, client, &details).await
 }
 
-pub(crate) async fn run(args: &RenameArgs) -> Result<()> {
+/// 扫描目录并生成重命名映射，不修改任何文件
+pub(crate) async fn build_rename_batch(args: &RenameArgs) -> Result<RenameBatch> {
     let path = args.path.as_str();
+    let config = Config::load()?;
+    let template = resolve_template(args, &config)?;
+    let clients = MetadataClients::new(&config, args.cache_mode)?;
 
-    println!("扫描目录: {path}");
+    status!("扫描目录: {path}");
 
     let scanner = FileScanner::new(args.recursive);
     let files = scanner.scan(path);
+    let mut batch = RenameBatch {
+        mode: args.mode,
+        shows: Vec::new(),
+        skipped: Vec::new(),
+        consensus: Vec::new(),
+    };
 
     if files.is_empty() {
-        println!("未找到视频文件");
-        return Ok(());
+        status!("未找到视频文件");
+        return Ok(batch);
     }
 
-    println!("找到 {} 个视频文件\n", files.len());
+    status!("找到 {} 个视频文件\n", files.len());
 
-    let parser = FileParser::new();
-    let parsed_files = collect_rename_candidates(&files, &parser);
+    let parser = FileParser::with_config(&config.parser)?;
+    let parsed_files =
+        collect_rename_candidates(&files, &parser, &mut batch.skipped, &mut batch.consensus);
 
     if parsed_files.is_empty() {
-        println!("没有可解析的文件");
-        return Ok(());
+        if batch
+            .skipped
+            .iter()
+            .all(|file| file.reason == SkipReason::AlreadyFormatted)
+        {
+            status!("没有需要重命名的文件");
+            return Ok(batch);
+        }
+        return Err(anyhow::Error::new(FailureKind::ParseFailure))
+            .context(format!("{} 个文件均无法解析", batch.skipped.len()));
     }
 
-    let anime_name = args
-        .name
-        .clone()
-        .unwrap_or_else(|| parsed_files[0].1.anime_name.clone());
-
-    println!("检测到番剧: {anime_name}");
+    let parsed_files = if args.verify_crc {
+        verify_checksums(args, parsed_files, &mut batch.skipped)
+    } else {
+        parsed_files
+    };
 
-    if let Some(id) = args.tmdb_id.or_else(|| extract_tmdb_id(path)) {
-        return rename_with_tmdb_id(args, &parsed_files, id).await;
+    // 手动指定番剧名或 TMDB ID 时，所有文件视为同一部番剧
+    let groups = if args.name.is_some() || args.tmdb_id.is_some() {
+        vec![parsed_files]
+    } else {
+        group_by_show(parsed_files, args.recursive)
+    };
+    if groups.len() > 1 {
+        status!("检测到 {} 部番剧，将分别匹配\n", groups.len());
     }
 
-    if args.use_anilist {
-        return rename_with_anilist(args, &parsed_files, &anime_name).await;
+    let group_count = groups.len();
+    let mut last_no_match = None;
+    for group in groups {
+        match resolve_rename(args, &group, &template, &mut batch.skipped, &clients).await {
+            Ok(resolved) => batch.shows.push(resolved),
+            Err(error)
+                if group_count > 1 && FailureKind::of(&error) == Some(FailureKind::NoMatch) =>
+            {
+                status!("跳过 {} 个文件: {error:#}\n", group.len());
+                batch
+                    .skipped
+                    .extend(group.iter().map(|(path, _)| SkippedFile {
+                        path: path.clone(),
+                        reason: SkipReason::NoMatch,
+                    }));
+                last_no_match = Some(error);
+            }
+            Err(error) => return Err(error),
+        }
     }
 
-    let client = TmdbClient::new();
-    println!("搜索 TMDB...");
-
-    let results = client
-        .search_tv(&anime_name, &args.language)
-        .await
-        .context("搜索失败")?;
-
-    if results.is_empty() {
-        println!("TMDB 未找到结果，尝试 AniList...");
-        return rename_with_anilist(args, &parsed_files, &anime_name).await;
+    if batch.shows.is_empty()
+        && let Some(error) = last_no_match
+    {
+        return Err(error);
     }
 
-    let tv_show = &results[0];
-    println!(
-        "找到匹配: {} ({})",
-        tv_show.name,
-        tv_show.first_air_date.as_deref().unwrap_or("未知")
-    );
-
-    let details = client
-        .get_tv_details(tv_show.id, &args.language)
-        .await
-        .context("获取详情失败")?;
+    Ok(batch)
+}
 
-    println!("共 {} 季，开始分析集数映射...\n", details.number_of_seasons);
+pub(crate) async fn run(args: &RenameArgs) -> Result<()> {
+    output::set_format(args.output);
 
-    let rename_map = build_tmdb_rename_map(args, &parsed_files, &details);
-    print_rename_preview(&rename_map, args.season_folders);
-    execute_rename(&rename_map, args.dry_run)
+    let batch = build_rename_batch(args).await?;
+    batch.print_preview();
+    execute_rename(args, &batch)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ReleaseInfo;
 
     fn make_season(season_number: u32, episode_count: u32) -> Season {
         Season {
@@ -698,6 +1603,63 @@ mod tests {
         }
     }
 
+    fn make_parsed(
+        episode_type: EpisodeType,
+        season_number: Option<u32>,
+        part: Option<u32>,
+    ) -> ParsedFile {
+        ParsedFile {
+            anime_name: "Show".to_string(),
+            episode_number: 1,
+            episode_end: None,
+            episode_fraction: None,
+            version: None,
+            season_number,
+            part,
+            final_season: false,
+            episode_type,
+            tags: Vec::new(),
+            release: ReleaseInfo::default(),
+            year: None,
+            extension: "mkv".to_string(),
+            is_already_formatted: false,
+        }
+    }
+
+    fn layout(normal_seasons: Vec<Season>) -> SeasonLayout {
+        SeasonLayout {
+            normal_seasons,
+            cours: HashMap::new(),
+            aired: HashMap::new(),
+        }
+    }
+
+    fn season_details(season_number: u32, episodes: Vec<Episode>) -> SeasonDetails {
+        SeasonDetails {
+            id: season_number,
+            name: String::new(),
+            season_number,
+            overview: None,
+            air_date: None,
+            poster_path: None,
+            episodes,
+        }
+    }
+
+    fn episode(episode_number: u32, air_date: Option<&str>) -> Episode {
+        Episode {
+            id: episode_number,
+            season_number: 1,
+            episode_number,
+            name: String::new(),
+            still_path: None,
+            air_date: air_date.map(str::to_string),
+            overview: None,
+            vote_average: 0.0,
+            vote_count: 0,
+        }
+    }
+
     #[test]
     fn test_apply_offset_never_drops_below_one() {
         assert_eq!(apply_offset(1, -10), 1);
@@ -715,7 +1677,13 @@ mod tests {
     fn test_compute_season_episode_prefers_explicit_season_arg() {
         let seasons = vec![make_season(1, 12), make_season(2, 12)];
 
-        let result = compute_season_episode(&EpisodeType::Normal, 5, Some(1), Some(3), 0, &seasons);
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, Some(1), None),
+            5,
+            Some(3),
+            0,
+            &layout(seasons),
+        );
 
         assert_eq!(result, Some((3, 5)));
     }
@@ -724,7 +1692,13 @@ mod tests {
     fn test_compute_season_episode_uses_parsed_season_when_present() {
         let seasons = vec![make_season(1, 12), make_season(2, 12)];
 
-        let result = compute_season_episode(&EpisodeType::Normal, 7, Some(2), None, 0, &seasons);
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, Some(2), None),
+            7,
+            None,
+            0,
+            &layout(seasons),
+        );
 
         assert_eq!(result, Some((2, 7)));
     }
@@ -733,86 +1707,686 @@ mod tests {
     fn test_compute_season_episode_maps_absolute_episode_across_seasons() {
         let seasons = vec![make_season(1, 12), make_season(2, 12)];
 
-        let result = compute_season_episode(&EpisodeType::Normal, 14, None, None, 0, &seasons);
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, None, None),
+            14,
+            None,
+            0,
+            &layout(seasons),
+        );
 
         assert_eq!(result, Some((2, 2)));
     }
 
+    #[test]
+    fn test_episode_group_layout_maps_absolute_numbers_through_groups() {
+        let group: EpisodeGroupDetails = serde_json::from_value(serde_json::json!({
+            "id": "5b11b4ab925141355f009c2d",
+            "name": "Story Arcs",
+            "groups": [
+                {"name": "Specials", "order": 0, "episodes": [
+                    {"order": 0, "id": 1, "season_number": 0, "episode_number": 1, "name": "Special"},
+                ]},
+                {"name": "East Blue", "order": 1, "episodes": [
+                    {"order": 0, "id": 2, "season_number": 1, "episode_number": 1, "name": "Romance Dawn"},
+                    {"order": 1, "id": 3, "season_number": 1, "episode_number": 2, "name": "Episode 2"},
+                ]},
+                {"name": "Alabasta", "order": 2, "episodes": [
+                    {"order": 0, "id": 4, "season_number": 1, "episode_number": 3, "name": "Reverse Mountain"},
+                ]},
+            ]
+        }))
+        .unwrap();
+        let layout = SeasonLayout::from_episode_group(&group);
+        let parsed = make_parsed(EpisodeType::Normal, None, None);
+
+        assert_eq!(
+            compute_season_episode(&parsed, 3, None, 0, &layout),
+            Some((2, 1))
+        );
+        assert_eq!(
+            build_group_episode_titles(&group),
+            HashMap::from([
+                ((0, 1), "Special".to_string()),
+                ((1, 1), "Romance Dawn".to_string()),
+                ((2, 1), "Reverse Mountain".to_string()),
+            ])
+        );
+    }
+
     #[test]
     fn test_compute_season_episode_maps_specials_to_season_zero() {
         let seasons = vec![make_season(1, 12)];
 
-        let result = compute_season_episode(&EpisodeType::OVA, 3, None, None, -1, &seasons);
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::OVA, None, None),
+            3,
+            None,
+            -1,
+            &layout(seasons),
+        );
 
         assert_eq!(result, Some((0, 2)));
     }
 
+    #[test]
+    fn test_compute_season_episode_maps_fractional_episodes_to_season_zero() {
+        let seasons = vec![make_season(1, 24)];
+        let mut recap = make_parsed(EpisodeType::Normal, Some(1), None);
+        recap.episode_fraction = Some(5);
+
+        let result = compute_season_episode(&recap, 13, None, 0, &layout(seasons));
+
+        assert_eq!(result, Some((0, 13)));
+    }
+
     #[test]
     fn test_compute_season_episode_returns_none_for_movie() {
         let seasons = vec![make_season(1, 12)];
 
-        let result = compute_season_episode(&EpisodeType::Movie, 1, None, None, 0, &seasons);
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Movie, None, None),
+            1,
+            None,
+            0,
+            &layout(seasons),
+        );
 
         assert_eq!(result, None);
     }
 
     #[test]
-    fn test_validate_rename_operations_rejects_duplicate_targets() {
-        let operations = vec![
-            RenameOperation {
-                source: PathBuf::from("/tmp/a.mkv"),
-                target: PathBuf::from("/tmp/output.mkv"),
-                kind: RenameKind::Video,
-            },
-            RenameOperation {
-                source: PathBuf::from("/tmp/b.mkv"),
-                target: PathBuf::from("/tmp/output.mkv"),
-                kind: RenameKind::Video,
-            },
-        ];
+    fn test_compute_season_episode_offsets_part_into_season() {
+        let mut layout = layout(vec![make_season(1, 25), make_season(2, 12)]);
+        layout.cours.insert(1, vec![(1, 13), (14, 12)]);
+
+        let restarted = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, Some(1), Some(2)),
+            3,
+            None,
+            0,
+            &layout,
+        );
+        let continuous = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, Some(1), Some(2)),
+            16,
+            None,
+            0,
+            &layout,
+        );
+        let part_only = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, None, Some(2)),
+            3,
+            None,
+            0,
+            &layout,
+        );
 
-        let error = validate_rename_operations(&operations).unwrap_err();
+        assert_eq!(restarted, Some((1, 16)));
+        assert_eq!(continuous, Some((1, 16)));
+        assert_eq!(part_only, Some((1, 16)));
+    }
 
-        assert!(error.to_string().contains("同一目标"));
+    #[test]
+    fn test_compute_season_episode_moves_split_part_to_next_season() {
+        let mut layout = layout(vec![make_season(1, 12), make_season(2, 12)]);
+        layout.cours.insert(1, vec![(1, 12)]);
+
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Normal, Some(1), Some(2)),
+            4,
+            None,
+            0,
+            &layout,
+        );
+
+        assert_eq!(result, Some((2, 4)));
     }
 
     #[test]
-    fn test_execute_rename_operations_handles_swapped_names() {
-        let dir = std::env::temp_dir().join(format!(
-            "rename_swap_{}_{}",
-            std::process::id(),
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_nanos()
-        ));
-        std::fs::create_dir_all(&dir).unwrap();
-
-        let first = dir.join("first.mkv");
-        let second = dir.join("second.mkv");
-        std::fs::write(&first, b"one").unwrap();
-        std::fs::write(&second, b"two").unwrap();
-
-        let operations = vec![
-            RenameOperation {
-                source: first.clone(),
-                target: second.clone(),
-                kind: RenameKind::Video,
-            },
-            RenameOperation {
-                source: second.clone(),
-                target: first.clone(),
-                kind: RenameKind::Video,
-            },
+    fn test_parsed_season_maps_final_season_to_last_tmdb_season() {
+        let layout = layout(vec![
+            make_season(1, 25),
+            make_season(2, 12),
+            make_season(3, 0),
+        ]);
+        let parser = FileParser::new();
+
+        let final_season = parser
+            .parse("Shingeki no Kyojin The Final Season - 05.mkv")
+            .unwrap();
+        let explicit = parser.parse("Shingeki no Kyojin S04 - 05.mkv").unwrap();
+
+        assert_eq!(parsed_season(&final_season, &layout), Some(2));
+        assert_eq!(parsed_season(&explicit, &layout), Some(4));
+    }
+
+    #[test]
+    fn test_map_absolute_skips_unaired_episodes_before_next_season() {
+        let mut layout = layout(vec![make_season(1, 13), make_season(2, 12)]);
+        let today = days_from_date("2024-07-01").unwrap();
+        let season_1 = season_details(
+            1,
+            (1..=12)
+                .map(|number| episode(number, Some("2024-01-05")))
+                .chain([episode(13, None)])
+                .collect(),
+        );
+        let season_2 = season_details(
+            2,
+            vec![
+                episode(1, Some("2024-06-20")),
+                episode(2, Some("2024-06-27")),
+                episode(3, Some("2024-07-04")),
+            ],
+        );
+        layout.record_air_dates(&season_1, today);
+        layout.record_air_dates(&season_2, today);
+        let parsed = make_parsed(EpisodeType::Normal, None, None);
+
+        assert_eq!(
+            compute_season_episode(&parsed, 13, None, 0, &layout),
+            Some((2, 1))
+        );
+        assert_eq!(
+            compute_season_episode(&parsed, 15, None, 0, &layout),
+            Some((2, 3))
+        );
+        assert!(!layout.is_unaired(2, 1));
+        assert!(layout.is_unaired(2, 3));
+        assert!(layout.is_unaired(1, 13));
+    }
+
+    #[test]
+    fn test_map_absolute_follows_aired_episode_numbers() {
+        let mut layout = layout(vec![make_season(1, 12), make_season(2, 12)]);
+        let today = days_from_date("2024-07-01").unwrap();
+        // 第 7 集延期，TMDB 尚未给出播出日期
+        let season_1 = season_details(
+            1,
+            (1..=8)
+                .map(|number| episode(number, (number != 7).then_some("2024-01-05")))
+                .collect(),
+        );
+        layout.record_air_dates(&season_1, today);
+        layout.record_air_dates(
+            &season_details(2, vec![episode(1, Some("2024-06-20"))]),
+            today,
+        );
+
+        assert_eq!(layout.map_absolute(6), Some((1, 6)));
+        assert_eq!(layout.map_absolute(7), Some((1, 8)));
+        assert_eq!(layout.map_absolute(8), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_seasons_covering_uses_aired_counts_once_known() {
+        let mut layout = layout(vec![
+            make_season(1, 12),
+            make_season(2, 12),
+            make_season(3, 12),
+        ]);
+
+        assert_eq!(layout.seasons_covering(10), BTreeSet::from([1]));
+        assert_eq!(layout.seasons_covering(13), BTreeSet::from([1, 2]));
+
+        let today = days_from_date("2024-07-01").unwrap();
+        let season_1 = season_details(
+            1,
+            (1..=12)
+                .map(|number| episode(number, (number <= 8).then_some("2024-01-05")))
+                .collect(),
+        );
+        layout.record_air_dates(&season_1, today);
+
+        assert_eq!(layout.seasons_covering(10), BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn test_map_absolute_keeps_full_count_when_no_later_season_aired() {
+        let mut layout = layout(vec![make_season(1, 12), make_season(2, 12)]);
+        let today = days_from_date("2024-03-01").unwrap();
+        let season_1 = season_details(
+            1,
+            (1..=12)
+                .map(|number| episode(number, (number <= 8).then_some("2024-01-05")))
+                .collect(),
+        );
+        layout.record_air_dates(&season_1, today);
+        layout.record_air_dates(&season_details(2, Vec::new()), today);
+
+        assert_eq!(layout.map_absolute(10), Some((1, 10)));
+        assert!(layout.is_unaired(1, 10));
+    }
+
+    #[test]
+    fn test_split_cours_breaks_on_long_air_date_gap() {
+        let season = SeasonDetails {
+            id: 1,
+            name: String::new(),
+            season_number: 1,
+            overview: None,
+            air_date: None,
+            poster_path: None,
+            episodes: vec![
+                episode(1, Some("2022-10-01")),
+                episode(2, Some("2022-10-08")),
+                episode(3, Some("2022-10-15")),
+                episode(4, Some("2023-04-08")),
+                episode(5, Some("2023-04-15")),
+            ],
+        };
+
+        assert_eq!(split_cours(&season), vec![(1, 3), (4, 2)]);
+    }
+
+    #[test]
+    fn test_days_from_date() {
+        assert_eq!(days_from_date("1970-01-01"), Some(0));
+        assert_eq!(days_from_date("2000-03-01"), Some(11_017));
+        assert_eq!(days_from_date("2024-02-29"), Some(19_782));
+        assert_eq!(days_from_date("2024-13-01"), None);
+    }
+
+    fn make_args() -> RenameArgs {
+        RenameArgs {
+            path: "/tmp/show".to_string(),
+            recursive: false,
+            dry_run: true,
+            name: None,
+            language: "zh-CN".to_string(),
+            keep_tags: None,
+            season_folders: false,
+            template: None,
+            episode_titles: false,
+            use_anilist: false,
+            season: None,
+            offset: 0,
+            tmdb_id: None,
+            episode_group: None,
+            yes: false,
+            output: output::OutputFormat::Text,
+            mode: RenameMode::Rename,
+            output_dir: None,
+            verify_crc: false,
+            skip_corrupted: false,
+            cache_mode: CacheMode::Normal,
+        }
+    }
+
+    #[test]
+    fn test_compute_episode_end_keeps_ranges_within_one_season() {
+        let layout = layout(vec![make_season(1, 12), make_season(2, 12)]);
+        let args = make_args();
+        let parser = FileParser::new();
+
+        let within = parser.parse("Show - 13-14.mkv").unwrap();
+        assert_eq!(compute_episode_end(&within, 2, 1, &args, &layout), Some(2));
+
+        let across = parser.parse("Show - 12-13.mkv").unwrap();
+        assert_eq!(compute_episode_end(&across, 1, 12, &args, &layout), None);
+
+        let single = parser.parse("Show - 12.mkv").unwrap();
+        assert_eq!(compute_episode_end(&single, 1, 12, &args, &layout), None);
+    }
+
+    #[test]
+    fn test_resolve_template_prefers_cli_over_config() {
+        let mut args = make_args();
+        args.template = Some("{show} - {episode:02}".to_string());
+        let mut config = Config::default();
+        config.rename.template = Some("{show} E{episode}".to_string());
+
+        assert_eq!(
+            resolve_template(&args, &config).unwrap().as_str(),
+            "{show} - {episode:02}"
+        );
+
+        args.template = None;
+        assert_eq!(
+            resolve_template(&args, &config).unwrap().as_str(),
+            "{show} E{episode}"
+        );
+    }
+
+    #[test]
+    fn test_resolve_template_adds_season_folder_prefix() {
+        let mut args = make_args();
+        args.season_folders = true;
+
+        let template = resolve_template(&args, &Config::default()).unwrap();
+        assert_eq!(
+            template.as_str(),
+            format!("{SEASON_FOLDER_PREFIX}{DEFAULT_TEMPLATE}")
+        );
+
+        args.template = Some("{show}/S{season:02}/{episode:02}".to_string());
+        let template = resolve_template(&args, &Config::default()).unwrap();
+        assert_eq!(template.as_str(), "{show}/S{season:02}/{episode:02}");
+    }
+
+    #[test]
+    fn test_build_rename_target_renders_template_with_extension() {
+        let template =
+            Template::parse("{show}/Season {season}/{show} S{season:02}E{episode:02}").unwrap();
+        let values = TemplateValues {
+            show: "Frieren".to_string(),
+            season: 1,
+            episode: 3,
+            ..TemplateValues::default()
+        };
+
+        let target = build_rename_target(Path::new("/media"), &template, &values, "mkv");
+
+        assert_eq!(
+            target,
+            PathBuf::from("/media/Frieren/Season 1/Frieren S01E03.mkv")
+        );
+    }
+
+    #[test]
+    fn test_build_extra_entry_moves_extras_next_to_show_directory() {
+        let show = ShowInfo {
+            name: "Frieren",
+            year: None,
+            tmdb_id: None,
+            episode_titles: HashMap::new(),
+        };
+        let mut args = make_args();
+        args.output_dir = Some(PathBuf::from("/library"));
+        let mut parsed = make_parsed(EpisodeType::NCOP, None, None);
+        parsed.episode_number = 2;
+
+        let nested =
+            Template::parse("{show}/Season {season}/{show} S{season:02}E{episode:02}").unwrap();
+        let entry = build_extra_entry(
+            &args,
+            Path::new("/downloads/[Group] Frieren NCOP2.mkv"),
+            &parsed,
+            &show,
+            &nested,
+        );
+        assert_eq!(
+            entry.target,
+            PathBuf::from("/library/Frieren/Extras/Frieren - NCOP2.mkv")
+        );
+        assert_eq!(entry.extra, Some(EpisodeType::NCOP));
+
+        args.output_dir = None;
+        parsed.episode_type = EpisodeType::PV;
+        let flat = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        for source in [
+            "/media/Frieren/Season 01/Frieren PV2.mkv",
+            "/media/Frieren/Trailers/Frieren - PV2.mkv",
+        ] {
+            let entry = build_extra_entry(&args, Path::new(source), &parsed, &show, &flat);
+            assert_eq!(
+                entry.target,
+                PathBuf::from("/media/Frieren/Trailers/Frieren - PV2.mkv"),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_season_episode_returns_none_for_extras() {
+        let result = compute_season_episode(
+            &make_parsed(EpisodeType::Menu, Some(1), None),
+            1,
+            None,
+            0,
+            &layout(vec![make_season(1, 12)]),
+        );
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_is_placeholder_episode_title_detects_generic_names() {
+        for title in [
+            "Episode 3",
+            "episode #12",
+            "Ep. 4",
+            "第 3 集",
+            "第3話",
+            "第12话",
+            "7",
+        ] {
+            assert!(is_placeholder_episode_title(title), "{title}");
+        }
+
+        for title in ["Episode of the Hero", "旅立ちの日", "第3集的秘密"] {
+            assert!(!is_placeholder_episode_title(title), "{title}");
+        }
+    }
+
+    #[test]
+    fn test_build_episode_titles_skips_placeholders_and_blank_names() {
+        let make_episode = |episode_number: u32, name: &str| Episode {
+            id: episode_number,
+            season_number: 1,
+            episode_number,
+            name: name.to_string(),
+            still_path: None,
+            air_date: None,
+            overview: None,
+            vote_average: 0.0,
+            vote_count: 0,
+        };
+        let season = SeasonDetails {
+            id: 1,
+            name: "Season 1".to_string(),
+            season_number: 1,
+            overview: None,
+            air_date: None,
+            poster_path: None,
+            episodes: vec![
+                make_episode(1, "冒険の終わり"),
+                make_episode(2, "Episode 2"),
+                make_episode(3, "  "),
+            ],
+        };
+
+        let titles = build_episode_titles(&HashMap::from([(1, season)]));
+
+        assert_eq!(titles.len(), 1);
+        assert_eq!(
+            titles.get(&(1, 1)).map(String::as_str),
+            Some("冒険の終わり")
+        );
+    }
+
+    #[test]
+    fn test_default_template_appends_sanitized_episode_title() {
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        let mut values = TemplateValues {
+            show: "Show".to_string(),
+            season: 1,
+            episode: 3,
+            ..TemplateValues::default()
+        };
+
+        let target = build_rename_target(Path::new("/media"), &template, &values, "mkv");
+        assert_eq!(target, PathBuf::from("/media/Show S01E03.mkv"));
+
+        values.episode_title = Some("Who Are You?".to_string());
+        let target = build_rename_target(Path::new("/media"), &template, &values, "mkv");
+        assert_eq!(
+            target,
+            PathBuf::from("/media/Show S01E03 - Who Are You？.mkv")
+        );
+    }
+
+    #[test]
+    fn test_collect_rename_candidates_records_skip_reasons() {
+        let files = vec![
+            PathBuf::from("/media/[Group] Show - 01 [1080p].mkv"),
+            PathBuf::from("/media/Show S01E02.mkv"),
+            PathBuf::from("/media/README.mkv"),
+            PathBuf::from("/media/[Group] Show - 03 [1080p].mkv"),
+            PathBuf::from("/media/[Group] Show - 03v2 [1080p].mkv"),
         ];
+        let mut skipped = Vec::new();
+        let mut consensus = Vec::new();
+
+        let parsed =
+            collect_rename_candidates(&files, &FileParser::new(), &mut skipped, &mut consensus);
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].0, files[4]);
+        assert_eq!(
+            skipped,
+            vec![
+                SkippedFile {
+                    path: files[1].clone(),
+                    reason: SkipReason::AlreadyFormatted,
+                },
+                SkippedFile {
+                    path: files[2].clone(),
+                    reason: SkipReason::ParseFailed,
+                },
+                SkippedFile {
+                    path: files[3].clone(),
+                    reason: SkipReason::SupersededVersion,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keep_latest_versions_matches_show_episode_range_and_directory() {
+        let files = [
+            PathBuf::from("/media/A/[Group] Show - 05 [1080p].mkv"),
+            PathBuf::from("/media/B/[Other] show - 05v2 [720p].mkv"),
+            PathBuf::from("/media/B/[Other] Show! - 06 [720p].mkv"),
+            PathBuf::from("/media/B/[Group] Show - 06v2 [1080p].mkv"),
+            PathBuf::from("/media/B/[Group] Show - 01-02 [1080p].mkv"),
+            PathBuf::from("/media/B/[Group] Show - 01v2 [1080p].mkv"),
+        ];
+        let parser = FileParser::new();
+        let entries = files
+            .iter()
+            .map(|file| (file.clone(), parser.parse_path(file).unwrap()))
+            .collect();
+        let mut skipped = Vec::new();
+
+        let kept: Vec<PathBuf> = keep_latest_versions(entries, &mut skipped)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+
+        assert_eq!(
+            kept,
+            vec![
+                files[0].clone(),
+                files[1].clone(),
+                files[3].clone(),
+                files[4].clone(),
+                files[5].clone(),
+            ]
+        );
+        assert_eq!(
+            skipped,
+            vec![SkippedFile {
+                path: files[2].clone(),
+                reason: SkipReason::SupersededVersion,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_collect_rename_candidates_compares_files_in_the_same_directory() {
+        let files = vec![
+            PathBuf::from("/media/mob/Mob Psycho 100 05.mkv"),
+            PathBuf::from("/media/mob/Mob Psycho 100 06.mkv"),
+            PathBuf::from("/media/other/Mob Psycho 100 07.mkv"),
+        ];
+        let mut skipped = Vec::new();
+        let mut consensus = Vec::new();
 
-        let (video_success, subtitle_success) = execute_rename_operations(&operations).unwrap();
+        let parsed =
+            collect_rename_candidates(&files, &FileParser::new(), &mut skipped, &mut consensus);
 
-        assert_eq!(video_success, 2);
-        assert_eq!(subtitle_success, 0);
-        assert_eq!(std::fs::read(&first).unwrap(), b"two");
-        assert_eq!(std::fs::read(&second).unwrap(), b"one");
+        let episodes: Vec<u32> = parsed
+            .iter()
+            .map(|(_, parsed)| parsed.episode_number)
+            .collect();
+        assert_eq!(episodes, vec![5, 6, 100]);
+        assert_eq!(
+            consensus,
+            vec![
+                ConsensusNote {
+                    path: files[0].clone(),
+                    consensus: Consensus::Corrected {
+                        isolated_episode: 100
+                    },
+                },
+                ConsensusNote {
+                    path: files[1].clone(),
+                    consensus: Consensus::Corrected {
+                        isolated_episode: 100
+                    },
+                },
+            ]
+        );
+    }
 
-        std::fs::remove_dir_all(&dir).unwrap();
+    #[test]
+    fn test_rename_plan_serializes_shows_and_skipped_files() {
+        let batch = RenameBatch {
+            mode: RenameMode::Rename,
+            shows: vec![ResolvedRename {
+                matched: MatchSource {
+                    provider: MatchProvider::Tmdb,
+                    id: 42,
+                    name: "Show".to_string(),
+                },
+                entries: vec![RenameEntry {
+                    source: PathBuf::from("/nonexistent/Show - 01.mkv"),
+                    target: PathBuf::from("/nonexistent/Show S01E01.mkv"),
+                    season: 1,
+                    episode: 1,
+                    episode_end: None,
+                    extra: None,
+                    unaired: true,
+                }],
+            }],
+            skipped: vec![
+                SkippedFile {
+                    path: PathBuf::from("/nonexistent/Show Movie.mkv"),
+                    reason: SkipReason::Movie,
+                },
+                SkippedFile {
+                    path: PathBuf::from("/nonexistent/Unknown - 01.mkv"),
+                    reason: SkipReason::NoMatch,
+                },
+            ],
+            consensus: Vec::new(),
+        };
+
+        let value = serde_json::to_value(batch.plan(true)).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "dry_run": true,
+                "shows": [{
+                    "match": {"provider": "tmdb", "id": 42, "name": "Show"},
+                    "operations": [{
+                        "source": "/nonexistent/Show - 01.mkv",
+                        "target": "/nonexistent/Show S01E01.mkv",
+                        "kind": "video",
+                        "mode": "rename",
+                        "season": 1,
+                        "episode": 1,
+                        "unaired": true,
+                    }],
+                }],
+                "skipped": [
+                    {"path": "/nonexistent/Show Movie.mkv", "reason": "movie"},
+                    {"path": "/nonexistent/Unknown - 01.mkv", "reason": "no_match"},
+                ],
+            })
+        );
     }
 }
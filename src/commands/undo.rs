@@ -0,0 +1,135 @@
+use crate::cli::UndoArgs;
+use crate::operations::journal::{JournalEntry, JournalStore};
+use crate::operations::{
+    RenameKind, execute_rename_operations, remove_created_dirs, remove_created_files,
+    validate_rename_operations,
+};
+use crate::output::{self, status};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn print_journal_list(store: &JournalStore) -> Result<()> {
+    let entries = store.list()?;
+
+    if entries.is_empty() {
+        println!("没有可撤销的重命名记录");
+        return Ok(());
+    }
+
+    println!("重命名记录（{}）:\n", store.dir().display());
+    for entry in entries.iter().rev() {
+        let videos = entry
+            .operations
+            .iter()
+            .filter(|operation| operation.kind == RenameKind::Video)
+            .count();
+        let subtitles = entry.operations.len() - videos;
+        println!(
+            "  {}  {}  视频 {videos} 个，字幕 {subtitles} 个",
+            entry.id,
+            entry.root.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_undo_preview(entry: &JournalEntry) {
+    println!(
+        "撤销预览 (ID: {}, 目录: {}):\n",
+        entry.id,
+        entry.root.display()
+    );
+
+    for (i, operation) in entry.undo_operations().iter().enumerate() {
+        println!("[{}]", i + 1);
+        println!(
+            "  当前文件: {}",
+            display_path(&operation.source, &entry.root)
+        );
+        println!("  恢复为: {}", display_path(&operation.target, &entry.root));
+        println!();
+    }
+
+    let created_files = entry.created_files();
+    if !created_files.is_empty() {
+        println!("将删除复制或链接生成的 {} 个文件:", created_files.len());
+        for file in &created_files {
+            let note = if file.source_unchanged() {
+                ""
+            } else {
+                "（源文件已不存在或已改动，将保留）"
+            };
+            println!("  {}{note}", display_path(&file.path, &entry.root));
+        }
+        println!();
+    }
+}
+
+fn resolve_entry(store: &JournalStore, args: &UndoArgs) -> Result<Option<JournalEntry>> {
+    match args.id.as_deref() {
+        Some(id) => store.load(id).map(Some),
+        None => store.latest(),
+    }
+}
+
+pub(crate) fn run(args: &UndoArgs) -> Result<()> {
+    let store = JournalStore::open_default()?;
+
+    if args.list {
+        return print_journal_list(&store);
+    }
+
+    let Some(entry) = resolve_entry(&store, args)? else {
+        println!("没有可撤销的重命名记录");
+        return Ok(());
+    };
+
+    print_undo_preview(&entry);
+
+    let operations = entry.undo_operations();
+    validate_rename_operations(&operations).context("无法撤销，文件状态已发生变化")?;
+
+    if args.dry_run {
+        println!("预览模式，未实际撤销");
+        return Ok(());
+    }
+
+    if !args.yes {
+        let input = output::prompt("继续撤销？[Y/n] ")?;
+        if !input.is_empty() && !input.eq_ignore_ascii_case("y") {
+            status!("已取消");
+            return Ok(());
+        }
+    }
+
+    let summary = execute_rename_operations(&operations)?;
+    let removed_files = remove_created_files(&entry.created_files())?;
+    for file in &removed_files.kept {
+        status!(
+            "警告: 源文件已不存在或已改动，保留复制或链接生成的文件: {}",
+            display_path(file, &entry.root)
+        );
+    }
+    remove_created_dirs(&entry.created_dirs);
+    store.remove(&entry.id)?;
+
+    if !operations.is_empty() {
+        println!("\n成功恢复 {} 个视频文件", summary.video_success);
+    }
+    if summary.subtitle_success > 0 {
+        println!("成功恢复 {} 个字幕文件", summary.subtitle_success);
+    }
+    if removed_files.removed > 0 {
+        println!("已删除 {} 个复制或链接生成的文件", removed_files.removed);
+    }
+
+    Ok(())
+}
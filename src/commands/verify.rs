@@ -0,0 +1,120 @@
+use crate::checksum::crc32_file;
+use crate::cli::VerifyArgs;
+use crate::error::FailureKind;
+use crate::parser::extract_crc32;
+use crate::scanner::FileScanner;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// 单个文件的 CRC32 校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CrcCheck {
+    /// 文件名中没有 CRC32 标签
+    Missing,
+    Passed,
+    Mismatch {
+        expected: u32,
+        actual: u32,
+    },
+}
+
+/// 流式计算文件的 CRC32，并与文件名中的 `[ABCD1234]` 校验值比较
+pub(crate) fn check_file(path: &Path) -> Result<CrcCheck> {
+    let Some(expected) = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(extract_crc32)
+    else {
+        return Ok(CrcCheck::Missing);
+    };
+
+    let (_, actual) =
+        crc32_file(path).with_context(|| format!("无法读取文件: {}", path.display()))?;
+    if actual == expected {
+        Ok(CrcCheck::Passed)
+    } else {
+        Ok(CrcCheck::Mismatch { expected, actual })
+    }
+}
+
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+pub(crate) fn run(args: &VerifyArgs) -> Result<()> {
+    let root = Path::new(&args.path);
+    let files = FileScanner::new(args.recursive).scan(&args.path);
+
+    if files.is_empty() {
+        println!("未找到视频文件");
+        return Ok(());
+    }
+
+    println!("校验 {} 个视频文件...\n", files.len());
+
+    let mut passed = 0;
+    let mut missing = 0;
+    let mut failed = 0;
+    for file in &files {
+        let name = display_path(file, root);
+        match check_file(file) {
+            Ok(CrcCheck::Missing) => missing += 1,
+            Ok(CrcCheck::Passed) => {
+                passed += 1;
+                println!("通过: {name}");
+            }
+            Ok(CrcCheck::Mismatch { expected, actual }) => {
+                failed += 1;
+                println!("损坏: {name}（期望 {expected:08X}，实际 {actual:08X}）");
+            }
+            Err(error) => {
+                failed += 1;
+                println!("失败: {name}: {error:#}");
+            }
+        }
+    }
+
+    println!("\n校验完成: 通过 {passed} 个，失败 {failed} 个，无校验值 {missing} 个");
+
+    if failed > 0 {
+        return Err(anyhow::Error::new(FailureKind::ChecksumMismatch))
+            .context(format!("{failed} 个文件 CRC32 校验失败"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_check_file_compares_filename_crc() {
+        let dir = TestDir::new("verify_check");
+        let good = dir.path().join("[Group] Show - 01 [CBF43926].mkv");
+        let bad = dir.path().join("[Group] Show - 02 [ABCD1234].mkv");
+        let plain = dir.path().join("Show - 03.mkv");
+        for path in [&good, &bad, &plain] {
+            fs::write(path, b"123456789").unwrap();
+        }
+
+        let results = [&good, &bad, &plain].map(|path| check_file(path).unwrap());
+
+        assert_eq!(
+            results,
+            [
+                CrcCheck::Passed,
+                CrcCheck::Mismatch {
+                    expected: 0xABCD_1234,
+                    actual: 0xCBF4_3926,
+                },
+                CrcCheck::Missing,
+            ]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::parser::EpisodeType;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_PATH_ENV: &str = "ANIME_RENAMER_CONFIG";
+const APP_DIR_NAME: &str = "anime_renamer";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 配置文件（TOML），所有字段均可省略
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rename: RenameConfig,
+    pub parser: ParserConfig,
+    pub cache: CacheConfig,
+    pub retry: RetryConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenameConfig {
+    /// 重命名使用的文件名模板
+    pub template: Option<String>,
+}
+
+/// TMDB / AniList 元数据缓存，有效期以小时计，为 0 时不缓存该类请求
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// TMDB 搜索结果
+    pub search_ttl_hours: u64,
+    /// TMDB 番剧详情
+    pub show_ttl_hours: u64,
+    /// TMDB 季详情（连载中的番剧会新增单集，有效期不宜过长）
+    pub season_ttl_hours: u64,
+    /// TMDB 单集演职员与外部 ID
+    pub episode_ttl_hours: u64,
+    pub anilist_ttl_hours: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            search_ttl_hours: 24,
+            show_ttl_hours: 24,
+            season_ttl_hours: 24,
+            episode_ttl_hours: 24 * 7,
+            anilist_ttl_hours: 24 * 7,
+        }
+    }
+}
+
+/// TMDB / AniList 请求遇到 429、5xx 或网络错误时的重试设置
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// 包括首次请求在内的最大尝试次数，1 表示不重试
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    /// 单次等待的上限；服务器返回的 `Retry-After` 超过此值时不再重试
+    pub max_delay_seconds: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay_ms: 1000,
+            max_delay_seconds: 30,
+        }
+    }
+}
+
+/// 自定义解析规则，运行时加入内置匹配链
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParserConfig {
+    pub season: Vec<MatcherRule>,
+    pub episode: Vec<MatcherRule>,
+    pub special: Vec<SpecialRule>,
+    pub cleanup: Vec<CleanupRule>,
+}
+
+/// 季度或集数匹配规则，第 1 个捕获组为数字；集数规则的第 2 个捕获组为范围的最后一集
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatcherRule {
+    /// 在 `anime_renamer parse` 中显示的名称
+    pub name: Option<String>,
+    pub pattern: String,
+    /// 数字越小越先尝试，与内置匹配器相同时内置优先
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// 命中时把文件标记为指定类型，先于内置关键字检查
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecialRule {
+    pub pattern: String,
+    #[serde(rename = "type")]
+    pub episode_type: EpisodeType,
+}
+
+/// 从番剧名中移除的内容
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CleanupRule {
+    pub pattern: String,
+    #[serde(default = "default_cleanup_replacement")]
+    pub replacement: String,
+}
+
+fn default_cleanup_replacement() -> String {
+    " ".to_string()
+}
+
+impl Config {
+    /// 读取 `ANIME_RENAMER_CONFIG` 指定的配置文件，未设置时读取默认位置；
+    /// 默认位置的文件不存在时返回默认配置
+    pub fn load() -> Result<Self> {
+        match env::var(CONFIG_PATH_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+        {
+            Some(path) => Self::load_from(Path::new(&path)),
+            None => match default_config_path() {
+                Some(path) if path.exists() => Self::load_from(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("读取配置文件失败: {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("解析配置文件失败: {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_config_uses_defaults() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_rename_template() {
+        let config = Config::parse(
+            r#"
+            [rename]
+            template = "{show}/Season {season:02}/{show} - S{season:02}E{episode:02}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.rename.template.as_deref(),
+            Some("{show}/Season {season:02}/{show} - S{season:02}E{episode:02}")
+        );
+    }
+
+    #[test]
+    fn test_parse_parser_rules() {
+        let config = Config::parse(
+            r#"
+            [[parser.episode]]
+            name = "Vol"
+            pattern = '\[Vol\.\d+\]\[(\d+)\]'
+            priority = 0
+
+            [[parser.special]]
+            pattern = '(?i)\bNCOP\b'
+            type = "special"
+
+            [[parser.cleanup]]
+            pattern = 'Vol\.\d+'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.parser.episode[0].name.as_deref(), Some("Vol"));
+        assert_eq!(config.parser.episode[0].pattern, r"\[Vol\.\d+\]\[(\d+)\]");
+        assert_eq!(config.parser.special[0].episode_type, EpisodeType::Special);
+        assert_eq!(config.parser.cleanup[0].replacement, " ");
+        assert!(config.parser.season.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cache_overrides_only_given_ttls() {
+        let config = Config::parse("[cache]\nseason_ttl_hours = 1\nanilist_ttl_hours = 0").unwrap();
+
+        assert_eq!(
+            config.cache,
+            CacheConfig {
+                season_ttl_hours: 1,
+                anilist_ttl_hours: 0,
+                ..CacheConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_defaults_and_overrides() {
+        let config = Config::parse("[retry]\nmax_attempts = 1").unwrap();
+
+        assert_eq!(config.retry.max_attempts, 1);
+        assert_eq!(
+            config.retry.initial_delay_ms,
+            RetryConfig::default().initial_delay_ms
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        let error = Config::parse("[rename]\ntemplat = \"{show}\"").unwrap_err();
+
+        assert!(format!("{error:#}").contains("templat"));
+    }
+}
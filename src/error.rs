@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// 需要区分退出码的失败类型，作为错误链中的一环附加在 anyhow 错误上
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// TMDB / AniList 都没有找到匹配的番剧
+    NoMatch,
+    /// 没有任何文件能解析出集数
+    ParseFailure,
+    /// 重命名目标冲突或源文件缺失
+    Conflict,
+    /// 执行失败并已（尝试）回滚
+    Rollback,
+    /// 文件内容与文件名中的 CRC32 不一致
+    ChecksumMismatch,
+}
+
+impl FailureKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::NoMatch => 3,
+            Self::ParseFailure => 4,
+            Self::Conflict => 5,
+            Self::Rollback => 6,
+            Self::ChecksumMismatch => 7,
+        }
+    }
+
+    /// 在错误链中查找失败类型
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        error.downcast_ref::<Self>().copied()
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::NoMatch => "未找到匹配的番剧",
+            Self::ParseFailure => "没有可解析的文件",
+            Self::Conflict => "重命名计划存在冲突",
+            Self::Rollback => "重命名执行失败",
+            Self::ChecksumMismatch => "CRC32 校验失败",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for FailureKind {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_failure_kind_is_found_through_context() {
+        let error = Err::<(), _>(anyhow::Error::new(FailureKind::Conflict))
+            .context("目标文件已存在")
+            .unwrap_err();
+
+        assert_eq!(FailureKind::of(&error), Some(FailureKind::Conflict));
+
+        let error = Err::<(), _>(anyhow::anyhow!("rename failed"))
+            .context(FailureKind::Rollback)
+            .context("外层说明")
+            .unwrap_err();
+        assert_eq!(FailureKind::of(&error), Some(FailureKind::Rollback));
+        assert_eq!(FailureKind::of(&anyhow::anyhow!("其他错误")), None);
+    }
+
+    #[test]
+    fn test_failure_kinds_have_distinct_exit_codes() {
+        let codes = [
+            FailureKind::NoMatch,
+            FailureKind::ParseFailure,
+            FailureKind::Conflict,
+            FailureKind::Rollback,
+            FailureKind::ChecksumMismatch,
+        ]
+        .map(FailureKind::exit_code);
+
+        assert_eq!(codes, [3, 4, 5, 6, 7]);
+    }
+}
@@ -1,21 +1,46 @@
 mod anilist;
+mod cache;
+mod checksum;
 mod cli;
 mod commands;
+mod config;
+mod error;
 mod nfo;
+mod operations;
+mod output;
 mod parser;
+mod retry;
 mod scanner;
+mod template;
+#[cfg(test)]
+mod test_support;
 mod tmdb;
 
 use crate::cli::{Cli, Command, RenameArgs};
+use crate::error::FailureKind;
 use anyhow::Result;
 use clap::Parser;
+use std::process::ExitCode;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
+async fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Some(Command::Nfo(args)) => commands::nfo::run(&args).await,
+        Some(Command::Undo(args)) => commands::undo::run(&args),
+        Some(Command::Plan(args)) => commands::plan::run(&args).await,
+        Some(Command::Apply(args)) => commands::apply::run(&args),
+        Some(Command::Verify(args)) => commands::verify::run(&args),
+        Some(Command::Parse(args)) => commands::parse::run(&args),
         None => commands::rename::run(&RenameArgs::try_from(cli.rename)?).await,
     }
 }
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            ExitCode::from(FailureKind::of(&error).map_or(1, FailureKind::exit_code))
+        }
+    }
+}
@@ -105,9 +105,10 @@ impl NfoWriter {
         self.write_file(&path, &nfo.render())
     }
 
-    pub fn write_episode(&self, video_path: &Path, nfo: &EpisodeNfo) -> Result<WriteOutcome> {
+    /// 多集文件写入一个包含多个 `<episodedetails>` 的 NFO（Kodi 约定）
+    pub fn write_episode(&self, video_path: &Path, nfos: &[EpisodeNfo]) -> Result<WriteOutcome> {
         let path = episode_nfo_path(video_path);
-        self.write_file(&path, &nfo.render())
+        self.write_file(&path, &render_episodes(nfos))
     }
 
     pub fn write_season(&self, season_dir: &Path, nfo: &SeasonNfo) -> Result<WriteOutcome> {
@@ -268,28 +269,35 @@ impl SeasonNfo {
 }
 
 impl EpisodeNfo {
-    pub fn render(&self) -> String {
-        let mut xml = String::new();
-        xml.push_str(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
-        xml.push('\n');
+    fn push_details(&self, xml: &mut String) {
         xml.push_str("<episodedetails>\n");
-        push_tag(&mut xml, "title", Some(self.title.as_str()), 1);
-        push_tag(&mut xml, "showtitle", Some(self.showtitle.as_str()), 1);
-        push_tag(&mut xml, "season", Some(&self.season.to_string()), 1);
-        push_tag(&mut xml, "episode", Some(&self.episode.to_string()), 1);
-        push_tag(&mut xml, "plot", self.plot.as_deref(), 1);
-        push_tag(&mut xml, "premiered", self.premiered.as_deref(), 1);
-        push_tag(&mut xml, "aired", self.aired.as_deref(), 1);
-        push_ratings(&mut xml, self.rating.as_ref(), 1);
-        push_unique_ids(&mut xml, &self.unique_ids, 1);
-        push_people(&mut xml, "credits", &self.credits, 1);
-        push_people(&mut xml, "director", &self.directors, 1);
-        push_actors(&mut xml, &self.actors, 1);
+        push_tag(xml, "title", Some(self.title.as_str()), 1);
+        push_tag(xml, "showtitle", Some(self.showtitle.as_str()), 1);
+        push_tag(xml, "season", Some(&self.season.to_string()), 1);
+        push_tag(xml, "episode", Some(&self.episode.to_string()), 1);
+        push_tag(xml, "plot", self.plot.as_deref(), 1);
+        push_tag(xml, "premiered", self.premiered.as_deref(), 1);
+        push_tag(xml, "aired", self.aired.as_deref(), 1);
+        push_ratings(xml, self.rating.as_ref(), 1);
+        push_unique_ids(xml, &self.unique_ids, 1);
+        push_people(xml, "credits", &self.credits, 1);
+        push_people(xml, "director", &self.directors, 1);
+        push_actors(xml, &self.actors, 1);
         xml.push_str("</episodedetails>\n");
-        xml
     }
 }
 
+/// 依次输出各集的 `<episodedetails>`，共用一个 XML 声明
+pub fn render_episodes(nfos: &[EpisodeNfo]) -> String {
+    let mut xml = String::new();
+    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
+    xml.push('\n');
+    for nfo in nfos {
+        nfo.push_details(&mut xml);
+    }
+    xml
+}
+
 pub fn episode_nfo_path(video_path: &Path) -> PathBuf {
     video_path.with_extension("nfo")
 }
@@ -439,42 +447,7 @@ fn escape_xml(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    static TEST_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
-
-    struct TestDir {
-        path: PathBuf,
-    }
-
-    impl TestDir {
-        fn new(prefix: &str) -> Self {
-            let unique = format!(
-                "{}_{}_{}_{}",
-                prefix,
-                std::process::id(),
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos(),
-                TEST_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
-            );
-            let path = std::env::temp_dir().join(unique);
-            fs::create_dir_all(&path).unwrap();
-            Self { path }
-        }
-
-        fn path(&self) -> &Path {
-            &self.path
-        }
-    }
-
-    impl Drop for TestDir {
-        fn drop(&mut self) {
-            let _ = fs::remove_dir_all(&self.path);
-        }
-    }
+    use crate::test_support::TestDir;
 
     fn sample_tvshow_nfo() -> TvShowNfo {
         TvShowNfo {
@@ -552,7 +525,7 @@ mod tests {
 
     #[test]
     fn test_episode_render_contains_core_tags() {
-        let xml = sample_episode_nfo().render();
+        let xml = render_episodes(&[sample_episode_nfo()]);
 
         assert!(xml.contains("<episodedetails>"));
         assert!(xml.contains("<showtitle>Series</showtitle>"));
@@ -567,6 +540,22 @@ mod tests {
         assert!(xml.contains("<type>GuestStar</type>"));
     }
 
+    #[test]
+    fn test_render_episodes_writes_one_block_per_episode() {
+        let first = sample_episode_nfo();
+        let second = EpisodeNfo {
+            episode: 3,
+            ..sample_episode_nfo()
+        };
+
+        let xml = render_episodes(&[first, second]);
+
+        assert_eq!(xml.matches("<?xml").count(), 1);
+        assert_eq!(xml.matches("<episodedetails>").count(), 2);
+        assert!(xml.contains("<episode>2</episode>"));
+        assert!(xml.contains("<episode>3</episode>"));
+    }
+
     #[test]
     fn test_season_render_contains_core_tags() {
         let nfo = SeasonNfo {
@@ -722,7 +711,7 @@ mod tests {
 
         let writer = NfoWriter::new(false, true);
         let outcome = writer
-            .write_episode(&video_path, &sample_episode_nfo())
+            .write_episode(&video_path, &[sample_episode_nfo()])
             .unwrap();
 
         assert_eq!(outcome.action, WriteAction::Written);
@@ -737,7 +726,7 @@ mod tests {
 
         let writer = NfoWriter::new(true, false);
         let outcome = writer
-            .write_episode(&video_path, &sample_episode_nfo())
+            .write_episode(&video_path, &[sample_episode_nfo()])
             .unwrap();
 
         assert_eq!(outcome.action, WriteAction::WouldWrite);
@@ -0,0 +1,400 @@
+use super::plan::SourceFingerprint;
+use super::{RenameOperation, absolute_path};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DATA_DIR_ENV: &str = "ANIME_RENAMER_DATA_DIR";
+const APP_DIR_NAME: &str = "anime_renamer";
+const JOURNAL_DIR_NAME: &str = "journal";
+const JOURNAL_EXTENSION: &str = "json";
+
+/// 一次成功执行的重命名批次
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub created_at: u64,
+    pub root: PathBuf,
+    pub operations: Vec<RenameOperation>,
+    #[serde(default)]
+    pub created_dirs: Vec<PathBuf>,
+    /// 复制与链接模式的源文件在执行时的大小与修改时间
+    #[serde(default)]
+    pub sources: Vec<RecordedSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedSource {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub fingerprint: SourceFingerprint,
+}
+
+/// 复制或链接生成的文件，以及生成它的源文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedFile {
+    pub path: PathBuf,
+    pub source: PathBuf,
+    /// 旧版日志没有记录时为 None，只检查源文件是否存在
+    pub fingerprint: Option<SourceFingerprint>,
+}
+
+impl CreatedFile {
+    /// 源文件仍然存在且未被改动时，删除生成的文件不会丢失数据
+    pub fn source_unchanged(&self) -> bool {
+        match SourceFingerprint::read(&self.source) {
+            Ok(current) => self.fingerprint.is_none_or(|recorded| recorded == current),
+            Err(_) => false,
+        }
+    }
+}
+
+impl JournalEntry {
+    /// 按相反顺序生成撤销操作，复制与链接模式生成的文件不在其中
+    pub fn undo_operations(&self) -> Vec<RenameOperation> {
+        self.operations
+            .iter()
+            .rev()
+            .filter(|operation| operation.mode.moves_source())
+            .map(RenameOperation::inverse)
+            .collect()
+    }
+
+    /// 复制与链接模式生成的文件，撤销时在源文件未改动的前提下直接删除
+    pub fn created_files(&self) -> Vec<CreatedFile> {
+        self.operations
+            .iter()
+            .filter(|operation| !operation.mode.moves_source())
+            .map(|operation| CreatedFile {
+                path: operation.target.clone(),
+                source: operation.source.clone(),
+                fingerprint: self
+                    .sources
+                    .iter()
+                    .find(|source| source.path == operation.source)
+                    .map(|source| source.fingerprint),
+            })
+            .collect()
+    }
+}
+
+/// 重命名日志目录，每个批次保存为一个 JSON 文件
+pub struct JournalStore {
+    dir: PathBuf,
+}
+
+impl JournalStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(resolve_data_dir()?.join(JOURNAL_DIR_NAME)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn record(
+        &self,
+        root: &Path,
+        operations: &[RenameOperation],
+        created_dirs: &[PathBuf],
+    ) -> Result<JournalEntry> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("创建日志目录失败: {}", self.dir.display()))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch");
+        let mut id = now.as_millis().to_string();
+        let mut suffix = 1;
+        while self.entry_path(&id).exists() {
+            id = format!("{}-{suffix}", now.as_millis());
+            suffix += 1;
+        }
+
+        // 日志可能在其他工作目录下被撤销，统一记录为绝对路径
+        let sources = operations
+            .iter()
+            .filter(|operation| !operation.mode.moves_source())
+            .filter_map(|operation| {
+                Some(RecordedSource {
+                    path: absolute_path(&operation.source),
+                    fingerprint: SourceFingerprint::read(&operation.source).ok()?,
+                })
+            })
+            .collect();
+        let entry = JournalEntry {
+            id,
+            created_at: now.as_secs(),
+            root: absolute_path(root),
+            operations: operations
+                .iter()
+                .map(|operation| RenameOperation {
+                    source: absolute_path(&operation.source),
+                    target: absolute_path(&operation.target),
+                    kind: operation.kind,
+                    mode: operation.mode,
+                })
+                .collect(),
+            created_dirs: created_dirs.iter().map(|dir| absolute_path(dir)).collect(),
+            sources,
+        };
+
+        let path = self.entry_path(&entry.id);
+        let temp_path = path.with_extension("tmp");
+        let content = serde_json::to_string_pretty(&entry).context("序列化重命名日志失败")?;
+        fs::write(&temp_path, content)
+            .with_context(|| format!("写入重命名日志失败: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &path)
+            .with_context(|| format!("写入重命名日志失败: {}", path.display()))?;
+
+        Ok(entry)
+    }
+
+    /// 按时间顺序（由旧到新）列出所有日志
+    pub fn list(&self) -> Result<Vec<JournalEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        let read_dir = fs::read_dir(&self.dir)
+            .with_context(|| format!("读取日志目录失败: {}", self.dir.display()))?;
+
+        for item in read_dir.filter_map(|item| item.ok()) {
+            let path = item.path();
+            if path.extension().and_then(|value| value.to_str()) != Some(JOURNAL_EXTENSION) {
+                continue;
+            }
+            entries.push(read_entry(&path)?);
+        }
+
+        entries.sort_by_key(|entry| id_sort_key(&entry.id));
+
+        Ok(entries)
+    }
+
+    pub fn latest(&self) -> Result<Option<JournalEntry>> {
+        Ok(self.list()?.pop())
+    }
+
+    pub fn load(&self, id: &str) -> Result<JournalEntry> {
+        let path = self.entry_path(id);
+        if !path.exists() {
+            bail!("未找到重命名日志: {id}");
+        }
+        read_entry(&path)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<()> {
+        let path = self.entry_path(id);
+        fs::remove_file(&path).with_context(|| format!("删除重命名日志失败: {}", path.display()))
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{JOURNAL_EXTENSION}"))
+    }
+}
+
+/// 日志 ID 形如 `<毫秒时间戳>` 或 `<毫秒时间戳>-<序号>`
+fn id_sort_key(id: &str) -> (u128, u32) {
+    let (millis, suffix) = id.split_once('-').unwrap_or((id, "0"));
+    (
+        millis.parse().unwrap_or_default(),
+        suffix.parse().unwrap_or_default(),
+    )
+}
+
+fn read_entry(path: &Path) -> Result<JournalEntry> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("读取重命名日志失败: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("解析重命名日志失败: {}", path.display()))
+}
+
+pub(crate) fn resolve_data_dir() -> Result<PathBuf> {
+    resolve_data_dir_from_env(env::var(DATA_DIR_ENV).ok())
+}
+
+fn resolve_data_dir_from_env(value: Option<String>) -> Result<PathBuf> {
+    if let Some(dir) = value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        return Ok(PathBuf::from(dir));
+    }
+
+    dirs::data_local_dir()
+        .map(|dir| dir.join(APP_DIR_NAME))
+        .context("无法确定数据目录，请设置 ANIME_RENAMER_DATA_DIR")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::{RenameKind, RenameMode};
+    use crate::test_support::TestDir;
+
+    fn sample_operations() -> Vec<RenameOperation> {
+        vec![
+            RenameOperation {
+                source: PathBuf::from("/media/Show 01.mkv"),
+                target: PathBuf::from("/media/Season 1/Show S01E01.mkv"),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+            RenameOperation {
+                source: PathBuf::from("/media/Show 01.ass"),
+                target: PathBuf::from("/media/Season 1/Show S01E01.ass"),
+                kind: RenameKind::Subtitle,
+                mode: RenameMode::Rename,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_record_and_load_round_trip() {
+        let dir = TestDir::new("journal_round_trip");
+        let store = JournalStore::new(dir.path().join("journal"));
+
+        let entry = store
+            .record(
+                Path::new("/media"),
+                &sample_operations(),
+                &[PathBuf::from("/media/Season 1")],
+            )
+            .unwrap();
+        let loaded = store.load(&entry.id).unwrap();
+
+        assert_eq!(loaded, entry);
+        assert_eq!(store.latest().unwrap(), Some(entry));
+    }
+
+    #[test]
+    fn test_record_fingerprints_sources_of_created_files() {
+        let dir = TestDir::new("journal_sources");
+        let store = JournalStore::new(dir.path().join("journal"));
+        let source = dir.path().join("Show 01.mkv");
+        fs::write(&source, b"video").unwrap();
+        let operations = vec![RenameOperation {
+            source: source.clone(),
+            target: dir.path().join("Show S01E01.mkv"),
+            kind: RenameKind::Video,
+            mode: RenameMode::Copy,
+        }];
+
+        let entry = store.record(dir.path(), &operations, &[]).unwrap();
+        let created = entry.created_files();
+
+        assert_eq!(
+            created[0].fingerprint,
+            Some(SourceFingerprint::read(&source).unwrap())
+        );
+        assert!(created[0].source_unchanged());
+        fs::remove_file(&source).unwrap();
+        assert!(!created[0].source_unchanged());
+    }
+
+    #[test]
+    fn test_list_orders_entries_and_remove_drops_them() {
+        let dir = TestDir::new("journal_list");
+        let store = JournalStore::new(dir.path().to_path_buf());
+
+        let first = store
+            .record(Path::new("/media"), &sample_operations(), &[])
+            .unwrap();
+        let second = store
+            .record(Path::new("/media"), &sample_operations(), &[])
+            .unwrap();
+
+        let ids: Vec<_> = store.list().unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![first.id.clone(), second.id.clone()]);
+
+        store.remove(&second.id).unwrap();
+        assert_eq!(store.latest().unwrap().map(|e| e.id), Some(first.id));
+    }
+
+    #[test]
+    fn test_load_missing_entry_reports_id() {
+        let dir = TestDir::new("journal_missing");
+        let store = JournalStore::new(dir.path().to_path_buf());
+
+        let error = store.load("42").unwrap_err();
+
+        assert!(error.to_string().contains("42"));
+    }
+
+    #[test]
+    fn test_undo_operations_reverse_order_and_direction() {
+        let entry = JournalEntry {
+            id: "1".to_string(),
+            created_at: 0,
+            root: PathBuf::from("/media"),
+            operations: sample_operations(),
+            created_dirs: Vec::new(),
+            sources: Vec::new(),
+        };
+
+        let undo = entry.undo_operations();
+
+        assert_eq!(
+            undo[0].source,
+            PathBuf::from("/media/Season 1/Show S01E01.ass")
+        );
+        assert_eq!(undo[0].target, PathBuf::from("/media/Show 01.ass"));
+        assert_eq!(undo[1].kind, RenameKind::Video);
+    }
+
+    #[test]
+    fn test_copied_files_are_deleted_instead_of_renamed_back() {
+        let mut operations = sample_operations();
+        operations[1].mode = RenameMode::Hardlink;
+        let entry = JournalEntry {
+            id: "1".to_string(),
+            created_at: 0,
+            root: PathBuf::from("/media"),
+            operations,
+            created_dirs: Vec::new(),
+            sources: Vec::new(),
+        };
+
+        let undo = entry.undo_operations();
+
+        assert_eq!(undo.len(), 1);
+        assert_eq!(undo[0].kind, RenameKind::Video);
+        assert_eq!(
+            entry.created_files(),
+            vec![CreatedFile {
+                path: PathBuf::from("/media/Season 1/Show S01E01.ass"),
+                source: PathBuf::from("/media/Show 01.ass"),
+                fingerprint: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_journal_entries_without_mode_default_to_rename() {
+        let entry: JournalEntry = serde_json::from_str(
+            r#"{"id":"1","created_at":0,"root":"/media","operations":[
+                {"source":"/media/a.mkv","target":"/media/b.mkv","kind":"video"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(entry.operations[0].mode, RenameMode::Rename);
+    }
+
+    #[test]
+    fn test_resolve_data_dir_prefers_env_value() {
+        assert_eq!(
+            resolve_data_dir_from_env(Some(" /srv/anime ".to_string())).unwrap(),
+            PathBuf::from("/srv/anime")
+        );
+    }
+}
@@ -0,0 +1,680 @@
+pub mod journal;
+pub mod plan;
+mod transfer;
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use journal::CreatedFile;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use transfer::move_file;
+
+static TEMP_RENAME_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameKind {
+    Video,
+    Subtitle,
+}
+
+/// 生成目标文件的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameMode {
+    /// 原地重命名
+    #[default]
+    Rename,
+    /// 移动到其他目录（与 rename 相同，但要求指定输出目录）
+    Move,
+    /// 复制，保留源文件
+    Copy,
+    /// 硬链接，跨文件系统时改为复制
+    Hardlink,
+    /// 指向源文件绝对路径的符号链接
+    Symlink,
+}
+
+impl RenameMode {
+    /// 是否会移走源文件
+    pub fn moves_source(self) -> bool {
+        matches!(self, Self::Rename | Self::Move)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rename => "重命名",
+            Self::Move => "移动",
+            Self::Copy => "复制",
+            Self::Hardlink => "硬链接",
+            Self::Symlink => "符号链接",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameOperation {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub kind: RenameKind,
+    #[serde(default)]
+    pub mode: RenameMode,
+}
+
+impl RenameOperation {
+    /// 生成撤销用的反向操作，只适用于会移走源文件的模式
+    pub fn inverse(&self) -> Self {
+        Self {
+            source: self.target.clone(),
+            target: self.source.clone(),
+            kind: self.kind,
+            mode: RenameMode::Rename,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StagedRename {
+    operation: RenameOperation,
+    /// 移动模式下暂存在源目录，其他模式下在目标目录生成
+    temp_path: PathBuf,
+}
+
+/// 一次批量重命名的执行结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionSummary {
+    pub video_success: usize,
+    pub subtitle_success: usize,
+    /// 因跨文件系统无法硬链接而改为复制的文件数
+    pub hardlink_fallbacks: usize,
+    /// 跨文件系统移动（复制校验后删除源文件）的文件数
+    pub cross_device_moves: usize,
+    /// 本次执行新建的目录（由浅到深）
+    pub created_dirs: Vec<PathBuf>,
+}
+
+fn display_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|value| value.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+pub fn validate_rename_operations(operations: &[RenameOperation]) -> Result<()> {
+    let mut targets: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    // 只有被移走的源文件腾出的位置可以作为其他文件的目标
+    let moved_sources: HashSet<&Path> = operations
+        .iter()
+        .filter(|operation| operation.mode.moves_source())
+        .map(|operation| operation.source.as_path())
+        .collect();
+
+    for operation in operations {
+        targets
+            .entry(operation.target.as_path())
+            .or_default()
+            .push(operation.source.as_path());
+    }
+
+    for (target, sources_for_target) in targets {
+        if sources_for_target.len() > 1 {
+            let joined_sources = sources_for_target
+                .iter()
+                .map(|source| source.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!(
+                "多个文件将重命名为同一目标 {}: {}",
+                target.display(),
+                joined_sources
+            );
+        }
+
+        if (target.exists() || target.is_symlink()) && !moved_sources.contains(target) {
+            bail!("目标文件已存在: {}", target.display());
+        }
+    }
+
+    for operation in operations {
+        if !operation.source.exists() {
+            bail!("源文件不存在: {}", operation.source.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// 转为绝对路径，失败时保留原路径
+pub fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn temporary_rename_path(source: &Path, index: usize) -> PathBuf {
+    let unique = TEMP_RENAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time should be after unix epoch")
+        .as_nanos();
+    let file_name = display_file_name(source);
+
+    source.with_file_name(format!(
+        ".anime-renamer-tmp-{nanos}-{unique}-{index}-{file_name}"
+    ))
+}
+
+/// 返回 `dir` 中尚不存在的各级目录（由浅到深）
+fn missing_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    missing
+}
+
+/// 删除复制或链接生成的文件的结果
+#[derive(Debug, Default)]
+pub struct RemovedFiles {
+    pub removed: usize,
+    /// 源文件已不存在或已改动而保留的文件
+    pub kept: Vec<PathBuf>,
+}
+
+/// 删除复制或链接生成的文件；已不存在的文件会被跳过，
+/// 源文件已不存在或已改动时保留生成的文件，避免删掉唯一的副本
+pub fn remove_created_files(files: &[CreatedFile]) -> Result<RemovedFiles> {
+    let mut result = RemovedFiles::default();
+
+    for file in files {
+        let path = &file.path;
+        if !path.exists() && !path.is_symlink() {
+            continue;
+        }
+        if !file.source_unchanged() {
+            result.kept.push(path.clone());
+            continue;
+        }
+        std::fs::remove_file(path).with_context(|| format!("删除文件失败: {}", path.display()))?;
+        result.removed += 1;
+    }
+
+    Ok(result)
+}
+
+/// 尽力删除新建的空目录，非空或删除失败的目录会被保留
+pub fn remove_created_dirs(created_dirs: &[PathBuf]) {
+    for dir in created_dirs.iter().rev() {
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
+/// 目标文件的生成方式与请求的模式不同的情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fallback {
+    None,
+    /// 无法硬链接，改为复制
+    HardlinkCopy,
+    /// 跨文件系统移动，复制校验后删除源文件
+    CrossDeviceMove,
+}
+
+/// 由暂存文件生成最终目标
+///
+/// 移动模式下 `temp_path` 是已暂存的源文件，其他模式下先在 `temp_path` 生成副本或链接
+fn materialize(operation: &RenameOperation, temp_path: &Path) -> std::io::Result<Fallback> {
+    let mut fallback = Fallback::None;
+
+    match operation.mode {
+        RenameMode::Rename | RenameMode::Move => {
+            return Ok(if move_file(temp_path, &operation.target)? {
+                Fallback::CrossDeviceMove
+            } else {
+                Fallback::None
+            });
+        }
+        RenameMode::Copy => {
+            std::fs::copy(&operation.source, temp_path)?;
+        }
+        RenameMode::Hardlink => {
+            if let Err(error) = std::fs::hard_link(&operation.source, temp_path) {
+                if error.kind() != std::io::ErrorKind::CrossesDevices {
+                    return Err(error);
+                }
+                std::fs::copy(&operation.source, temp_path)?;
+                fallback = Fallback::HardlinkCopy;
+            }
+        }
+        RenameMode::Symlink => symlink_file(&absolute_path(&operation.source), temp_path)?,
+    }
+
+    std::fs::rename(temp_path, &operation.target)?;
+    Ok(fallback)
+}
+
+#[cfg(unix)]
+fn symlink_file(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_file(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+fn remove_if_present(path: &Path) -> std::io::Result<()> {
+    if path.exists() || path.is_symlink() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn rollback_rename_operations(
+    staged: &[StagedRename],
+    finalized_count: usize,
+    failed_index: usize,
+    created_dirs: &[PathBuf],
+) -> Result<()> {
+    for staged_rename in staged[..finalized_count].iter().rev() {
+        let operation = &staged_rename.operation;
+        if !operation.mode.moves_source() {
+            remove_if_present(&operation.target)
+                .with_context(|| format!("回滚失败: 无法删除 {}", operation.target.display()))?;
+        } else if operation.target.exists() {
+            move_file(&operation.target, &operation.source).with_context(|| {
+                format!(
+                    "回滚失败: {} -> {}",
+                    operation.target.display(),
+                    operation.source.display()
+                )
+            })?;
+        }
+    }
+
+    for staged_rename in &staged[failed_index..] {
+        let operation = &staged_rename.operation;
+        if !operation.mode.moves_source() {
+            remove_if_present(&staged_rename.temp_path).with_context(|| {
+                format!("回滚失败: 无法删除 {}", staged_rename.temp_path.display())
+            })?;
+        } else if staged_rename.temp_path.exists() {
+            std::fs::rename(&staged_rename.temp_path, &operation.source).with_context(|| {
+                format!(
+                    "回滚失败: {} -> {}",
+                    staged_rename.temp_path.display(),
+                    operation.source.display()
+                )
+            })?;
+        }
+    }
+
+    remove_created_dirs(created_dirs);
+
+    Ok(())
+}
+
+pub fn execute_rename_operations(operations: &[RenameOperation]) -> Result<ExecutionSummary> {
+    let mut staged = Vec::with_capacity(operations.len());
+
+    for (index, operation) in operations.iter().enumerate() {
+        if !operation.mode.moves_source() {
+            staged.push(StagedRename {
+                operation: operation.clone(),
+                temp_path: temporary_rename_path(&operation.target, index),
+            });
+            continue;
+        }
+
+        let temp_path = temporary_rename_path(&operation.source, index);
+        if let Err(error) = std::fs::rename(&operation.source, &temp_path) {
+            rollback_rename_operations(&staged, 0, 0, &[]).context(format!(
+                "暂存重命名失败且回滚未完成: {} -> {} ({error})",
+                operation.source.display(),
+                temp_path.display()
+            ))?;
+            bail!(
+                "暂存重命名失败，已回滚: {} -> {} ({error})",
+                operation.source.display(),
+                temp_path.display()
+            );
+        }
+        staged.push(StagedRename {
+            operation: operation.clone(),
+            temp_path,
+        });
+    }
+
+    let mut summary = ExecutionSummary::default();
+
+    for (index, staged_rename) in staged.iter().enumerate() {
+        if let Some(parent_dir) = staged_rename.operation.target.parent() {
+            let missing = missing_dirs(parent_dir);
+            if let Err(error) = std::fs::create_dir_all(parent_dir) {
+                summary.created_dirs.extend(missing);
+                rollback_rename_operations(&staged, index, index, &summary.created_dirs).context(
+                    format!(
+                        "创建目录失败且回滚未完成: {} ({error})",
+                        parent_dir.display()
+                    ),
+                )?;
+                bail!("创建目录失败，已回滚: {} ({error})", parent_dir.display());
+            }
+            summary.created_dirs.extend(missing);
+        }
+
+        if staged_rename.operation.target.exists()
+            && let Err(error) = std::fs::remove_file(&staged_rename.operation.target)
+        {
+            rollback_rename_operations(&staged, index, index, &summary.created_dirs).context(
+                format!(
+                    "移除已存在目标失败且回滚未完成: {} ({error})",
+                    staged_rename.operation.target.display()
+                ),
+            )?;
+            bail!(
+                "移除已存在目标失败，已回滚: {} ({error})",
+                staged_rename.operation.target.display()
+            );
+        }
+
+        match materialize(&staged_rename.operation, &staged_rename.temp_path) {
+            Ok(Fallback::None) => {}
+            Ok(Fallback::HardlinkCopy) => summary.hardlink_fallbacks += 1,
+            Ok(Fallback::CrossDeviceMove) => summary.cross_device_moves += 1,
+            Err(error) => {
+                rollback_rename_operations(&staged, index, index, &summary.created_dirs).context(
+                    format!(
+                        "执行{}失败且回滚未完成: {} -> {} ({error})",
+                        staged_rename.operation.mode.label(),
+                        staged_rename.operation.source.display(),
+                        staged_rename.operation.target.display()
+                    ),
+                )?;
+                bail!(
+                    "执行{}失败，已回滚: {} -> {} ({error})",
+                    staged_rename.operation.mode.label(),
+                    staged_rename.operation.source.display(),
+                    staged_rename.operation.target.display()
+                );
+            }
+        }
+
+        match staged_rename.operation.kind {
+            RenameKind::Video => summary.video_success += 1,
+            RenameKind::Subtitle => summary.subtitle_success += 1,
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_validate_rename_operations_rejects_duplicate_targets() {
+        let operations = vec![
+            RenameOperation {
+                source: PathBuf::from("/tmp/a.mkv"),
+                target: PathBuf::from("/tmp/output.mkv"),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+            RenameOperation {
+                source: PathBuf::from("/tmp/b.mkv"),
+                target: PathBuf::from("/tmp/output.mkv"),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+        ];
+
+        let error = validate_rename_operations(&operations).unwrap_err();
+
+        assert!(error.to_string().contains("同一目标"));
+    }
+
+    #[test]
+    fn test_execute_rename_operations_handles_swapped_names() {
+        let dir = TestDir::new("rename_swap");
+
+        let first = dir.path().join("first.mkv");
+        let second = dir.path().join("second.mkv");
+        fs::write(&first, b"one").unwrap();
+        fs::write(&second, b"two").unwrap();
+
+        let operations = vec![
+            RenameOperation {
+                source: first.clone(),
+                target: second.clone(),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+            RenameOperation {
+                source: second.clone(),
+                target: first.clone(),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+        ];
+
+        let summary = execute_rename_operations(&operations).unwrap();
+
+        assert_eq!(summary.video_success, 2);
+        assert_eq!(summary.subtitle_success, 0);
+        assert_eq!(fs::read(&first).unwrap(), b"two");
+        assert_eq!(fs::read(&second).unwrap(), b"one");
+    }
+
+    #[test]
+    fn test_execute_rename_operations_records_created_season_dirs() {
+        let dir = TestDir::new("rename_created_dirs");
+        let source = dir.path().join("Show 01.mkv");
+        let season_dir = dir.path().join("Season 1");
+        fs::write(&source, b"video").unwrap();
+
+        let operations = vec![RenameOperation {
+            source,
+            target: season_dir.join("Show S01E01.mkv"),
+            kind: RenameKind::Video,
+            mode: RenameMode::Rename,
+        }];
+
+        let summary = execute_rename_operations(&operations).unwrap();
+
+        assert_eq!(summary.created_dirs, vec![season_dir]);
+    }
+
+    #[test]
+    fn test_inverse_operations_restore_original_layout() {
+        let dir = TestDir::new("rename_inverse");
+        let source = dir.path().join("Show 01.mkv");
+        let subtitle = dir.path().join("Show 01.ass");
+        fs::write(&source, b"video").unwrap();
+        fs::write(&subtitle, b"subtitle").unwrap();
+
+        let operations = vec![
+            RenameOperation {
+                source: source.clone(),
+                target: dir.path().join("Season 1/Show S01E01.mkv"),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            },
+            RenameOperation {
+                source: subtitle.clone(),
+                target: dir.path().join("Season 1/Show S01E01.ass"),
+                kind: RenameKind::Subtitle,
+                mode: RenameMode::Rename,
+            },
+        ];
+        let summary = execute_rename_operations(&operations).unwrap();
+
+        let inverse: Vec<_> = operations.iter().rev().map(|op| op.inverse()).collect();
+        validate_rename_operations(&inverse).unwrap();
+        let undo_summary = execute_rename_operations(&inverse).unwrap();
+        remove_created_dirs(&summary.created_dirs);
+
+        assert_eq!(undo_summary.video_success, 1);
+        assert_eq!(undo_summary.subtitle_success, 1);
+        assert_eq!(fs::read(&source).unwrap(), b"video");
+        assert_eq!(fs::read(&subtitle).unwrap(), b"subtitle");
+        assert!(!dir.path().join("Season 1").exists());
+    }
+
+    fn operation(source: &Path, target: PathBuf, mode: RenameMode) -> RenameOperation {
+        RenameOperation {
+            source: source.to_path_buf(),
+            target,
+            kind: RenameKind::Video,
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_existing_source_as_copy_target() {
+        let dir = TestDir::new("copy_onto_source");
+        let first = dir.path().join("first.mkv");
+        let second = dir.path().join("second.mkv");
+        fs::write(&first, b"one").unwrap();
+        fs::write(&second, b"two").unwrap();
+
+        let operations = vec![
+            operation(&first, second.clone(), RenameMode::Copy),
+            operation(&second, dir.path().join("third.mkv"), RenameMode::Copy),
+        ];
+
+        let error = validate_rename_operations(&operations).unwrap_err();
+
+        assert!(error.to_string().contains("目标文件已存在"));
+    }
+
+    #[test]
+    fn test_execute_copy_and_hardlink_keep_source() {
+        let dir = TestDir::new("rename_copy_link");
+        let source = dir.path().join("[Group] Show - 01.mkv");
+        fs::write(&source, b"video").unwrap();
+        let library = dir.path().join("library");
+        let copied = library.join("Show S01E01.mkv");
+        let linked = library.join("Show S01E01.link.mkv");
+
+        let summary = execute_rename_operations(&[
+            operation(&source, copied.clone(), RenameMode::Copy),
+            operation(&source, linked.clone(), RenameMode::Hardlink),
+        ])
+        .unwrap();
+
+        assert_eq!(summary.video_success, 2);
+        assert_eq!(summary.created_dirs, vec![library]);
+        assert_eq!(fs::read(&source).unwrap(), b"video");
+        assert_eq!(fs::read(&copied).unwrap(), b"video");
+        assert_eq!(fs::read(&linked).unwrap(), b"video");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let source_inode = fs::metadata(&source).unwrap().ino();
+            assert_eq!(fs::metadata(&linked).unwrap().ino(), source_inode);
+            assert_ne!(fs::metadata(&copied).unwrap().ino(), source_inode);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_symlink_points_to_absolute_source() {
+        let dir = TestDir::new("rename_symlink");
+        let source = dir.path().join("Show - 01.mkv");
+        fs::write(&source, b"video").unwrap();
+        let target = dir.path().join("Season 1/Show S01E01.mkv");
+
+        execute_rename_operations(&[operation(&source, target.clone(), RenameMode::Symlink)])
+            .unwrap();
+
+        assert_eq!(fs::read_link(&target).unwrap(), absolute_path(&source));
+        assert_eq!(fs::read(&target).unwrap(), b"video");
+    }
+
+    #[test]
+    fn test_execute_copy_rolls_back_created_targets_on_failure() {
+        let dir = TestDir::new("rename_copy_rollback");
+        let first = dir.path().join("first.mkv");
+        let second = dir.path().join("second.mkv");
+        fs::write(&first, b"one").unwrap();
+        fs::write(&second, b"two").unwrap();
+        // 目标目录的父路径是普通文件，第二个操作创建目录时必然失败
+        let blocker = dir.path().join("blocker");
+        fs::write(&blocker, b"").unwrap();
+        let copied = dir.path().join("library/first.mkv");
+
+        let error = execute_rename_operations(&[
+            operation(&first, copied.clone(), RenameMode::Copy),
+            operation(&second, blocker.join("second.mkv"), RenameMode::Copy),
+        ])
+        .unwrap_err();
+
+        assert!(error.to_string().contains("已回滚"));
+        assert!(!copied.exists());
+        assert!(!dir.path().join("library").exists());
+        assert_eq!(fs::read(&first).unwrap(), b"one");
+        assert_eq!(fs::read(&second).unwrap(), b"two");
+    }
+
+    #[test]
+    fn test_remove_created_files_skips_missing_files() {
+        let dir = TestDir::new("remove_created_files");
+        let source = dir.path().join("source.mkv");
+        let copied = dir.path().join("copy.mkv");
+        fs::write(&source, b"video").unwrap();
+        fs::write(&copied, b"video").unwrap();
+        let created = |path: PathBuf| CreatedFile {
+            path,
+            source: source.clone(),
+            fingerprint: Some(plan::SourceFingerprint::read(&source).unwrap()),
+        };
+
+        let result = remove_created_files(&[
+            created(copied.clone()),
+            created(dir.path().join("missing.mkv")),
+        ])
+        .unwrap();
+
+        assert_eq!(result.removed, 1);
+        assert!(result.kept.is_empty());
+        assert!(!copied.exists());
+    }
+
+    #[test]
+    fn test_remove_created_files_keeps_copies_of_missing_or_changed_sources() {
+        let dir = TestDir::new("remove_created_keep");
+        let source = dir.path().join("source.mkv");
+        let changed_copy = dir.path().join("changed.mkv");
+        let orphan_copy = dir.path().join("orphan.mkv");
+        fs::write(&source, b"video").unwrap();
+        fs::write(&changed_copy, b"video").unwrap();
+        fs::write(&orphan_copy, b"video").unwrap();
+        let fingerprint = plan::SourceFingerprint::read(&source).unwrap();
+        fs::write(&source, b"edited video").unwrap();
+
+        let result = remove_created_files(&[
+            CreatedFile {
+                path: changed_copy.clone(),
+                source: source.clone(),
+                fingerprint: Some(fingerprint),
+            },
+            CreatedFile {
+                path: orphan_copy.clone(),
+                source: dir.path().join("deleted.mkv"),
+                fingerprint: None,
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(result.removed, 0);
+        assert_eq!(result.kept, vec![changed_copy.clone(), orphan_copy.clone()]);
+        assert!(changed_copy.exists());
+        assert!(orphan_copy.exists());
+    }
+}
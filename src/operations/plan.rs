@@ -0,0 +1,195 @@
+use super::{RenameOperation, absolute_path};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const PLAN_VERSION: u32 = 1;
+
+/// 生成计划时源文件的大小与修改时间，执行前用于确认文件未被改动
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceFingerprint {
+    pub size: u64,
+    pub modified_ns: u64,
+}
+
+impl SourceFingerprint {
+    pub fn read(path: &Path) -> Result<Self> {
+        let metadata =
+            fs::metadata(path).with_context(|| format!("读取文件信息失败: {}", path.display()))?;
+        let modified = metadata
+            .modified()
+            .with_context(|| format!("读取修改时间失败: {}", path.display()))?;
+        let modified_ns = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Ok(Self {
+            size: metadata.len(),
+            modified_ns,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedRename {
+    #[serde(flatten)]
+    pub operation: RenameOperation,
+    #[serde(flatten)]
+    pub fingerprint: SourceFingerprint,
+}
+
+/// 可编辑的重命名计划文件，`target` 可在执行前手动修改
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanFile {
+    pub version: u32,
+    pub root: PathBuf,
+    pub operations: Vec<PlannedRename>,
+}
+
+impl PlanFile {
+    pub fn new(root: &Path, operations: &[RenameOperation]) -> Result<Self> {
+        let operations = operations
+            .iter()
+            .map(|operation| {
+                Ok(PlannedRename {
+                    fingerprint: SourceFingerprint::read(&operation.source)?,
+                    operation: RenameOperation {
+                        source: absolute_path(&operation.source),
+                        target: absolute_path(&operation.target),
+                        kind: operation.kind,
+                        mode: operation.mode,
+                    },
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            version: PLAN_VERSION,
+            root: absolute_path(root),
+            operations,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("序列化重命名计划失败")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("读取计划文件失败: {}", path.display()))?;
+        let plan: Self = serde_json::from_str(&content)
+            .with_context(|| format!("解析计划文件失败: {}", path.display()))?;
+
+        if plan.version != PLAN_VERSION {
+            bail!(
+                "不支持的计划文件版本 {}（当前支持 {PLAN_VERSION}）",
+                plan.version
+            );
+        }
+
+        Ok(plan)
+    }
+
+    /// 确认每个源文件仍然存在，且大小与修改时间和生成计划时一致
+    pub fn verify_sources(&self) -> Result<()> {
+        let mut changed = Vec::new();
+
+        for planned in &self.operations {
+            let source = &planned.operation.source;
+            if !source.exists() {
+                bail!("源文件不存在: {}", source.display());
+            }
+            if SourceFingerprint::read(source)? != planned.fingerprint {
+                changed.push(source.display().to_string());
+            }
+        }
+
+        if !changed.is_empty() {
+            bail!("生成计划后源文件已被修改: {}", changed.join(", "));
+        }
+
+        Ok(())
+    }
+
+    pub fn rename_operations(&self) -> Vec<RenameOperation> {
+        self.operations
+            .iter()
+            .map(|planned| planned.operation.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::{RenameKind, RenameMode};
+    use crate::test_support::TestDir;
+
+    fn write_plan(dir: &TestDir) -> PlanFile {
+        let source = dir.path().join("Show - 01.mkv");
+        fs::write(&source, b"video").unwrap();
+
+        PlanFile::new(
+            dir.path(),
+            &[RenameOperation {
+                source,
+                target: dir.path().join("Show S01E01.mkv"),
+                kind: RenameKind::Video,
+                mode: RenameMode::Rename,
+            }],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_file_round_trips_through_json() {
+        let dir = TestDir::new("plan_round_trip");
+        let plan = write_plan(&dir);
+        let path = dir.path().join("plan.json");
+        fs::write(&path, plan.to_json().unwrap()).unwrap();
+
+        let loaded = PlanFile::load(&path).unwrap();
+
+        assert_eq!(loaded, plan);
+        assert_eq!(loaded.operations[0].fingerprint.size, 5);
+        loaded.verify_sources().unwrap();
+    }
+
+    #[test]
+    fn test_verify_sources_rejects_modified_source() {
+        let dir = TestDir::new("plan_modified");
+        let plan = write_plan(&dir);
+
+        fs::write(&plan.operations[0].operation.source, b"re-encoded video").unwrap();
+        let error = plan.verify_sources().unwrap_err();
+
+        assert!(error.to_string().contains("已被修改"));
+    }
+
+    #[test]
+    fn test_verify_sources_rejects_missing_source() {
+        let dir = TestDir::new("plan_missing");
+        let plan = write_plan(&dir);
+
+        fs::remove_file(&plan.operations[0].operation.source).unwrap();
+        let error = plan.verify_sources().unwrap_err();
+
+        assert!(error.to_string().contains("源文件不存在"));
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_version() {
+        let dir = TestDir::new("plan_version");
+        let mut plan = write_plan(&dir);
+        plan.version = 99;
+        let path = dir.path().join("plan.json");
+        fs::write(&path, plan.to_json().unwrap()).unwrap();
+
+        let error = PlanFile::load(&path).unwrap_err();
+
+        assert!(error.to_string().contains("版本"));
+    }
+}
@@ -0,0 +1,115 @@
+use super::temporary_rename_path;
+use crate::checksum::{copy_with_crc32, crc32_file};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// 移动文件；目标在其他文件系统上时改为复制、校验后再删除源文件
+///
+/// 返回是否走了跨文件系统的复制流程
+pub fn move_file(from: &Path, to: &Path) -> io::Result<bool> {
+    move_file_with(from, to, |from, to| fs::rename(from, to))
+}
+
+fn move_file_with(
+    from: &Path,
+    to: &Path,
+    rename: impl Fn(&Path, &Path) -> io::Result<()>,
+) -> io::Result<bool> {
+    match rename(from, to) {
+        Ok(()) => Ok(false),
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => {
+            copy_then_delete(from, to)?;
+            Ok(true)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// 先复制到目标目录下的临时文件，大小与 CRC32 一致后再改名到位并删除源文件
+///
+/// 任一步骤失败都会清理已写入的数据，源文件保持不变
+fn copy_then_delete(from: &Path, to: &Path) -> io::Result<()> {
+    let partial = temporary_rename_path(to, 0);
+
+    let result = copy_with_crc32(from, &partial).and_then(|(size, crc)| {
+        let (written_size, written_crc) = crc32_file(&partial)?;
+        if written_size != size || written_crc != crc {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "复制校验失败: {} (大小 {size} / {written_size}, CRC32 {crc:08X} / {written_crc:08X})",
+                    from.display()
+                ),
+            ));
+        }
+        if fs::metadata(from)?.len() != size {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("复制期间源文件发生变化: {}", from.display()),
+            ));
+        }
+        fs::rename(&partial, to)
+    });
+
+    if let Err(error) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(error);
+    }
+
+    if let Err(error) = fs::remove_file(from) {
+        let _ = fs::remove_file(to);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDir;
+
+    fn cross_device_rename(_: &Path, _: &Path) -> io::Result<()> {
+        Err(io::Error::from(ErrorKind::CrossesDevices))
+    }
+
+    #[test]
+    fn test_move_file_copies_and_deletes_across_devices() {
+        let dir = TestDir::new("move_cross_device");
+        let source = dir.path().join("Show - 01.mkv");
+        let target = dir.path().join("Show S01E01.mkv");
+        fs::write(&source, b"video").unwrap();
+
+        let copied = move_file_with(&source, &target, cross_device_rename).unwrap();
+
+        assert!(copied);
+        assert!(!source.exists());
+        assert_eq!(fs::read(&target).unwrap(), b"video");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_move_file_keeps_source_when_copy_fails() {
+        let dir = TestDir::new("move_cross_device_failure");
+        let source = dir.path().join("Show - 01.mkv");
+        let target = dir.path().join("missing/Show S01E01.mkv");
+        fs::write(&source, b"video").unwrap();
+
+        move_file_with(&source, &target, cross_device_rename).unwrap_err();
+
+        assert_eq!(fs::read(&source).unwrap(), b"video");
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_move_file_uses_plain_rename_on_same_device() {
+        let dir = TestDir::new("move_same_device");
+        let source = dir.path().join("Show - 01.mkv");
+        let target = dir.path().join("Show S01E01.mkv");
+        fs::write(&source, b"video").unwrap();
+
+        assert!(!move_file(&source, &target).unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"video");
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// 命令结果的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+pub fn set_format(format: OutputFormat) {
+    JSON_OUTPUT.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// 输出进度与提示信息；JSON 模式下写入 stderr，保证 stdout 只有 JSON
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use status;
+
+/// 打印提示并读取一行输入（已去除首尾空白）
+pub fn prompt(message: &str) -> Result<String> {
+    if is_json() {
+        eprint!("{message}");
+        io::stderr().flush()?;
+    } else {
+        print!("{message}");
+        io::stdout().flush()?;
+    }
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, value)?;
+    writeln!(stdout)?;
+    Ok(())
+}
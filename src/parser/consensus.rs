@@ -0,0 +1,262 @@
+use super::{FileParser, FolderInfo, ParsedFile};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 文件的解析结果与同目录其他文件比较后的结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Consensus {
+    /// 单独解析时把标题中固定不变的数字（如 `86`、`Mob Psycho 100`）当作集数，已改用变化的数字
+    Corrected { isolated_episode: u32 },
+    /// 集数取自与同目录大多数文件不同的位置，需要确认
+    Disagrees,
+}
+
+/// 单个文件的解析结果，以及与同目录文件比较后的结论
+pub type SiblingParse = (Option<ParsedFile>, Option<Consensus>);
+
+/// 文件名中独立的数字，不含 `S01E05`、`1080p` 之类与字母相连的数字
+#[derive(Debug, PartialEq)]
+struct NumberToken<'a> {
+    span: (usize, usize),
+    /// 去掉前导零后的数字，用于逐位比较
+    digits: &'a str,
+}
+
+fn number_tokens(stem: &str) -> Vec<NumberToken<'_>> {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    static NUMBER: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"[A-Za-z0-9]+").unwrap());
+    let number = NUMBER.get_or_init(|| Regex::new(r"^(\d+)(?:[vV]\d{1,2})?$").unwrap());
+
+    word.find_iter(stem)
+        .filter_map(|m| {
+            let digits = number.captures(m.as_str())?.get(1)?;
+            Some(NumberToken {
+                span: (m.start(), m.start() + digits.end()),
+                digits: digits.as_str().trim_start_matches('0'),
+            })
+        })
+        .collect()
+}
+
+/// 数字个数相同的一组文件名，逐位标记数字是否在所有文件中都相同
+#[derive(Debug, PartialEq)]
+struct NumberLayout {
+    members: Vec<usize>,
+    constant: Vec<bool>,
+}
+
+/// 只保留至少两个文件且存在变化位置的分组；全都相同时无法判断哪个是集数
+fn number_layouts(tokens: &[Vec<NumberToken>]) -> Vec<NumberLayout> {
+    let mut by_count: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (index, file_tokens) in tokens.iter().enumerate() {
+        if !file_tokens.is_empty() {
+            by_count.entry(file_tokens.len()).or_default().push(index);
+        }
+    }
+
+    by_count
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(count, members)| {
+            let first = &tokens[members[0]];
+            let constant = (0..count)
+                .map(|column| {
+                    members
+                        .iter()
+                        .all(|&member| tokens[member][column].digits == first[column].digits)
+                })
+                .collect();
+            NumberLayout { members, constant }
+        })
+        .filter(|layout| layout.constant.contains(&false))
+        .collect()
+}
+
+fn overlaps(a: (usize, usize), b: (usize, usize)) -> bool {
+    a.0 < b.1 && a.1 > b.0
+}
+
+impl FileParser {
+    /// 解析同一目录（`folder`）中的一批文件：先逐个解析，再比较各文件名中的数字，
+    /// 把所有文件都相同的数字视为标题的一部分
+    pub fn parse_siblings(&self, filenames: &[String], folder: &FolderInfo) -> Vec<SiblingParse> {
+        let stems: Vec<&str> = filenames
+            .iter()
+            .map(|filename| {
+                Path::new(filename)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or(filename)
+            })
+            .collect();
+        let tokens: Vec<Vec<NumberToken>> = stems.iter().map(|stem| number_tokens(stem)).collect();
+        let mut results: Vec<_> = filenames
+            .iter()
+            .map(|filename| (self.parse_excluding(filename, folder, &[]), None))
+            .collect();
+
+        for layout in number_layouts(&tokens) {
+            let column_of = |member: usize, span: (usize, usize)| {
+                tokens[member]
+                    .iter()
+                    .position(|token| overlaps(token.span, span))
+            };
+
+            for &member in &layout.members {
+                let Some((parsed, span)) = &results[member].0 else {
+                    continue;
+                };
+                if !column_of(member, *span).is_some_and(|column| layout.constant[column]) {
+                    continue;
+                }
+
+                let constant_spans: Vec<(usize, usize)> = tokens[member]
+                    .iter()
+                    .zip(&layout.constant)
+                    .filter(|(_, constant)| **constant)
+                    .map(|(token, _)| token.span)
+                    .collect();
+                let isolated_episode = parsed.episode_number;
+                if let Some(reparsed) =
+                    self.parse_excluding(&filenames[member], folder, &constant_spans)
+                {
+                    results[member] = (
+                        Some(reparsed),
+                        Some(Consensus::Corrected { isolated_episode }),
+                    );
+                }
+            }
+
+            let columns: Vec<(usize, Option<usize>)> = layout
+                .members
+                .iter()
+                .map(|&member| {
+                    let column = results[member]
+                        .0
+                        .as_ref()
+                        .and_then(|(_, span)| column_of(member, *span));
+                    (member, column)
+                })
+                .collect();
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for column in columns.iter().filter_map(|(_, column)| *column) {
+                *counts.entry(column).or_default() += 1;
+            }
+            let Some(majority) = counts
+                .into_iter()
+                .max_by_key(|(column, count)| (*count, std::cmp::Reverse(*column)))
+                .map(|(column, _)| column)
+            else {
+                continue;
+            };
+
+            for (member, column) in columns {
+                if column.is_some_and(|column| column != majority) {
+                    results[member].1 = Some(Consensus::Disagrees);
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|(parsed, consensus)| (parsed.map(|(parsed, _)| parsed), consensus))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(filenames: &[&str]) -> Vec<String> {
+        filenames.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_number_tokens_skip_numbers_joined_with_letters() {
+        let tokens = number_tokens("[Group] 86 S01E05 - 05v2 [1080p][0123ABCD]");
+
+        assert_eq!(
+            tokens,
+            vec![
+                NumberToken {
+                    span: (8, 10),
+                    digits: "86",
+                },
+                NumberToken {
+                    span: (20, 22),
+                    digits: "5",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_layouts_require_a_varying_column() {
+        let stems = ["Show 100 - 01", "Show 100 - 02", "Show - 01", "Other - 01"];
+        let tokens: Vec<_> = stems.iter().map(|stem| number_tokens(stem)).collect();
+
+        assert_eq!(
+            number_layouts(&tokens),
+            vec![NumberLayout {
+                members: vec![0, 1],
+                constant: vec![true, false],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_siblings_treats_constant_numbers_as_title() {
+        let parser = FileParser::new();
+        let filenames = names(&[
+            "[Group] Mob Psycho 100 05 [1080p].mkv",
+            "[Group] Mob Psycho 100 06 [1080p].mkv",
+            "[Group] Mob Psycho 100 07 [1080p].mkv",
+        ]);
+
+        let results = parser.parse_siblings(&filenames, &FolderInfo::default());
+
+        for ((parsed, consensus), episode) in results.iter().zip([5, 6, 7]) {
+            let parsed = parsed.as_ref().unwrap();
+            assert_eq!(parsed.anime_name, "Mob Psycho 100");
+            assert_eq!(parsed.episode_number, episode);
+            assert_eq!(
+                *consensus,
+                Some(Consensus::Corrected {
+                    isolated_episode: 100
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_siblings_flags_files_using_another_column() {
+        let parser = FileParser::new();
+        let filenames = names(&[
+            "Show - 01 (13).mkv",
+            "Show - 02 (14).mkv",
+            "Show 03 [15].mkv",
+        ]);
+
+        let results = parser.parse_siblings(&filenames, &FolderInfo::default());
+
+        assert_eq!(results[0].1, None);
+        assert_eq!(results[1].1, None);
+        assert_eq!(results[2].0.as_ref().unwrap().episode_number, 15);
+        assert_eq!(results[2].1, Some(Consensus::Disagrees));
+    }
+
+    #[test]
+    fn test_parse_siblings_keeps_single_files_unchanged() {
+        let parser = FileParser::new();
+        let results = parser.parse_siblings(&names(&["Show - 01.mkv"]), &FolderInfo::default());
+
+        assert_eq!(results[0].0.as_ref().unwrap().episode_number, 1);
+        assert_eq!(results[0].1, None);
+    }
+}
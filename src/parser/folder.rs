@@ -0,0 +1,130 @@
+use super::FileParser;
+use super::matchers::{CJK_NUMBER, extract_tmdb_id, parse_number};
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 从文件所在目录推断出的番剧信息，优先级低于文件名
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderInfo {
+    /// 番剧目录名去掉年份、`[tmdbid=...]` 等标签后的名称
+    pub show: Option<String>,
+    pub year: Option<u32>,
+    /// `Season 02`、`S2`、`第二季` 之类的季度目录，`Specials` 为第 0 季
+    pub season: Option<u32>,
+}
+
+/// 季度目录对应的季号
+pub fn season_directory(name: &str) -> Option<u32> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(&format!(
+            r"(?i)^(?:season\s*(\d+)|s(\d{{1,2}})|(specials?)|第\s*({CJK_NUMBER})\s*[季期部])$"
+        ))
+        .unwrap()
+    });
+
+    let cap = regex.captures(name.trim())?;
+    if cap.get(3).is_some() {
+        return Some(0);
+    }
+    [1, 2, 4]
+        .into_iter()
+        .find_map(|group| cap.get(group))
+        .and_then(|number| parse_number(number.as_str()))
+}
+
+/// 离文件最近的带 `[tmdbid=...]` 的目录中的 TMDB ID
+pub fn folder_tmdb_id(dir: &Path) -> Option<u32> {
+    dir.ancestors()
+        .filter_map(|ancestor| ancestor.file_name())
+        .find_map(|name| extract_tmdb_id(&name.to_string_lossy()))
+}
+
+fn tmdb_id_tag_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)\[?tmdbid=\d+\]?").unwrap())
+}
+
+impl FileParser {
+    /// 解析文件所在目录；位于季度目录中时，番剧目录为上一级
+    pub fn folder_info(&self, dir: &Path) -> FolderInfo {
+        let dir_name = |dir: &Path| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        };
+
+        let season = dir_name(dir).and_then(|name| season_directory(&name));
+        let show_dir = if season.is_some() {
+            dir.parent()
+        } else {
+            Some(dir)
+        };
+        let Some(show_dir_name) = show_dir.and_then(dir_name) else {
+            return FolderInfo {
+                season,
+                ..FolderInfo::default()
+            };
+        };
+
+        let name = tmdb_id_tag_regex().replace_all(&show_dir_name, " ");
+        let show = self.clean_anime_name(&name);
+        FolderInfo {
+            show: (!show.is_empty()).then_some(show),
+            year: self.extract_year(&show_dir_name),
+            season,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_season_directory_numbers() {
+        let cases = [
+            ("Season 02", Some(2)),
+            ("season2", Some(2)),
+            ("S3", Some(3)),
+            ("Specials", Some(0)),
+            ("第二季", Some(2)),
+            ("Show Season 2", None),
+            ("Extras", None),
+        ];
+
+        for (name, season) in cases {
+            assert_eq!(season_directory(name), season, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_folder_tmdb_id_prefers_nearest_directory() {
+        let dir = Path::new("/anime/[tmdbid=1] Collection/Show [tmdbid=123]/Season 02");
+
+        assert_eq!(folder_tmdb_id(dir), Some(123));
+        assert_eq!(folder_tmdb_id(Path::new("/anime/Show/Season 02")), None);
+    }
+
+    #[test]
+    fn test_folder_info_reads_show_directory_above_season_directory() {
+        let parser = FileParser::new();
+
+        assert_eq!(
+            parser.folder_info(Path::new("/anime/Show Name (2019) [tmdbid=123]/Season 02")),
+            FolderInfo {
+                show: Some("Show Name".to_string()),
+                year: Some(2019),
+                season: Some(2),
+            }
+        );
+        assert_eq!(
+            parser.folder_info(Path::new("/anime/[BDRip] 葬送的芙莉莲")),
+            FolderInfo {
+                show: Some("葬送的芙莉莲".to_string()),
+                year: None,
+                season: None,
+            }
+        );
+    }
+}
@@ -1,9 +1,18 @@
-use regex::Regex;
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+/// 中文数字或阿拉伯数字（含全角），用于 `第十二话`、`第二季` 等格式
+pub const CJK_NUMBER: &str = r"[\d零〇一二两三四五六七八九十百]{1,6}";
+
+/// 多集范围允许的最大跨度，避免把 `01-1080` 之类误识别为范围
+const MAX_EPISODE_RANGE_SPAN: u32 = 12;
 
 /// 匹配结果
 #[derive(Debug, Clone)]
 pub struct MatchResult {
     pub value: u32,
+    /// 多集范围（如 `01-02`）的最后一集
+    pub end_value: Option<u32>,
     pub matched_text: String,
     pub start_pos: usize,
     pub end_pos: usize,
@@ -11,15 +20,30 @@ pub struct MatchResult {
 
 /// 匹配器 trait - 责任链中的一环
 pub trait Matcher: Send + Sync {
-    fn try_match(&self, text: &str) -> Option<MatchResult>;
+    /// 从字节位置 `start` 开始查找第一个匹配
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult>;
 
     fn priority(&self) -> u32;
 
-    /// 用于调试
-    #[allow(dead_code)]
+    /// 用于调试（`anime_renamer parse`）
     fn name(&self) -> &str;
 }
 
+/// 责任链中的一次匹配尝试，用于解释解析过程
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchAttempt {
+    pub matcher: String,
+    pub priority: u32,
+    /// 数字所在的字节区间，未匹配时为空
+    pub span: Option<(usize, usize)>,
+    pub matched_text: Option<String>,
+    pub value: Option<u32>,
+    /// 与排除区间重叠，继续向后查找
+    pub excluded: bool,
+    /// 最终采用的匹配
+    pub selected: bool,
+}
+
 /// 责任链管理器
 pub struct MatcherChain {
     matchers: Vec<Box<dyn Matcher>>,
@@ -39,19 +63,61 @@ impl MatcherChain {
         self
     }
 
-    /// 执行匹配链，返回第一个成功的匹配
+    /// 执行匹配链，返回第一个成功的匹配；与排除位置重叠时继续向后查找同一匹配器
     pub fn execute(&self, text: &str, exclude_positions: &[(usize, usize)]) -> Option<MatchResult> {
+        self.run(text, exclude_positions, None)
+    }
+
+    /// 与 `execute` 相同，并记录每个匹配器的尝试过程
+    pub fn execute_traced(
+        &self,
+        text: &str,
+        exclude_positions: &[(usize, usize)],
+    ) -> (Option<MatchResult>, Vec<MatchAttempt>) {
+        let mut attempts = Vec::new();
+        let result = self.run(text, exclude_positions, Some(&mut attempts));
+        (result, attempts)
+    }
+
+    fn run(
+        &self,
+        text: &str,
+        exclude_positions: &[(usize, usize)],
+        mut trace: Option<&mut Vec<MatchAttempt>>,
+    ) -> Option<MatchResult> {
         for matcher in &self.matchers {
-            if let Some(result) = matcher.try_match(text) {
+            let mut record = |result: Option<&MatchResult>, excluded: bool| {
+                if let Some(attempts) = trace.as_deref_mut() {
+                    attempts.push(MatchAttempt {
+                        matcher: matcher.name().to_string(),
+                        priority: matcher.priority(),
+                        span: result.map(|result| (result.start_pos, result.end_pos)),
+                        matched_text: result.map(|result| result.matched_text.clone()),
+                        value: result.map(|result| result.value),
+                        excluded,
+                        selected: result.is_some() && !excluded,
+                    });
+                }
+            };
+
+            let mut search_start = 0;
+            while let Some(result) = matcher.try_match_at(text, search_start) {
                 // 检查是否与排除位置重叠
                 let overlaps = exclude_positions
                     .iter()
                     .any(|(start, end)| result.start_pos < *end && result.end_pos > *start);
+                record(Some(&result), overlaps);
 
                 if !overlaps {
                     return Some(result);
                 }
+
+                let Some(next) = text[result.start_pos..].chars().next() else {
+                    break;
+                };
+                search_start = result.start_pos + next.len_utf8();
             }
+            record(None, false);
         }
         None
     }
@@ -71,12 +137,13 @@ impl SeasonNumberMatcher {
 }
 
 impl Matcher for SeasonNumberMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -106,12 +173,13 @@ impl SeasonWordMatcher {
 }
 
 impl Matcher for SeasonWordMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -127,7 +195,7 @@ impl Matcher for SeasonWordMatcher {
     }
 }
 
-/// 第3季 格式
+/// 第3季、第二期、第二部 格式
 pub struct ChineseSeasonMatcher {
     regex: Regex,
 }
@@ -135,18 +203,19 @@ pub struct ChineseSeasonMatcher {
 impl ChineseSeasonMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"第\s*(\d{1,2})\s*季").unwrap(),
+            regex: Regex::new(&format!(r"第\s*({CJK_NUMBER})\s*[季期部]")).unwrap(),
         }
     }
 }
 
 impl Matcher for ChineseSeasonMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -192,8 +261,8 @@ impl RomanSeasonMatcher {
 }
 
 impl Matcher for RomanSeasonMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let roman_match = cap.get(1)?;
             let roman = roman_match.as_str();
             let value = self.roman_to_number(roman)?;
@@ -209,6 +278,7 @@ impl Matcher for RomanSeasonMatcher {
             if before_ok {
                 Some(MatchResult {
                     value,
+                    end_value: None,
                     matched_text: roman.to_string(),
                     start_pos: roman_match.start(),
                     end_pos: roman_match.end(),
@@ -227,28 +297,27 @@ impl Matcher for RomanSeasonMatcher {
     }
 }
 
-// ==================== 集数匹配器 ====================
-
-/// S01E12 格式
-pub struct SxEyMatcher {
+/// 2nd Season、3rd Season 等序数格式
+pub struct OrdinalSeasonMatcher {
     regex: Regex,
 }
 
-impl SxEyMatcher {
+impl OrdinalSeasonMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"[Ss](\d{1,2})[Ee](\d{1,4})").unwrap(),
+            regex: Regex::new(r"(?i)\b(\d{1,2})(?:st|nd|rd|th)\s*season\b").unwrap(),
         }
     }
 }
 
-impl Matcher for SxEyMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
-            let num_match = cap.get(2)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+impl Matcher for OrdinalSeasonMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
+            let num_match = cap.get(1)?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -260,30 +329,68 @@ impl Matcher for SxEyMatcher {
         1
     }
     fn name(&self) -> &str {
-        "SxEy(S01E12)"
+        "OrdinalSeason(2nd Season)"
     }
 }
 
-/// [01] 方括号格式
-pub struct BracketEpisodeMatcher {
+/// Unicode 罗马数字季度（Ⅱ、Ⅲ）
+pub struct UnicodeRomanSeasonMatcher {
     regex: Regex,
 }
 
-impl BracketEpisodeMatcher {
+impl UnicodeRomanSeasonMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"\[(\d{1,4})\]").unwrap(),
+            regex: Regex::new(r"[Ⅰ-Ⅻ]").unwrap(),
         }
     }
 }
 
-impl Matcher for BracketEpisodeMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
-            let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+impl Matcher for UnicodeRomanSeasonMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.find_at(text, start).map(|roman| MatchResult {
+            value: roman.as_str().chars().next().unwrap() as u32 - 'Ⅰ' as u32 + 1,
+            end_value: None,
+            matched_text: roman.as_str().to_string(),
+            start_pos: roman.start(),
+            end_pos: roman.end(),
+        })
+    }
+
+    fn priority(&self) -> u32 {
+        9
+    }
+    fn name(&self) -> &str {
+        "UnicodeRomanSeason(Ⅱ)"
+    }
+}
+
+// ==================== 分段匹配器 ====================
+
+/// Part 2、Cour 2、2nd Cour 格式
+pub struct PartMatcher {
+    regex: Regex,
+}
+
+impl PartMatcher {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(
+                r"(?i)\b(?:part|cour)\s*(\d{1,2})\b|\b(\d{1,2})(?:st|nd|rd|th)\s*(?:part|cour)\b",
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Matcher for PartMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
+            let num_match = cap.get(1).or_else(|| cap.get(2))?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -292,33 +399,34 @@ impl Matcher for BracketEpisodeMatcher {
     }
 
     fn priority(&self) -> u32 {
-        5
+        1
     }
     fn name(&self) -> &str {
-        "Bracket([01])"
+        "Part(Part 2)"
     }
 }
 
-/// 第01集/话 中文格式
-pub struct ChineseEpisodeMatcher {
+/// 第2クール 格式
+pub struct JapaneseCourMatcher {
     regex: Regex,
 }
 
-impl ChineseEpisodeMatcher {
+impl JapaneseCourMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"第\s*(\d{1,4})\s*(?:集|话|話)").unwrap(),
+            regex: Regex::new(&format!(r"第\s*({CJK_NUMBER})\s*クール")).unwrap(),
         }
     }
 }
 
-impl Matcher for ChineseEpisodeMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+impl Matcher for JapaneseCourMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -326,6 +434,164 @@ impl Matcher for ChineseEpisodeMatcher {
         })
     }
 
+    fn priority(&self) -> u32 {
+        2
+    }
+    fn name(&self) -> &str {
+        "JapaneseCour(第2クール)"
+    }
+}
+
+/// 由捕获组构造集数匹配结果；`end_groups` 中第一个匹配到的组为范围的最后一集，
+/// 范围不合理（不递增或跨度过大）时只保留第一集
+fn episode_result(cap: &Captures, start_group: usize, end_groups: &[usize]) -> Option<MatchResult> {
+    let whole = cap.get(0)?;
+    let num_match = cap.get(start_group)?;
+    let value = parse_number(num_match.as_str())?;
+
+    let Some(end_match) = end_groups.iter().find_map(|group| cap.get(*group)) else {
+        return Some(MatchResult {
+            value,
+            end_value: None,
+            matched_text: whole.as_str().to_string(),
+            start_pos: num_match.start(),
+            end_pos: num_match.end(),
+        });
+    };
+
+    match parse_number(end_match.as_str()) {
+        Some(end) if end > value && end - value <= MAX_EPISODE_RANGE_SPAN => Some(MatchResult {
+            value,
+            end_value: Some(end),
+            matched_text: whole.as_str().to_string(),
+            start_pos: num_match.start(),
+            end_pos: end_match.end(),
+        }),
+        _ => Some(MatchResult {
+            value,
+            end_value: None,
+            matched_text: whole.as_str()[..num_match.end() - whole.start()].to_string(),
+            start_pos: num_match.start(),
+            end_pos: num_match.end(),
+        }),
+    }
+}
+
+// ==================== 集数匹配器 ====================
+
+/// 配置文件中定义的正则匹配器，第 1 个捕获组为数字，第 2 个捕获组（可选）为范围的最后一集
+pub struct RegexMatcher {
+    name: String,
+    regex: Regex,
+    priority: u32,
+}
+
+impl RegexMatcher {
+    pub fn new(name: String, regex: Regex, priority: u32) -> Self {
+        Self {
+            name,
+            regex,
+            priority,
+        }
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// S01E12 格式
+pub struct SxEyMatcher {
+    regex: Regex,
+}
+
+impl SxEyMatcher {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(r"[Ss](\d{1,2})[Ee](\d{1,4})(?:-?[Ee](\d{1,4})|-(\d{1,4})(?:\D|$))?")
+                .unwrap(),
+        }
+    }
+}
+
+impl Matcher for SxEyMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 2, &[3, 4]))
+    }
+
+    fn priority(&self) -> u32 {
+        1
+    }
+    fn name(&self) -> &str {
+        "SxEy(S01E12)"
+    }
+}
+
+/// [01] 方括号格式
+pub struct BracketEpisodeMatcher {
+    regex: Regex,
+}
+
+impl BracketEpisodeMatcher {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(r"\[(\d{1,4})(?:[-~&](\d{1,4}))?(?:\.\d)?(?:[vV]\d{1,2})?\]")
+                .unwrap(),
+        }
+    }
+}
+
+impl Matcher for BracketEpisodeMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
+    }
+
+    fn priority(&self) -> u32 {
+        5
+    }
+    fn name(&self) -> &str {
+        "Bracket([01])"
+    }
+}
+
+/// 第01集/第十二话 中文格式
+pub struct ChineseEpisodeMatcher {
+    regex: Regex,
+}
+
+impl ChineseEpisodeMatcher {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(&format!(
+                r"第\s*({CJK_NUMBER})(?:\.\d)?(?:\s*[-~&]\s*({CJK_NUMBER}))?\s*(?:集|话|話)"
+            ))
+            .unwrap(),
+        }
+    }
+}
+
+impl Matcher for ChineseEpisodeMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
+    }
+
     fn priority(&self) -> u32 {
         2
     }
@@ -342,23 +608,19 @@ pub struct EpMatcher {
 impl EpMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"[Ee][Pp]\s*(\d{1,4})").unwrap(),
+            regex: Regex::new(
+                r"[Ee][Pp]\s*(\d{1,4})(?:\s*[-~&]\s*(?:[Ee][Pp]\s*)?(\d{1,4})(?:\D|$))?",
+            )
+            .unwrap(),
         }
     }
 }
 
 impl Matcher for EpMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
-            let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
-            Some(MatchResult {
-                value,
-                matched_text: cap.get(0)?.as_str().to_string(),
-                start_pos: num_match.start(),
-                end_pos: num_match.end(),
-            })
-        })
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
     }
 
     fn priority(&self) -> u32 {
@@ -377,23 +639,16 @@ pub struct DelimiterEpisodeMatcher {
 impl DelimiterEpisodeMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"[\s\-_\.：:]+(\d{1,4})(?:\D|$)").unwrap(),
+            regex: Regex::new(r"[\s\-_\.：:]+(\d{1,4})(?:[-~&](\d{1,4}))?(?:\D|$)").unwrap(),
         }
     }
 }
 
 impl Matcher for DelimiterEpisodeMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
-            let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
-            Some(MatchResult {
-                value,
-                matched_text: cap.get(0)?.as_str().to_string(),
-                start_pos: num_match.start(),
-                end_pos: num_match.end(),
-            })
-        })
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
     }
 
     fn priority(&self) -> u32 {
@@ -404,6 +659,34 @@ impl Matcher for DelimiterEpisodeMatcher {
     }
 }
 
+/// 05.mkv、05 - 标题.mkv 等以集数开头的文件名（常见于 BD 原盘整理）
+pub struct LeadingNumberMatcher {
+    regex: Regex,
+}
+
+impl LeadingNumberMatcher {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(r"^(\d{1,4})(?:[^\dA-Za-z]|[vV]\d|$)").unwrap(),
+        }
+    }
+}
+
+impl Matcher for LeadingNumberMatcher {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[]))
+    }
+
+    fn priority(&self) -> u32 {
+        30
+    }
+    fn name(&self) -> &str {
+        "LeadingNumber(05)"
+    }
+}
+
 /// _S001 格式（老式动画编号）
 pub struct UnderscoreSMatcher {
     regex: Regex,
@@ -418,12 +701,13 @@ impl UnderscoreSMatcher {
 }
 
 impl Matcher for UnderscoreSMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex.captures_at(text, start).and_then(|cap| {
             let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
+            let value = parse_number(num_match.as_str())?;
             Some(MatchResult {
                 value,
+                end_value: None,
                 matched_text: cap.get(0)?.as_str().to_string(),
                 start_pos: num_match.start(),
                 end_pos: num_match.end(),
@@ -447,23 +731,16 @@ pub struct EMatcher {
 impl EMatcher {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(r"[Ee](\d{1,4})(?:\D|$)").unwrap(),
+            regex: Regex::new(r"[Ee](\d{1,4})(?:-?[Ee](\d{1,4}))?(?:\D|$)").unwrap(),
         }
     }
 }
 
 impl Matcher for EMatcher {
-    fn try_match(&self, text: &str) -> Option<MatchResult> {
-        self.regex.captures(text).and_then(|cap| {
-            let num_match = cap.get(1)?;
-            let value = num_match.as_str().parse::<u32>().ok()?;
-            Some(MatchResult {
-                value,
-                matched_text: cap.get(0)?.as_str().to_string(),
-                start_pos: num_match.start(),
-                end_pos: num_match.end(),
-            })
-        })
+    fn try_match_at(&self, text: &str, start: usize) -> Option<MatchResult> {
+        self.regex
+            .captures_at(text, start)
+            .and_then(|cap| episode_result(&cap, 1, &[2]))
     }
 
     fn priority(&self) -> u32 {
@@ -474,6 +751,83 @@ impl Matcher for EMatcher {
     }
 }
 
+/// 解析阿拉伯数字（含全角 `１２`）或中文数字（`十二`、`二十五`、`一百零五`、`〇三`）
+pub fn parse_number(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.chars().all(|c| full_width_digit(c).is_some()) {
+        return text.chars().try_fold(0u32, |value, c| {
+            value.checked_mul(10)?.checked_add(full_width_digit(c)?)
+        });
+    }
+
+    parse_chinese_number(text)
+}
+
+fn full_width_digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        '０'..='９' => Some(c as u32 - '０' as u32),
+        _ => None,
+    }
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    }
+}
+
+fn parse_chinese_number(text: &str) -> Option<u32> {
+    // 不带单位时逐位读取，如 〇三、一二
+    if !text.contains(['十', '百']) {
+        return text
+            .chars()
+            .try_fold(0u32, |value, c| Some(value * 10 + chinese_digit(c)?));
+    }
+
+    let mut total = 0;
+    let mut current: Option<u32> = None;
+    let mut last_unit = u32::MAX;
+
+    for c in text.chars() {
+        let unit = match c {
+            '十' => 10,
+            '百' => 100,
+            _ => {
+                let digit = chinese_digit(c)?;
+                // 两个数字不能相连（"零" 只作占位，如 一百零五）
+                if current.is_some_and(|value| value != 0) {
+                    return None;
+                }
+                current = Some(digit);
+                continue;
+            }
+        };
+        if unit >= last_unit {
+            return None;
+        }
+        total += current.unwrap_or(1) * unit;
+        current = None;
+        last_unit = unit;
+    }
+
+    Some(total + current.unwrap_or(0))
+}
+
 pub fn extract_tmdb_id(path: &str) -> Option<u32> {
     let regex = Regex::new(r"(?i)\[?tmdbid=(\d+)\]?").unwrap();
     regex
@@ -521,3 +875,36 @@ mod tmdb_id_tests {
         assert_eq!(extract_tmdb_id(path), Some(99999));
     }
 }
+
+#[cfg(test)]
+mod number_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number_handles_ascii_and_full_width_digits() {
+        assert_eq!(parse_number("12"), Some(12));
+        assert_eq!(parse_number("１２"), Some(12));
+        assert_eq!(parse_number("０３"), Some(3));
+    }
+
+    #[test]
+    fn test_parse_number_handles_chinese_numerals() {
+        assert_eq!(parse_number("二"), Some(2));
+        assert_eq!(parse_number("十"), Some(10));
+        assert_eq!(parse_number("十二"), Some(12));
+        assert_eq!(parse_number("二十"), Some(20));
+        assert_eq!(parse_number("二十五"), Some(25));
+        assert_eq!(parse_number("一百零五"), Some(105));
+        assert_eq!(parse_number("一百一十"), Some(110));
+        assert_eq!(parse_number("〇三"), Some(3));
+        assert_eq!(parse_number("两"), Some(2));
+    }
+
+    #[test]
+    fn test_parse_number_rejects_malformed_numerals() {
+        assert_eq!(parse_number(""), None);
+        assert_eq!(parse_number("十百"), None);
+        assert_eq!(parse_number("二三十"), None);
+        assert_eq!(parse_number("abc"), None);
+    }
+}
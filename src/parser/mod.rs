@@ -1,11 +1,27 @@
+mod consensus;
+mod folder;
 mod matchers;
+mod release;
 
-pub use matchers::extract_tmdb_id;
+use crate::config::{MatcherRule, ParserConfig};
+use anyhow::{Context, Result, bail};
+pub use consensus::{Consensus, SiblingParse};
+pub use folder::{FolderInfo, folder_tmdb_id, season_directory};
+pub use matchers::MatchAttempt;
 use matchers::*;
 use regex::Regex;
+pub use release::{ReleaseInfo, TagKind, extract_crc32};
+use release::{classify_release, is_release_tag};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, PartialEq)]
+const FINAL_SEASON_PATTERN: &str =
+    r"(?i)(?:\bthe\s+)?\bfinal\s+season\b|最终季|最終季|ファイナルシーズン";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[allow(clippy::upper_case_acronyms)]
 pub enum EpisodeType {
     Normal,
@@ -13,27 +29,139 @@ pub enum EpisodeType {
     OAD,
     Special,
     Movie,
+    /// 无字幕片头（NCOP）
+    NCOP,
+    /// 无字幕片尾（NCED）
+    NCED,
+    /// PV、预告
+    PV,
+    /// 广告
+    CM,
+    /// BD 菜单
+    Menu,
+}
+
+/// 花絮在媒体库中的目录名
+pub const EXTRAS_FOLDERS: [&str; 3] = ["Extras", "Trailers", "Other"];
+
+impl EpisodeType {
+    /// 花絮放入的 Jellyfin/Plex 目录，正片与特别篇返回 None
+    pub fn extras_folder(&self) -> Option<&'static str> {
+        match self {
+            EpisodeType::NCOP | EpisodeType::NCED => Some(EXTRAS_FOLDERS[0]),
+            EpisodeType::PV | EpisodeType::CM => Some(EXTRAS_FOLDERS[1]),
+            EpisodeType::Menu => Some(EXTRAS_FOLDERS[2]),
+            _ => None,
+        }
+    }
+
+    /// 花絮文件名中的类型标签，与编号一起组成 `NCOP1`、`PV2`
+    pub fn extra_label(&self) -> &'static str {
+        match self {
+            EpisodeType::NCOP => "NCOP",
+            EpisodeType::NCED => "NCED",
+            EpisodeType::PV => "PV",
+            EpisodeType::CM => "CM",
+            EpisodeType::Menu => "Menu",
+            _ => "",
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ParsedFile {
     pub anime_name: String,
     pub episode_number: u32,
+    /// 多集文件（如 `01-02`、`S01E01E02`）的最后一集
+    pub episode_end: Option<u32>,
+    /// 小数集数的小数部分（`13.5` 中的 5），通常是总集篇之类的特别篇
+    pub episode_fraction: Option<u32>,
+    /// 字幕组重新发布的版本（`05v2` 中的 2）
+    pub version: Option<u32>,
     pub season_number: Option<u32>,
+    /// 分段播出的第几部分（`Part 2`、`Cour 2`、`第2クール`）
+    pub part: Option<u32>,
+    /// 文件名标注为最终季（`The Final Season`），季度需要按 TMDB 的最后一季确定
+    pub final_season: bool,
     pub episode_type: EpisodeType,
     pub tags: Vec<String>,
+    /// 从标签中识别出的发布信息
+    pub release: ReleaseInfo,
+    /// 括号中的年份（如 `(2019)`、`[2019]`），用于区分同名番剧
+    pub year: Option<u32>,
     pub extension: String,
     pub is_already_formatted: bool,
 }
 
+impl ParsedFile {
+    /// 文件包含的全部集数
+    pub fn episode_numbers(&self) -> RangeInclusive<u32> {
+        self.episode_number..=self.episode_end.unwrap_or(self.episode_number)
+    }
+}
+
+/// 只包含集数（或 `01-02` 之类的集数范围，可带 `v2`、`.5` 后缀）的方括号标签
+fn is_episode_tag(tag: &str) -> bool {
+    let (numbers, suffix) = tag.split_at(tag.find(['.', 'v', 'V']).unwrap_or(tag.len()));
+    if episode_suffix(suffix).len != suffix.len() {
+        return false;
+    }
+
+    let mut parts = numbers.splitn(2, ['-', '~', '&']);
+    parts.all(|part| parse_number(part).is_some())
+}
+
+/// 紧跟在集数后的小数部分与版本号（`.5`、`v2`、`.5v2`）
+#[derive(Debug, Default, PartialEq)]
+struct EpisodeSuffix {
+    fraction: Option<u32>,
+    version: Option<u32>,
+    /// 后缀占用的字节数
+    len: usize,
+}
+
+fn episode_suffix(text: &str) -> EpisodeSuffix {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX
+        .get_or_init(|| Regex::new(r"^(?:\.(\d))?(?:[vV](\d{1,2}))?(?:[^\dA-Za-z]|$)").unwrap());
+
+    let Some(cap) = regex.captures(text) else {
+        return EpisodeSuffix::default();
+    };
+    let value = |group| cap.get(group).and_then(|m| m.as_str().parse().ok());
+    EpisodeSuffix {
+        fraction: value(1),
+        version: value(2),
+        len: cap.get(2).or_else(|| cap.get(1)).map_or(0, |m| m.end()),
+    }
+}
+
+/// `anime_renamer parse` 输出的解析过程
+#[derive(Debug, Serialize)]
+pub struct ParseExplanation {
+    pub season: Vec<MatchAttempt>,
+    pub part: Vec<MatchAttempt>,
+    /// 集数匹配时排除的区间（季度、分段与分辨率标签的位置）
+    pub episode_exclusions: Vec<(usize, usize)>,
+    pub episode: Vec<MatchAttempt>,
+    pub parsed: Option<ParsedFile>,
+}
+
 pub struct FileParser {
     season_chain: MatcherChain,
+    part_chain: MatcherChain,
     episode_chain: MatcherChain,
     tag_regex: Regex,
     special_keywords: Vec<(Regex, EpisodeType)>,
+    /// PV、CM、Menu 等常见于番剧名的花絮关键字，需结合上下文判断
+    contextual_keywords: Vec<(Regex, EpisodeType)>,
     formatted_season_regex: Regex,
     resolution_regex: Regex,
+    year_regex: Regex,
+    final_season_regex: Regex,
     season_cleanup_regexes: Vec<Regex>,
+    /// 配置文件中的番剧名清理规则
+    custom_cleanup: Vec<(Regex, String)>,
     paren_regex: Regex,
     space_regex: Regex,
     already_formatted_regex: Regex,
@@ -43,20 +171,42 @@ impl FileParser {
     pub fn new() -> Self {
         let season_chain = MatcherChain::new()
             .add_matcher(Box::new(SeasonNumberMatcher::new())) // S3
+            .add_matcher(Box::new(OrdinalSeasonMatcher::new())) // 2nd Season
             .add_matcher(Box::new(SeasonWordMatcher::new())) // Season 3
-            .add_matcher(Box::new(ChineseSeasonMatcher::new())) // 第3季
+            .add_matcher(Box::new(ChineseSeasonMatcher::new())) // 第3季、第二期
+            .add_matcher(Box::new(UnicodeRomanSeasonMatcher::new())) // Ⅱ
             .add_matcher(Box::new(RomanSeasonMatcher::new())); // IV
 
+        let part_chain = MatcherChain::new()
+            .add_matcher(Box::new(PartMatcher::new())) // Part 2、2nd Cour
+            .add_matcher(Box::new(JapaneseCourMatcher::new())); // 第2クール
+
         let episode_chain = MatcherChain::new()
             .add_matcher(Box::new(SxEyMatcher::new())) // S01E12
-            .add_matcher(Box::new(ChineseEpisodeMatcher::new())) // 第01集
+            .add_matcher(Box::new(ChineseEpisodeMatcher::new())) // 第01集、第十二话
             .add_matcher(Box::new(EpMatcher::new())) // EP01
             .add_matcher(Box::new(EMatcher::new())) // E220
             .add_matcher(Box::new(BracketEpisodeMatcher::new())) // [01]
             .add_matcher(Box::new(UnderscoreSMatcher::new())) // _S001
-            .add_matcher(Box::new(DelimiterEpisodeMatcher::new())); // - 04
+            .add_matcher(Box::new(DelimiterEpisodeMatcher::new())) // - 04
+            .add_matcher(Box::new(LeadingNumberMatcher::new())); // 05.mkv
 
+        // 花絮关键字后的数字为花絮编号（NCOP1、PV 2）
         let special_keywords = vec![
+            (
+                Regex::new(
+                    r"(?i)\b(?:NC|creditless[\s_-]*)OP[\s_-]?(\d{1,2})?\b|ノンクレ(?:ジット)?\s*OP\s*(\d{1,2})?",
+                )
+                .unwrap(),
+                EpisodeType::NCOP,
+            ),
+            (
+                Regex::new(
+                    r"(?i)\b(?:NC|creditless[\s_-]*)ED[\s_-]?(\d{1,2})?\b|ノンクレ(?:ジット)?\s*ED\s*(\d{1,2})?",
+                )
+                .unwrap(),
+                EpisodeType::NCED,
+            ),
             (
                 Regex::new(r"(?i)剧场版|theater|theatrical|movie|gekijouban|gekijōban").unwrap(),
                 EpisodeType::Movie,
@@ -68,27 +218,86 @@ impl FileParser {
                 EpisodeType::Special,
             ),
         ];
+        let contextual_keywords = vec![
+            (
+                Regex::new(concat!(
+                    r"(?i)\b(?:PV|trailer|teaser|preview)[\s_-]?(\d{1,2})?\b",
+                    r"|予告\s*(\d{1,2})?|预告\s*(\d{1,2})?",
+                ))
+                .unwrap(),
+                EpisodeType::PV,
+            ),
+            (
+                Regex::new(r"(?i)\b(?:CM|spot)[\s_-]?(\d{1,2})?\b").unwrap(),
+                EpisodeType::CM,
+            ),
+            (
+                Regex::new(r"(?i)\bmenu[\s_-]?(\d{1,2})?\b").unwrap(),
+                EpisodeType::Menu,
+            ),
+        ];
 
         Self {
             season_chain,
+            part_chain,
             episode_chain,
             tag_regex: Regex::new(r"\[([^\]]+)\]").unwrap(),
             special_keywords,
+            contextual_keywords,
             formatted_season_regex: Regex::new(r"[Ss](\d{1,2})[Ee]\d{1,4}").unwrap(),
             resolution_regex: Regex::new(r"\[(1080|720|480|2160|4K)[^\]]*\]").unwrap(),
+            year_regex: Regex::new(r"[(\[（]((?:19|20)\d{2})[)\]）]").unwrap(),
+            final_season_regex: Regex::new(FINAL_SEASON_PATTERN).unwrap(),
             season_cleanup_regexes: vec![
+                Regex::new(FINAL_SEASON_PATTERN).unwrap(),
+                Regex::new(r"(?i)\b\d{1,2}(?:st|nd|rd|th)\s*(?:season|part|cour)\b").unwrap(),
+                Regex::new(r"(?i)\b(?:part|cour)\s*\d{1,2}\b").unwrap(),
+                Regex::new(&format!(r"第\s*{CJK_NUMBER}\s*クール")).unwrap(),
+                Regex::new(r"[Ⅰ-Ⅻ]").unwrap(),
                 Regex::new(r"[Ss]eason\s*\d{1,2}").unwrap(),
-                Regex::new(r"第\s*\d{1,2}\s*季").unwrap(),
+                Regex::new(&format!(r"第\s*{CJK_NUMBER}\s*[季期部]")).unwrap(),
                 Regex::new(r"\b[IVX]+\b").unwrap(),
                 Regex::new(r"[Ss]\d{1,2}(?:\s|[\]\[]|$)").unwrap(),
                 Regex::new(r"_[Ss]\d{1,4}").unwrap(),
             ],
+            custom_cleanup: Vec::new(),
             paren_regex: Regex::new(r"\([^)]*\)").unwrap(),
             space_regex: Regex::new(r"\s+").unwrap(),
             already_formatted_regex: Regex::new(r"\s+S\d{2}E\d{2,}\s*").unwrap(),
         }
     }
 
+    /// 在内置规则的基础上加入配置文件中的自定义规则
+    pub fn with_config(config: &ParserConfig) -> Result<Self> {
+        let mut parser = Self::new();
+
+        for (index, rule) in config.season.iter().enumerate() {
+            let matcher = custom_matcher("parser.season", index, rule)?;
+            parser.season_chain = parser.season_chain.add_matcher(Box::new(matcher));
+        }
+        for (index, rule) in config.episode.iter().enumerate() {
+            let matcher = custom_matcher("parser.episode", index, rule)?;
+            parser.episode_chain = parser.episode_chain.add_matcher(Box::new(matcher));
+        }
+
+        let mut special_keywords = Vec::new();
+        for (index, rule) in config.special.iter().enumerate() {
+            let regex = compile_rule("parser.special", index, &rule.pattern)?;
+            special_keywords.push((regex, rule.episode_type.clone()));
+        }
+        special_keywords.append(&mut parser.special_keywords);
+        parser.special_keywords = special_keywords;
+
+        for (index, rule) in config.cleanup.iter().enumerate() {
+            let regex = compile_rule("parser.cleanup", index, &rule.pattern)?;
+            parser
+                .custom_cleanup
+                .push((regex, rule.replacement.clone()));
+        }
+
+        Ok(parser)
+    }
+
     /// 检测是否是特殊内容（OVA/OAD/SP/剧场版等）
     fn detect_special_type(&self, text: &str) -> EpisodeType {
         for (pattern, episode_type) in &self.special_keywords {
@@ -99,12 +308,95 @@ impl FileParser {
         EpisodeType::Normal
     }
 
+    /// 花絮关键字本身作为集数匹配，编号取关键字后的数字，没有时为 1
+    fn extract_extra(&self, text: &str, episode_type: &EpisodeType) -> Option<MatchResult> {
+        let cap = self
+            .special_keywords
+            .iter()
+            .filter(|(_, keyword_type)| keyword_type == episode_type)
+            .find_map(|(pattern, _)| pattern.captures(text))?;
+        let whole = cap.get(0)?;
+        let value = cap
+            .iter()
+            .skip(1)
+            .flatten()
+            .find_map(|number| parse_number(number.as_str()))
+            .unwrap_or(1);
+
+        Some(MatchResult {
+            value,
+            end_value: None,
+            matched_text: whole.as_str().to_string(),
+            start_pos: whole.start(),
+            end_pos: whole.end(),
+        })
+    }
+
+    /// 检测 PV/CM/Menu 花絮：关键字须位于方括号标签内、后接编号、紧跟在集数之后，
+    /// 或没有集数时位于末尾，避免把番剧名中的 Spot、Menu 等单词当作花絮
+    ///
+    /// 返回花絮类型、关键字原文，以及用作集数的匹配（优先关键字编号，其次已匹配的集数）
+    fn detect_contextual_extra(
+        &self,
+        stem: &str,
+        episode: Option<&MatchResult>,
+    ) -> Option<(EpisodeType, String, MatchResult)> {
+        let is_separator = |text: &str| {
+            let text = self.tag_regex.replace_all(text, "");
+            let text = self.paren_regex.replace_all(&text, "");
+            text.chars()
+                .all(|c| c.is_whitespace() || matches!(c, '-' | '_' | '.'))
+        };
+
+        for (pattern, episode_type) in &self.contextual_keywords {
+            for cap in pattern.captures_iter(stem) {
+                let whole = cap.get(0)?;
+                let own_number = cap
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .find_map(|number| parse_number(number.as_str()));
+                let in_tag = self
+                    .tag_regex
+                    .find_iter(stem)
+                    .any(|tag| tag.start() < whole.start() && whole.end() <= tag.end());
+                let after_episode = episode.is_some_and(|episode| {
+                    episode.end_pos <= whole.start()
+                        && is_separator(&stem[episode.end_pos..whole.start()])
+                });
+                let trailing = episode.is_none() && is_separator(&stem[whole.end()..]);
+
+                if !(in_tag || own_number.is_some() || after_episode || trailing) {
+                    continue;
+                }
+
+                let marker = whole.as_str().trim().to_string();
+                let result = match (own_number, episode) {
+                    (None, Some(episode)) => episode.clone(),
+                    (own_number, _) => MatchResult {
+                        value: own_number.unwrap_or(1),
+                        end_value: None,
+                        matched_text: whole.as_str().to_string(),
+                        start_pos: whole.start(),
+                        end_pos: whole.end(),
+                    },
+                };
+                return Some((episode_type.clone(), marker, result));
+            }
+        }
+        None
+    }
+
     fn extract_season(&self, text: &str) -> Option<u32> {
         self.season_chain
             .execute(text, &[])
             .map(|result| result.value)
     }
 
+    fn extract_part(&self, text: &str) -> Option<MatchResult> {
+        self.part_chain.execute(text, &[])
+    }
+
     fn extract_formatted_season(&self, text: &str) -> Option<u32> {
         self.formatted_season_regex
             .captures(text)
@@ -112,7 +404,18 @@ impl FileParser {
             .and_then(|value| value.as_str().parse::<u32>().ok())
     }
 
-    fn extract_episode(&self, text: &str) -> Option<(u32, String)> {
+    fn extract_episode(
+        &self,
+        text: &str,
+        extra_exclusions: &[(usize, usize)],
+    ) -> Option<MatchResult> {
+        let mut exclusions = self.episode_exclusions(text);
+        exclusions.extend_from_slice(extra_exclusions);
+        self.episode_chain.execute(text, &exclusions)
+    }
+
+    /// 集数匹配需要避开的区间
+    fn episode_exclusions(&self, text: &str) -> Vec<(usize, usize)> {
         // 先获取季度的数字位置，避免重叠
         let mut exclude_positions = Vec::new();
 
@@ -120,6 +423,10 @@ impl FileParser {
             exclude_positions.push((season_result.start_pos, season_result.end_pos));
         }
 
+        if let Some(part_result) = self.extract_part(text) {
+            exclude_positions.push((part_result.start_pos, part_result.end_pos));
+        }
+
         // 排除分辨率标签的位置（如 [1080], [720]）
         for cap in self.resolution_regex.captures_iter(text) {
             if let Some(m) = cap.get(0) {
@@ -127,13 +434,26 @@ impl FileParser {
             }
         }
 
-        self.episode_chain
-            .execute(text, &exclude_positions)
-            .map(|result| (result.value, result.matched_text))
+        exclude_positions
+    }
+
+    fn extract_year(&self, stem: &str) -> Option<u32> {
+        self.year_regex
+            .captures(stem)
+            .and_then(|cap| cap[1].parse().ok())
+    }
+
+    fn apply_custom_cleanup(&self, name: &str) -> String {
+        let mut name = name.to_string();
+        for (pattern, replacement) in &self.custom_cleanup {
+            name = pattern.replace_all(&name, replacement.as_str()).to_string();
+        }
+        name
     }
 
     fn clean_anime_name(&self, name: &str) -> String {
-        let mut name = self.tag_regex.replace_all(name, "").to_string();
+        let name = self.apply_custom_cleanup(name);
+        let mut name = self.tag_regex.replace_all(&name, "").to_string();
 
         for (pattern, _) in &self.special_keywords {
             name = pattern.replace_all(&name, " ").to_string();
@@ -143,6 +463,8 @@ impl FileParser {
             name = pattern.replace_all(&name, " ").to_string();
         }
 
+        name = self.year_regex.replace_all(&name, " ").to_string();
+
         // 移除括号及其内容（通常是总集数）
         name = self.paren_regex.replace_all(&name, " ").to_string();
 
@@ -157,7 +479,50 @@ impl FileParser {
         name.trim().to_string()
     }
 
+    /// 记录季度、分段与集数匹配链的每次尝试，并给出最终解析结果；`filename` 带目录时结合目录解析
+    pub fn explain(&self, filename: &str) -> ParseExplanation {
+        let stem = Path::new(filename)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(filename);
+        let episode_exclusions = self.episode_exclusions(stem);
+
+        ParseExplanation {
+            season: self.season_chain.execute_traced(stem, &[]).1,
+            part: self.part_chain.execute_traced(stem, &[]).1,
+            episode: self
+                .episode_chain
+                .execute_traced(stem, &episode_exclusions)
+                .1,
+            episode_exclusions,
+            parsed: self.parse_path(Path::new(filename)),
+        }
+    }
+
+    /// 只按文件名解析，不参考所在目录
+    #[cfg(test)]
     pub fn parse(&self, filename: &str) -> Option<ParsedFile> {
+        self.parse_excluding(filename, &FolderInfo::default(), &[])
+            .map(|(parsed, _)| parsed)
+    }
+
+    /// 结合所在目录解析文件，文件名中的信息优先，缺少的番剧名、季度和年份取自目录
+    pub fn parse_path(&self, path: &Path) -> Option<ParsedFile> {
+        let folder = path
+            .parent()
+            .map(|dir| self.folder_info(dir))
+            .unwrap_or_default();
+        self.parse_excluding(path.file_name()?.to_str()?, &folder, &[])
+            .map(|(parsed, _)| parsed)
+    }
+
+    /// 集数匹配额外避开 `extra_exclusions`，同时返回集数在文件名主干中的位置
+    fn parse_excluding(
+        &self,
+        filename: &str,
+        folder: &FolderInfo,
+        extra_exclusions: &[(usize, usize)],
+    ) -> Option<(ParsedFile, (usize, usize))> {
         let path = Path::new(filename);
         let extension = path
             .extension()
@@ -173,31 +538,51 @@ impl FileParser {
             .map(|cap| cap[1].to_string())
             .collect();
 
-        let episode_type = self.detect_special_type(stem);
+        let mut episode_type = self.detect_special_type(stem);
 
         let is_already_formatted = self.already_formatted_regex.is_match(stem);
 
         let season_number = self
             .extract_season(stem)
-            .or_else(|| self.extract_formatted_season(stem));
-
-        let (episode_number, episode_match) = self.extract_episode(stem)?;
+            .or_else(|| self.extract_formatted_season(stem))
+            .or(folder.season);
 
-        // 找到包含集数的方括号标签的索引（排除分辨率标签）
-        let episode_tag_index = tags.iter().position(|tag| {
-            // 排除常见的分辨率标签
-            if tag.contains("1080")
-                || tag.contains("720")
-                || tag.contains("480")
-                || tag.contains("2160")
-                || tag.contains("4K")
-            {
-                return false;
+        // 花絮关键字只作为花絮标记时才从番剧名中移除
+        let mut extra_marker = None;
+        let episode = match episode_type.extras_folder() {
+            Some(_) => self.extract_extra(stem, &episode_type),
+            None => {
+                let episode = self.extract_episode(stem, extra_exclusions);
+                match self.detect_contextual_extra(stem, episode.as_ref()) {
+                    Some((extra_type, marker, result)) => {
+                        episode_type = extra_type;
+                        extra_marker = Some(marker);
+                        Some(result)
+                    }
+                    None => episode,
+                }
             }
-            tag.parse::<u32>().is_ok()
+        };
+        let MatchResult {
+            value: episode_number,
+            end_value: episode_end,
+            matched_text: episode_match,
+            start_pos: episode_start_pos,
+            end_pos: episode_end_pos,
+        } = episode?;
+        let suffix = episode_suffix(&stem[episode_end_pos..]);
+
+        // 找到包含集数（或花絮关键字）的方括号标签的索引（排除分辨率标签）
+        let is_extra = episode_type.extras_folder().is_some();
+        let episode_tag_index = tags.iter().position(|tag| {
+            !is_release_tag(tag)
+                && (is_episode_tag(tag) || (is_extra && tag.contains(episode_match.as_str())))
         });
 
-        let mut anime_name = if let Some(idx) = episode_tag_index {
+        let mut anime_name = if episode_start_pos == 0 {
+            // 以集数开头（`05 - 标题.mkv`）时，后面通常是单集标题而不是番剧名
+            String::new()
+        } else if let Some(idx) = episode_tag_index {
             // 找到集数标签，现在要从前面的标签中提取番剧名
             // 策略：找到最长的有意义的标签（通常是番剧名）
             let candidate_tags: Vec<&String> = tags[..idx]
@@ -207,9 +592,9 @@ impl FileParser {
                     let tag_lower = tag.to_lowercase();
                     !tag_lower.contains("字幕")
                         && !tag_lower.contains("新番")
-                        && !tag.contains("1080")
-                        && !tag.contains("720")
+                        && !is_release_tag(tag)
                         && tag.len() > 2 // 至少3个字符
+                        && !self.apply_custom_cleanup(tag).trim().is_empty()
                 })
                 .collect();
 
@@ -226,40 +611,81 @@ impl FileParser {
             // 否则使用原逻辑
             let mut name = stem.to_string();
 
-            // 移除集数匹配及后面的括号内容（如果有）
-            let remove_pattern = if episode_match.ends_with('(') {
-                // 如果集数匹配以 ( 结尾，移除到对应的 )
-                let start_pos = name.find(&episode_match).unwrap_or(0);
-                let after_match = &name[start_pos + episode_match.len()..];
-                if let Some(close_pos) = after_match.find(')') {
-                    format!("{}{}", episode_match, &after_match[..=close_pos])
-                } else {
-                    episode_match.clone()
+            // 移除集数匹配、`v2`/`.5` 后缀及后面的括号内容（如果有）
+            let remove_pattern = match name.find(&episode_match) {
+                Some(start_pos) => {
+                    let mut end_pos =
+                        (start_pos + episode_match.len()).max(episode_end_pos + suffix.len);
+                    if episode_match.ends_with('(')
+                        && let Some(close_pos) = name[end_pos..].find(')')
+                    {
+                        // 如果集数匹配以 ( 结尾，移除到对应的 )
+                        end_pos += close_pos + 1;
+                    }
+                    name[start_pos..end_pos].to_string()
                 }
-            } else {
-                episode_match.clone()
+                None => episode_match.clone(),
             };
 
             name = name.replace(&remove_pattern, " ");
             name
         };
 
+        if let Some(marker) = &extra_marker {
+            anime_name = anime_name.replacen(marker.as_str(), " ", 1);
+        }
         anime_name = self.clean_anime_name(&anime_name);
 
         if anime_name.is_empty() {
-            return None;
+            anime_name = folder.show.clone()?;
         }
 
-        Some(ParsedFile {
+        let release = classify_release(stem, &tags);
+        let year = self.extract_year(stem).or(folder.year);
+        let part = self.extract_part(stem).map(|result| result.value);
+        let final_season = self.final_season_regex.is_match(stem);
+
+        let parsed = ParsedFile {
             anime_name,
             episode_number,
+            episode_end,
+            episode_fraction: suffix.fraction,
+            version: suffix.version,
             season_number,
+            part,
+            final_season,
             episode_type,
             tags,
+            release,
+            year,
             extension,
             is_already_formatted,
-        })
+        };
+        Some((parsed, (episode_start_pos, episode_end_pos)))
+    }
+}
+
+/// 编译配置中的正则，错误信息指出是哪一条规则
+fn compile_rule(section: &str, index: usize, pattern: &str) -> Result<Regex> {
+    Regex::new(pattern)
+        .with_context(|| format!("[[{section}]] 第 {} 条规则的正则无效: {pattern}", index + 1))
+}
+
+fn custom_matcher(section: &str, index: usize, rule: &MatcherRule) -> Result<RegexMatcher> {
+    let regex = compile_rule(section, index, &rule.pattern)?;
+    if regex.captures_len() < 2 {
+        bail!(
+            "[[{section}]] 第 {} 条规则缺少捕获数字的分组: {}",
+            index + 1,
+            rule.pattern
+        );
     }
+
+    let name = rule
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("Custom({})", rule.pattern));
+    Ok(RegexMatcher::new(name, regex, rule.priority))
 }
 
 #[cfg(test)]
@@ -275,6 +701,197 @@ mod tests {
         assert_eq!(result.extension, "mkv");
     }
 
+    #[test]
+    fn test_parse_extracts_release_group_and_resolution() {
+        let parser = FileParser::new();
+        let result = parser
+            .parse("[LoliHouse] 孤独搖滾！- 01 [WebRip 1080p HEVC-10bit AAC].mkv")
+            .unwrap();
+
+        assert_eq!(result.release.group.as_deref(), Some("LoliHouse"));
+        assert_eq!(result.release.resolution.as_deref(), Some("1080p"));
+
+        let plain = parser.parse("鬼灭之刃 27.mkv").unwrap();
+        assert_eq!(plain.release.group, None);
+        assert_eq!(plain.release.resolution, None);
+    }
+
+    #[test]
+    fn test_parse_extracts_year() {
+        let parser = FileParser::new();
+
+        let result = parser
+            .parse("Fruits Basket (2019) - 05 [1080p].mkv")
+            .unwrap();
+        assert_eq!(result.anime_name, "Fruits Basket");
+        assert_eq!(result.year, Some(2019));
+        assert_eq!(result.episode_number, 5);
+
+        let full_width = parser.parse("[Group] 水果篮子（2019） - 05.mkv").unwrap();
+        assert_eq!(full_width.anime_name, "水果篮子");
+        assert_eq!(full_width.year, Some(2019));
+
+        assert_eq!(parser.parse("鬼灭之刃 27.mkv").unwrap().year, None);
+    }
+
+    #[test]
+    fn test_parse_episode_ranges() {
+        let parser = FileParser::new();
+        let cases = [
+            ("Show - 01-02 [1080p].mkv", "Show", 1, Some(2)),
+            ("Show S01E01E02.mkv", "Show", 1, Some(2)),
+            ("Show S01E01-E02.mkv", "Show", 1, Some(2)),
+            (
+                "[Group][Frieren][11-12][1080p].mkv",
+                "Frieren",
+                11,
+                Some(12),
+            ),
+            ("Show 第03-04集.mp4", "Show", 3, Some(4)),
+            ("Show EP05~06.mkv", "Show", 5, Some(6)),
+            ("Show - 01.mkv", "Show", 1, None),
+        ];
+
+        for (filename, name, episode, episode_end) in cases {
+            let result = parser.parse(filename).unwrap();
+            assert_eq!(result.anime_name, name, "{filename}");
+            assert_eq!(result.episode_number, episode, "{filename}");
+            assert_eq!(result.episode_end, episode_end, "{filename}");
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_implausible_episode_ranges() {
+        let parser = FileParser::new();
+
+        let result = parser.parse("Show S01E03-1080p.mkv").unwrap();
+        assert_eq!(result.episode_number, 3);
+        assert_eq!(result.episode_end, None);
+
+        let reversed = parser.parse("Show - 05-02.mkv").unwrap();
+        assert_eq!(reversed.episode_number, 5);
+        assert_eq!(reversed.episode_end, None);
+        assert_eq!(reversed.episode_numbers(), 5..=5);
+    }
+
+    #[test]
+    fn test_parse_chinese_numerals_and_full_width_digits() {
+        let parser = FileParser::new();
+        let cases = [
+            ("番剧名 第十二话.mkv", "番剧名", 12, None),
+            ("番剧名 第二季 第03集.mkv", "番剧名", 3, Some(2)),
+            ("番剧名 第二期 - 05.mkv", "番剧名", 5, Some(2)),
+            ("番剧名 第三部 第二十五話.mp4", "番剧名", 25, Some(3)),
+            ("番剧名 - １２.mkv", "番剧名", 12, None),
+            ("[字幕组][番剧名][１２][1080P].mkv", "番剧名", 12, None),
+        ];
+
+        for (filename, name, episode, season) in cases {
+            let result = parser.parse(filename).expect(filename);
+            assert_eq!(result.anime_name, name, "{filename}");
+            assert_eq!(result.episode_number, episode, "{filename}");
+            assert_eq!(result.season_number, season, "{filename}");
+        }
+    }
+
+    #[test]
+    fn test_parse_anime_style_season_and_part_markers() {
+        let parser = FileParser::new();
+        let cases = [
+            ("Show 2nd Season - 05.mkv", "Show", 5, Some(2), None, false),
+            (
+                "Show Season 2 Part 2 - 03.mkv",
+                "Show",
+                3,
+                Some(2),
+                Some(2),
+                false,
+            ),
+            (
+                "Show 3rd Season 2nd Cour - 01.mkv",
+                "Show",
+                1,
+                Some(3),
+                Some(2),
+                false,
+            ),
+            (
+                "[Group] Show Cour 2 - 04 [1080p].mkv",
+                "Show",
+                4,
+                None,
+                Some(2),
+                false,
+            ),
+            ("Show 第2クール - 07.mkv", "Show", 7, None, Some(2), false),
+            ("Show Ⅱ - 08.mkv", "Show", 8, Some(2), None, false),
+            (
+                "Show The Final Season Part 2 - 01.mkv",
+                "Show",
+                1,
+                None,
+                Some(2),
+                true,
+            ),
+        ];
+
+        for (filename, name, episode, season, part, final_season) in cases {
+            let result = parser.parse(filename).expect(filename);
+            assert_eq!(result.anime_name, name, "{filename}");
+            assert_eq!(result.episode_number, episode, "{filename}");
+            assert_eq!(result.season_number, season, "{filename}");
+            assert_eq!(result.part, part, "{filename}");
+            assert_eq!(result.final_season, final_season, "{filename}");
+        }
+    }
+
+    #[test]
+    fn test_parse_versions_and_fractional_episodes() {
+        let parser = FileParser::new();
+        let cases = [
+            ("[Group] Show - 05v2 [1080p].mkv", "Show", 5, None, Some(2)),
+            (
+                "[Group][Frieren][05v2][1080p].mkv",
+                "Frieren",
+                5,
+                None,
+                Some(2),
+            ),
+            ("Show S01E05v3.mkv", "Show", 5, None, Some(3)),
+            ("[Group] Show - 13.5 [1080p].mkv", "Show", 13, Some(5), None),
+            (
+                "[Group][Frieren][13.5v2].mkv",
+                "Frieren",
+                13,
+                Some(5),
+                Some(2),
+            ),
+            ("番剧名 第13.5话.mkv", "番剧名", 13, Some(5), None),
+        ];
+
+        for (filename, name, episode, fraction, version) in cases {
+            let result = parser.parse(filename).expect(filename);
+            assert_eq!(result.anime_name, name, "{filename}");
+            assert_eq!(result.episode_number, episode, "{filename}");
+            assert_eq!(result.episode_fraction, fraction, "{filename}");
+            assert_eq!(result.version, version, "{filename}");
+        }
+
+        let dotted = parser.parse("Show.S01E05.1080p.mkv").unwrap();
+        assert_eq!(dotted.episode_number, 5);
+        assert_eq!(dotted.episode_fraction, None);
+    }
+
+    #[test]
+    fn test_parse_ignores_crc32_tag_as_show_name() {
+        let parser = FileParser::new();
+        let result = parser.parse("[Sub][Frieren][ABCD1234][05].mkv").unwrap();
+
+        assert_eq!(result.anime_name, "Frieren");
+        assert_eq!(result.episode_number, 5);
+        assert_eq!(result.release.crc32.as_deref(), Some("ABCD1234"));
+    }
+
     #[test]
     fn test_parse_simple() {
         let parser = FileParser::new();
@@ -416,35 +1033,212 @@ mod tests {
     #[test]
     fn test_one_punch_man_format() {
         let parser = FileParser::new();
-
         let filename =
             "[LoliHouse] One-Punch Man S3 - 04(28) [WebRip 1080p HEVC-10bit AAC SRTx2].mkv";
-        println!("测试文件: {}", filename);
 
-        let stem = "[LoliHouse] One-Punch Man S3 - 04(28) [WebRip 1080p HEVC-10bit AAC SRTx2]";
+        let explanation = parser.explain(filename);
+        let selected = |attempts: &[MatchAttempt]| {
+            attempts
+                .iter()
+                .find(|attempt| attempt.selected)
+                .map(|attempt| (attempt.matcher.clone(), attempt.value))
+        };
+
+        assert_eq!(
+            selected(&explanation.season),
+            Some(("SeasonNumber(S3)".to_string(), Some(3)))
+        );
+        assert_eq!(
+            selected(&explanation.episode),
+            Some(("Delimiter(- 04)".to_string(), Some(4)))
+        );
+        let parsed = explanation.parsed.unwrap();
+        assert_eq!(parsed.season_number, Some(3));
+        assert_eq!(parsed.episode_number, 4);
+    }
+
+    #[test]
+    fn test_explain_records_matches_skipped_by_exclusions() {
+        let parser = FileParser::new();
 
-        // 测试季度提取
-        let season = parser.extract_season(stem);
-        println!("\n提取到的季度: {:?}", season);
+        let explanation = parser.explain("Show 2nd Season 05.mkv");
+        let delimiter: Vec<&MatchAttempt> = explanation
+            .episode
+            .iter()
+            .filter(|attempt| attempt.matcher == "Delimiter(- 04)")
+            .collect();
 
-        // 测试集数提取
-        let episode = parser.extract_episode(stem);
-        println!("提取到的集数: {:?}", episode);
+        assert_eq!(explanation.episode_exclusions, vec![(5, 6)]);
+        assert_eq!(delimiter.len(), 2);
+        assert!(delimiter[0].excluded && !delimiter[0].selected);
+        assert_eq!(delimiter[1].value, Some(5));
+        assert!(delimiter[1].selected);
+        assert!(explanation.episode[0].span.is_none());
+    }
+
+    fn matcher_rule(name: &str, pattern: &str) -> MatcherRule {
+        MatcherRule {
+            name: Some(name.to_string()),
+            pattern: pattern.to_string(),
+            priority: 0,
+        }
+    }
 
-        let result = parser.parse(filename);
+    #[test]
+    fn test_with_config_adds_custom_rules() {
+        let config = ParserConfig {
+            season: vec![matcher_rule("Vol", r"Vol\.(\d+)")],
+            episode: vec![matcher_rule("Hash", r"#(\d{2})")],
+            special: vec![crate::config::SpecialRule {
+                pattern: r"(?i)\bNCOP\b".to_string(),
+                episode_type: EpisodeType::Special,
+            }],
+            cleanup: vec![crate::config::CleanupRule {
+                pattern: r"Vol\.\d+".to_string(),
+                replacement: " ".to_string(),
+            }],
+        };
+        let parser = FileParser::with_config(&config).unwrap();
 
-        match result {
-            Some(parsed) => {
-                println!("\n解析成功!");
-                println!("  番剧名: {}", parsed.anime_name);
-                println!("  集数: {}", parsed.episode_number);
-                println!("  季度: {:?}", parsed.season_number);
-                assert_eq!(parsed.season_number, Some(3));
-                assert_eq!(parsed.episode_number, 4);
-            }
-            None => {
-                panic!("解析失败");
-            }
+        let result = parser.parse("[Group] Show Vol.2 #05 [1080p].mkv").unwrap();
+        assert_eq!(result.anime_name, "Show");
+        assert_eq!(result.season_number, Some(2));
+        assert_eq!(result.episode_number, 5);
+
+        let explanation = parser.explain("[Group] Show Vol.2 #05 [1080p].mkv");
+        assert_eq!(explanation.episode[0].matcher, "Hash");
+        assert!(explanation.episode[0].selected);
+
+        let tagged = parser.parse("[Sub][K-On][Vol.03][05].mkv").unwrap();
+        assert_eq!(tagged.anime_name, "K-On");
+        assert_eq!(tagged.episode_number, 5);
+
+        let special = parser.parse("[Group] Show NCOP 01.mkv").unwrap();
+        assert_eq!(special.episode_type, EpisodeType::Special);
+    }
+
+    #[test]
+    fn test_with_config_rejects_invalid_rules() {
+        let invalid = ParserConfig {
+            episode: vec![matcher_rule("Broken", r"\[(\d+")],
+            ..ParserConfig::default()
+        };
+        let error = FileParser::with_config(&invalid).err().unwrap();
+        assert!(format!("{error:#}").contains("[[parser.episode]] 第 1 条规则的正则无效"));
+
+        let no_group = ParserConfig {
+            season: vec![matcher_rule("Vol", r"Vol\.\d+")],
+            ..ParserConfig::default()
+        };
+        let error = FileParser::with_config(&no_group).err().unwrap();
+        assert!(error.to_string().contains("缺少捕获数字的分组"));
+    }
+
+    #[test]
+    fn test_parse_path_fills_missing_fields_from_folders() {
+        let parser = FileParser::new();
+
+        let bare = parser
+            .parse_path(Path::new(
+                "/anime/Show Name (2019) [tmdbid=123]/Season 02/05.mkv",
+            ))
+            .unwrap();
+        assert_eq!(bare.anime_name, "Show Name");
+        assert_eq!(bare.season_number, Some(2));
+        assert_eq!(bare.episode_number, 5);
+        assert_eq!(bare.year, Some(2019));
+
+        let titled = parser
+            .parse_path(Path::new(
+                "/anime/Show Name/Specials/03 - Beach Episode.mkv",
+            ))
+            .unwrap();
+        assert_eq!(titled.anime_name, "Show Name");
+        assert_eq!(titled.season_number, Some(0));
+        assert_eq!(titled.episode_number, 3);
+
+        assert!(parser.parse("05 - Beach Episode.mkv").is_none());
+    }
+
+    #[test]
+    fn test_parse_path_prefers_file_name_over_folders() {
+        let parser = FileParser::new();
+
+        let result = parser
+            .parse_path(Path::new(
+                "/anime/Show Name (2019)/Season 02/[Group] Other Name (2020) S3 - 05.mkv",
+            ))
+            .unwrap();
+
+        assert_eq!(result.anime_name, "Other Name");
+        assert_eq!(result.season_number, Some(3));
+        assert_eq!(result.year, Some(2020));
+    }
+
+    #[test]
+    fn test_parse_extras_with_their_own_numbers() {
+        let parser = FileParser::new();
+        let cases = [
+            ("[Group] Frieren NCOP1 [1080p].mkv", EpisodeType::NCOP, 1),
+            ("[Group][Frieren][NCED2][1080p].mkv", EpisodeType::NCED, 2),
+            ("[Group] Frieren - PV 3 [1080p].mkv", EpisodeType::PV, 3),
+            ("[Group] Frieren Preview [1080p].mkv", EpisodeType::PV, 1),
+            ("Frieren CM02.mkv", EpisodeType::CM, 2),
+            ("Frieren Menu.mkv", EpisodeType::Menu, 1),
+            ("[Group] Frieren - 05 [1080p].mkv", EpisodeType::Normal, 5),
+        ];
+
+        for (filename, episode_type, number) in cases {
+            let result = parser.parse(filename).expect(filename);
+            assert_eq!(result.anime_name, "Frieren", "{filename}");
+            assert_eq!(result.episode_type, episode_type, "{filename}");
+            assert_eq!(result.episode_number, number, "{filename}");
         }
+
+        let bare = parser
+            .parse_path(Path::new("/anime/Frieren/NCOP1.mkv"))
+            .unwrap();
+        assert_eq!(bare.anime_name, "Frieren");
+        assert_eq!(bare.episode_type, EpisodeType::NCOP);
+        assert_eq!(EpisodeType::NCOP.extras_folder(), Some("Extras"));
+        assert_eq!(EpisodeType::CM.extras_folder(), Some("Trailers"));
+        assert_eq!(EpisodeType::OVA.extras_folder(), None);
+    }
+
+    #[test]
+    fn test_extras_keyword_needs_tag_or_number() {
+        let parser = FileParser::new();
+        let cases = [
+            (
+                "[SubsPlease] Spot the Difference - 05 (1080p).mkv",
+                "Spot the Difference",
+                5,
+            ),
+            (
+                "[Group] Menu no Nai Shokudou - 03 [1080p].mkv",
+                "Menu no Nai Shokudou",
+                3,
+            ),
+            ("Sneak Preview - 07.mkv", "Sneak Preview", 7),
+            ("[Group] Trailer Park - 12 [720p].mkv", "Trailer Park", 12),
+        ];
+
+        for (filename, name, number) in cases {
+            let result = parser.parse(filename).expect(filename);
+            assert_eq!(result.episode_type, EpisodeType::Normal, "{filename}");
+            assert_eq!(result.anime_name, name, "{filename}");
+            assert_eq!(result.episode_number, number, "{filename}");
+        }
+
+        let tagged = parser.parse("[Group][Frieren][PV][1080p].mkv").unwrap();
+        assert_eq!(tagged.episode_type, EpisodeType::PV);
+        assert_eq!(tagged.anime_name, "Frieren");
+
+        let after_episode = parser
+            .parse("[Group] Frieren - 05 Preview [1080p].mkv")
+            .unwrap();
+        assert_eq!(after_episode.episode_type, EpisodeType::PV);
+        assert_eq!(after_episode.episode_number, 5);
+        assert_eq!(after_episode.anime_name, "Frieren");
     }
 }
@@ -0,0 +1,301 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// 文件名中的发布信息（字幕组、分辨率、编码、片源、字幕语言等）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReleaseInfo {
+    /// 字幕组（文件名以方括号开头时的第一个标签）
+    pub group: Option<String>,
+    pub resolution: Option<String>,
+    /// 视频编码，如 `HEVC`、`x264`
+    pub video_codec: Option<String>,
+    /// 色深，如 `10bit`
+    pub bit_depth: Option<String>,
+    /// 音频编码，如 `AAC`、`FLAC`
+    pub audio_codec: Option<String>,
+    /// 片源，如 `WebRip`、`BDRip`
+    pub source: Option<String>,
+    /// 字幕语言，如 `简繁`、`CHS`、`JPSC`
+    pub subtitle_languages: Vec<String>,
+    /// 方括号中的 8 位十六进制 CRC32 校验值
+    pub crc32: Option<String>,
+    /// 封装格式提示，如 `MKV`、`MP4`
+    pub container: Option<String>,
+}
+
+/// `--keep-tags` 可保留的标签类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TagKind {
+    /// 所有方括号标签（原样保留）
+    All,
+    Group,
+    Resolution,
+    VideoCodec,
+    BitDepth,
+    AudioCodec,
+    Source,
+    Subtitles,
+    Crc32,
+    Container,
+}
+
+impl ReleaseInfo {
+    /// 某一类型的标签值
+    pub fn values(&self, kind: TagKind) -> Vec<&str> {
+        fn single(value: &Option<String>) -> Vec<&str> {
+            value.as_deref().into_iter().collect()
+        }
+
+        match kind {
+            TagKind::All => Vec::new(),
+            TagKind::Group => single(&self.group),
+            TagKind::Resolution => single(&self.resolution),
+            TagKind::VideoCodec => single(&self.video_codec),
+            TagKind::BitDepth => single(&self.bit_depth),
+            TagKind::AudioCodec => single(&self.audio_codec),
+            TagKind::Source => single(&self.source),
+            TagKind::Subtitles => self.subtitle_languages.iter().map(String::as_str).collect(),
+            TagKind::Crc32 => single(&self.crc32),
+            TagKind::Container => single(&self.container),
+        }
+    }
+
+    /// 按 `--keep-tags` 指定的类型挑选要保留的标签；包含 `all` 时保留全部原始标签
+    pub fn kept_tags(&self, raw_tags: &[String], kinds: &[TagKind]) -> Vec<String> {
+        if kinds.contains(&TagKind::All) {
+            return raw_tags.to_vec();
+        }
+
+        kinds
+            .iter()
+            .flat_map(|kind| self.values(*kind))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+struct Patterns {
+    resolution: Regex,
+    video_codec: Regex,
+    bit_depth: Regex,
+    audio_codec: Regex,
+    source: Regex,
+    subtitle_code: Regex,
+    subtitle_cjk: Regex,
+    crc32: Regex,
+    container: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        resolution: resolution_regex().clone(),
+        video_codec: Regex::new(r"(?i)(?-u:\b)(?:HEVC|AVC|AV1|VP9|[xh]\.?26[45])(?-u:\b)").unwrap(),
+        bit_depth: Regex::new(r"(?i)(?-u:\b)(?:8|10|12)[-\s]?bits?(?-u:\b)").unwrap(),
+        audio_codec: Regex::new(
+            r"(?i)(?-u:\b)(?:AAC|FLAC|OPUS|E?AC-?3|DTS(?:-HD)?|TrueHD|MP3|DDP)(?:x\d)?(?-u:\b)",
+        )
+        .unwrap(),
+        source: Regex::new(
+            r"(?i)(?-u:\b)(?:Web-?Rip|WEB-?DL|BD-?Rip|BluRay|Blu-ray|BDMV|DVD-?Rip|HDTV|TV-?Rip|WEB|BD|DVD)(?-u:\b)",
+        )
+        .unwrap(),
+        subtitle_code: Regex::new(r"(?i)(?-u:\b)(?:CHS|CHT|JPSC|JPTC|BIG5)(?-u:\b)").unwrap(),
+        subtitle_cjk: Regex::new(r"^(?:[简繁日中英双]|体|语|内封|内嵌|外挂|字幕)+$").unwrap(),
+        crc32: Regex::new(r"^[0-9A-Fa-f]{8}$").unwrap(),
+        container: Regex::new(r"(?i)(?-u:\b)(?:MKV|MP4|AVI)(?-u:\b)").unwrap(),
+    })
+}
+
+/// 分辨率，如 `1080p`、`1920x1080`、`4K`
+pub(super) fn resolution_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:\d{3,4}[x×](?:2160|1080|720|480)[pi]?|(?:2160|1080|720|480)[pi])\b|\b4K\b|^(?:2160|1080|720|480)$",
+        )
+        .unwrap()
+    })
+}
+
+/// 对方括号标签分类；方括号外（如 `Show.S01E01.1080p.WEB-DL`）的信息只在标签中找不到时使用
+pub fn classify_release(stem: &str, tags: &[String]) -> ReleaseInfo {
+    let patterns = patterns();
+    let find = |regex: &Regex| {
+        tags.iter()
+            .map(String::as_str)
+            .chain(std::iter::once(stem))
+            .find_map(|text| regex.find(text))
+            .map(|value| value.as_str().to_string())
+    };
+
+    let mut subtitle_languages: Vec<String> = Vec::new();
+    for tag in tags {
+        let tokens = tag
+            .split([' ', '_', '&', '+', '-'])
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            let is_cjk = patterns.subtitle_cjk.is_match(token) && token.contains(['简', '繁']);
+            let codes = patterns.subtitle_code.find_iter(token).map(|m| m.as_str());
+            let languages: Vec<&str> = if is_cjk { vec![token] } else { codes.collect() };
+            for language in languages {
+                if !subtitle_languages.iter().any(|known| known == language) {
+                    subtitle_languages.push(language.to_string());
+                }
+            }
+        }
+    }
+
+    ReleaseInfo {
+        group: release_group(stem, tags),
+        resolution: find(&patterns.resolution),
+        video_codec: find(&patterns.video_codec),
+        bit_depth: find(&patterns.bit_depth),
+        audio_codec: find(&patterns.audio_codec),
+        source: find(&patterns.source),
+        subtitle_languages,
+        crc32: tags.iter().rev().find(|tag| is_crc32_tag(tag)).cloned(),
+        container: tags
+            .iter()
+            .find_map(|tag| patterns.container.find(tag))
+            .map(|value| value.as_str().to_string()),
+    }
+}
+
+/// 文件名中方括号（或圆括号）内的 CRC32 校验值，如 `[ABCD1234]`；有多个时取最后一个
+pub fn extract_crc32(file_name: &str) -> Option<u32> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| Regex::new(r"[\[(]([0-9A-Fa-f]{8})[\])]").unwrap());
+
+    regex
+        .captures_iter(file_name)
+        .map(|cap| cap.get(1).unwrap().as_str())
+        .filter(|tag| is_crc32_tag(tag))
+        .last()
+        .and_then(|tag| u32::from_str_radix(tag, 16).ok())
+}
+
+/// 8 位十六进制的 CRC32 标签；全是数字时更可能是日期之类的编号
+fn is_crc32_tag(tag: &str) -> bool {
+    patterns().crc32.is_match(tag) && !tag.chars().all(|c| c.is_ascii_digit())
+}
+
+/// 标签是否只描述发布信息（分辨率、编码、片源、字幕语言等），不会是番剧名
+pub(super) fn is_release_tag(tag: &str) -> bool {
+    let patterns = patterns();
+    [
+        &patterns.resolution,
+        &patterns.video_codec,
+        &patterns.source,
+        &patterns.audio_codec,
+        &patterns.subtitle_code,
+    ]
+    .iter()
+    .any(|regex| regex.is_match(tag))
+        || patterns.subtitle_cjk.is_match(tag)
+        || is_crc32_tag(tag)
+}
+
+fn release_group(stem: &str, tags: &[String]) -> Option<String> {
+    if !stem.starts_with('[') {
+        return None;
+    }
+
+    tags.first()
+        .filter(|tag| tag.parse::<u32>().is_err())
+        .filter(|tag| !resolution_regex().is_match(tag))
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify_release_splits_combined_tag() {
+        let stem = "[LoliHouse] Bocchi the Rock! - 01 [WebRip 1080p HEVC-10bit AAC][简繁内封字幕]";
+        let info = classify_release(
+            stem,
+            &tags(&["LoliHouse", "WebRip 1080p HEVC-10bit AAC", "简繁内封字幕"]),
+        );
+
+        assert_eq!(info.group.as_deref(), Some("LoliHouse"));
+        assert_eq!(info.resolution.as_deref(), Some("1080p"));
+        assert_eq!(info.video_codec.as_deref(), Some("HEVC"));
+        assert_eq!(info.bit_depth.as_deref(), Some("10bit"));
+        assert_eq!(info.audio_codec.as_deref(), Some("AAC"));
+        assert_eq!(info.source.as_deref(), Some("WebRip"));
+        assert_eq!(info.subtitle_languages, vec!["简繁内封字幕"]);
+        assert_eq!(info.crc32, None);
+    }
+
+    #[test]
+    fn test_classify_release_reads_crc_subtitle_codes_and_container() {
+        let stem = "[Group][Show][05][BDRip 1920x1080 x264 FLAC][CHS_JPSC][MKV][ABCD1234]";
+        let info = classify_release(
+            stem,
+            &tags(&[
+                "Group",
+                "Show",
+                "05",
+                "BDRip 1920x1080 x264 FLAC",
+                "CHS_JPSC",
+                "MKV",
+                "ABCD1234",
+            ]),
+        );
+
+        assert_eq!(info.resolution.as_deref(), Some("1920x1080"));
+        assert_eq!(info.video_codec.as_deref(), Some("x264"));
+        assert_eq!(info.audio_codec.as_deref(), Some("FLAC"));
+        assert_eq!(info.source.as_deref(), Some("BDRip"));
+        assert_eq!(info.subtitle_languages, vec!["CHS", "JPSC"]);
+        assert_eq!(info.container.as_deref(), Some("MKV"));
+        assert_eq!(info.crc32.as_deref(), Some("ABCD1234"));
+    }
+
+    #[test]
+    fn test_extract_crc32_ignores_numeric_tags() {
+        assert_eq!(
+            extract_crc32("[Group] Show - 01 [1080p][ABCD1234].mkv"),
+            Some(0xABCD_1234)
+        );
+        assert_eq!(
+            extract_crc32("[Group] Show - 01 (5a1f09c3).mkv"),
+            Some(0x5A1F_09C3)
+        );
+        assert_eq!(extract_crc32("[Group] Show - 01 [20240101].mkv"), None);
+        assert_eq!(extract_crc32("Show - 01.mkv"), None);
+    }
+
+    #[test]
+    fn test_classify_release_falls_back_to_dotted_names() {
+        let info = classify_release("Show.S01E05.1080p.WEB-DL.x265.10bit", &[]);
+
+        assert_eq!(info.group, None);
+        assert_eq!(info.resolution.as_deref(), Some("1080p"));
+        assert_eq!(info.source.as_deref(), Some("WEB-DL"));
+        assert_eq!(info.video_codec.as_deref(), Some("x265"));
+        assert_eq!(info.bit_depth.as_deref(), Some("10bit"));
+    }
+
+    #[test]
+    fn test_kept_tags_selects_kinds_in_order() {
+        let raw = tags(&["LoliHouse", "WebRip 1080p HEVC-10bit AAC"]);
+        let info = classify_release("[LoliHouse] Show - 01 [WebRip 1080p HEVC-10bit AAC]", &raw);
+
+        assert_eq!(
+            info.kept_tags(&raw, &[TagKind::Resolution, TagKind::Group]),
+            vec!["1080p", "LoliHouse"]
+        );
+        assert_eq!(info.kept_tags(&raw, &[TagKind::All]), raw);
+        assert!(info.kept_tags(&raw, &[TagKind::Crc32]).is_empty());
+    }
+}
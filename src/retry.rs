@@ -0,0 +1,256 @@
+use crate::config::RetryConfig;
+use crate::output::status;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// TMDB / AniList 共用的重试策略：指数退避加随机抖动，优先遵循 `Retry-After`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    service: &'static str,
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    /// 克隆的策略共享同一计数，用于统计整次运行的重试次数
+    retries: Arc<AtomicUsize>,
+}
+
+impl RetryPolicy {
+    pub fn new(service: &'static str, config: &RetryConfig) -> Self {
+        Self {
+            service,
+            max_attempts: config.max_attempts.max(1),
+            initial_delay: Duration::from_millis(config.initial_delay_ms),
+            max_delay: Duration::from_secs(config.max_delay_seconds),
+            retries: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn retry_count(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// 发送请求；429、5xx、超时与连接失败会按策略重试，其余结果原样返回
+    pub async fn send(&self, request: impl Fn() -> RequestBuilder) -> reqwest::Result<Response> {
+        let request = &request;
+        self.run(|| async move { classify(request().send().await) })
+            .await
+    }
+
+    /// 反复执行 `operation` 直到完成或用完尝试次数，所有可重试的情况共用同一计数；
+    /// 用完后返回最后一次的结果
+    pub async fn run<T, Fut>(&self, mut operation: impl FnMut() -> Fut) -> T
+    where
+        Fut: Future<Output = Attempt<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Attempt::Done(result) => return result,
+                Attempt::Retry {
+                    result,
+                    reason,
+                    retry_after,
+                } => {
+                    if !self.wait(attempt, &reason, retry_after).await {
+                        return result;
+                    }
+                }
+            }
+            attempt += 1;
+        }
+    }
+
+    /// 第 `attempt` 次尝试失败后等待；已达到最大尝试次数，
+    /// 或 `Retry-After` 要求的等待超过 `max_delay` 时返回 false
+    async fn wait(&self, attempt: u32, reason: &str, retry_after: Option<Duration>) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        let delay = match retry_after {
+            Some(delay) if delay > self.max_delay => {
+                status!(
+                    "{} 请求失败（{reason}），服务器要求 {} 秒后重试，超过最长等待 {} 秒，不再重试",
+                    self.service,
+                    delay.as_secs(),
+                    self.max_delay.as_secs()
+                );
+                return false;
+            }
+            Some(delay) => delay,
+            None => self.backoff_delay(attempt),
+        };
+        self.retries.fetch_add(1, Ordering::Relaxed);
+        status!(
+            "{} 请求失败（{reason}），{:.1} 秒后重试（{}/{}）",
+            self.service,
+            delay.as_secs_f64(),
+            attempt + 1,
+            self.max_attempts
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
+
+    /// 第 n 次失败后等待 `initial_delay * 2^(n-1)`（不超过 `max_delay`），并随机取其 50%–100%
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_delay);
+        let jitter = RandomState::new().hash_one(attempt) % 1000;
+        exponential / 2 + exponential / 2 * u32::try_from(jitter).unwrap_or(0) / 1000
+    }
+}
+
+/// 一次尝试的结果；可重试时仍附带本次结果，用完尝试次数后原样返回
+pub enum Attempt<T> {
+    Done(T),
+    Retry {
+        result: T,
+        reason: String,
+        retry_after: Option<Duration>,
+    },
+}
+
+/// 429、5xx、超时与连接失败可以重试
+pub fn classify(result: reqwest::Result<Response>) -> Attempt<reqwest::Result<Response>> {
+    let (reason, retry_after) = match &result {
+        Ok(response) if is_retryable_status(response.status()) => (
+            response.status().to_string(),
+            parse_retry_after(response.headers()),
+        ),
+        Err(error) if error.is_timeout() || error.is_connect() => (error.to_string(), None),
+        _ => return Attempt::Done(result),
+    };
+    Attempt::Retry {
+        result,
+        reason,
+        retry_after,
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// 只支持秒数形式的 `Retry-After`（TMDB 与 AniList 均使用该形式）
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn make_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            "TMDB",
+            &RetryConfig {
+                max_attempts,
+                initial_delay_ms: 1000,
+                max_delay_seconds: 5,
+            },
+        )
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_with_jitter_and_caps() {
+        let policy = make_policy(10);
+
+        for (attempt, full) in [(1, 1000), (2, 2000), (3, 4000), (4, 5000), (9, 5000)] {
+            let delay = policy.backoff_delay(attempt).as_millis();
+            assert!(
+                (full / 2..=full).contains(&delay),
+                "attempt {attempt}: {delay}ms"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after_reads_seconds_only() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("60"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(60)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_retryable_status_covers_rate_limit_and_server_errors() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn test_wait_stops_at_max_attempts_and_counts_retries() {
+        let policy = make_policy(2);
+        let shared = policy.clone();
+
+        assert!(policy.wait(1, "503", Some(Duration::ZERO)).await);
+        assert!(!policy.wait(2, "503", Some(Duration::ZERO)).await);
+        assert_eq!(shared.retry_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_gives_up_when_retry_after_exceeds_max_delay() {
+        let policy = make_policy(3);
+
+        assert!(!policy.wait(1, "429", Some(Duration::from_secs(3600))).await);
+        assert_eq!(policy.retry_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_shares_attempts_across_retry_reasons() {
+        let policy = RetryPolicy::new(
+            "AniList",
+            &RetryConfig {
+                max_attempts: 3,
+                initial_delay_ms: 0,
+                max_delay_seconds: 0,
+            },
+        );
+        let mut calls = 0;
+
+        let result = policy
+            .run(|| {
+                calls += 1;
+                let reason = if calls % 2 == 0 {
+                    "Too Many Requests"
+                } else {
+                    "503"
+                };
+                async move {
+                    Attempt::Retry {
+                        result: calls,
+                        reason: reason.to_string(),
+                        retry_after: None,
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result, 3);
+        assert_eq!(policy.retry_count(), 2);
+    }
+}
@@ -0,0 +1,499 @@
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+/// 默认文件名模板
+pub const DEFAULT_TEMPLATE: &str =
+    "{show} S{season:02}E{episode:02}<-E{episode_end:02}>< - {episode_title}>{tags}";
+/// `--season-folders` 时为不含目录的模板添加的前缀
+pub const SEASON_FOLDER_PREFIX: &str = "Season {season}/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Show,
+    Season,
+    Episode,
+    EpisodeEnd,
+    EpisodeTitle,
+    Absolute,
+    Year,
+    TmdbId,
+    Group,
+    Resolution,
+    VideoCodec,
+    BitDepth,
+    AudioCodec,
+    Source,
+    Subtitles,
+    Crc32,
+    Tags,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "show" => Some(Self::Show),
+            "season" => Some(Self::Season),
+            "episode" => Some(Self::Episode),
+            "episode_end" => Some(Self::EpisodeEnd),
+            "episode_title" => Some(Self::EpisodeTitle),
+            "absolute" => Some(Self::Absolute),
+            "year" => Some(Self::Year),
+            "tmdb_id" => Some(Self::TmdbId),
+            "group" => Some(Self::Group),
+            "resolution" => Some(Self::Resolution),
+            "video_codec" => Some(Self::VideoCodec),
+            "bit_depth" => Some(Self::BitDepth),
+            "audio_codec" => Some(Self::AudioCodec),
+            "source" => Some(Self::Source),
+            "subtitles" => Some(Self::Subtitles),
+            "crc32" => Some(Self::Crc32),
+            "tags" => Some(Self::Tags),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Self::Season
+                | Self::Episode
+                | Self::EpisodeEnd
+                | Self::Absolute
+                | Self::Year
+                | Self::TmdbId
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    placeholder: Placeholder,
+    /// `{season:02}` 中的补零宽度
+    zero_pad: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+    /// `<...>` 可选片段：其中任一占位符为空时整段省略
+    Optional(Vec<Segment>),
+}
+
+/// 渲染模板所需的字段值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateValues {
+    pub show: String,
+    pub season: u32,
+    pub episode: u32,
+    /// 多集文件的最后一集
+    pub episode_end: Option<u32>,
+    pub episode_title: Option<String>,
+    pub absolute: Option<u32>,
+    pub year: Option<u32>,
+    pub tmdb_id: Option<u32>,
+    pub group: Option<String>,
+    pub resolution: Option<String>,
+    pub video_codec: Option<String>,
+    pub bit_depth: Option<String>,
+    pub audio_codec: Option<String>,
+    pub source: Option<String>,
+    /// 字幕语言，渲染时以 `&` 连接
+    pub subtitles: Vec<String>,
+    pub crc32: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// 文件名模板，使用 `/` 分隔目录层级，渲染结果不含扩展名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let trimmed = source.trim();
+        if trimmed.is_empty() {
+            bail!("文件名模板不能为空");
+        }
+        if trimmed.starts_with('/') || trimmed.starts_with('\\') {
+            bail!("文件名模板必须是相对路径: {source}");
+        }
+
+        let mut segments = Vec::new();
+        let mut optional: Option<Vec<Segment>> = None;
+        let mut literal = String::new();
+        let mut chars = trimmed.chars().peekable();
+
+        fn flush(literal: &mut String, target: &mut Vec<Segment>) {
+            if !literal.is_empty() {
+                target.push(Segment::Literal(std::mem::take(literal)));
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(value) => spec.push(value),
+                            None => bail!("文件名模板中的占位符未闭合: {source}"),
+                        }
+                    }
+                    let field = parse_field(&spec)?;
+                    let target = optional.as_mut().unwrap_or(&mut segments);
+                    flush(&mut literal, target);
+                    target.push(Segment::Field(field));
+                }
+                '}' => bail!("文件名模板中存在多余的 '}}': {source}"),
+                '<' => {
+                    if optional.is_some() {
+                        bail!("文件名模板不支持嵌套可选片段: {source}");
+                    }
+                    flush(&mut literal, &mut segments);
+                    optional = Some(Vec::new());
+                }
+                '>' => {
+                    let Some(mut inner) = optional.take() else {
+                        bail!("文件名模板中存在多余的 '>': {source}");
+                    };
+                    flush(&mut literal, &mut inner);
+                    segments.push(Segment::Optional(inner));
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if optional.is_some() {
+            bail!("文件名模板中的可选片段未闭合: {source}");
+        }
+        flush(&mut literal, &mut segments);
+
+        for segment in &segments {
+            if let Segment::Literal(text) = segment
+                && text.split(['/', '\\']).any(|part| part.trim() == "..")
+            {
+                bail!("文件名模板不能包含上级目录 '..': {source}");
+            }
+        }
+
+        Ok(Self {
+            source: trimmed.to_string(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// 模板是否自带目录层级
+    pub fn has_directories(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Literal(text) => text.contains('/'),
+            Segment::Optional(inner) => inner
+                .iter()
+                .any(|item| matches!(item, Segment::Literal(text) if text.contains('/'))),
+            Segment::Field(_) => false,
+        })
+    }
+
+    /// 渲染为相对路径（不含扩展名）
+    pub fn render(&self, values: &TemplateValues) -> PathBuf {
+        let mut rendered = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Field(field) => {
+                    rendered.push_str(&render_field(field, values).unwrap_or_default())
+                }
+                Segment::Optional(inner) => {
+                    let mut section = String::new();
+                    let mut complete = true;
+                    for item in inner {
+                        match item {
+                            Segment::Literal(text) => section.push_str(text),
+                            Segment::Field(field) => match render_field(field, values) {
+                                Some(value) => section.push_str(&value),
+                                None => {
+                                    complete = false;
+                                    break;
+                                }
+                            },
+                            Segment::Optional(_) => unreachable!("nested optional segments"),
+                        }
+                    }
+                    if complete {
+                        rendered.push_str(&section);
+                    }
+                }
+            }
+        }
+
+        rendered
+            .split(['/', '\\'])
+            .map(tidy_component)
+            .filter(|component| !component.is_empty())
+            .collect()
+    }
+}
+
+fn parse_field(spec: &str) -> Result<Field> {
+    let (name, format) = match spec.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format.trim())),
+        None => (spec.trim(), None),
+    };
+
+    let Some(placeholder) = Placeholder::from_name(name) else {
+        bail!("未知的模板占位符: {{{name}}}");
+    };
+
+    let zero_pad = match format {
+        None | Some("") => None,
+        Some(format) => {
+            let width = format
+                .strip_prefix('0')
+                .and_then(|width| width.parse::<usize>().ok())
+                .filter(|_| placeholder.is_numeric());
+            match width {
+                Some(width) => Some(width),
+                None => bail!("不支持的格式说明 {{{name}:{format}}}，仅数字占位符支持 :0N 补零"),
+            }
+        }
+    };
+
+    Ok(Field {
+        placeholder,
+        zero_pad,
+    })
+}
+
+fn render_number(value: u32, zero_pad: Option<usize>) -> String {
+    match zero_pad {
+        Some(width) => format!("{value:0width$}"),
+        None => value.to_string(),
+    }
+}
+
+/// 返回 `None` 表示该占位符没有可用的值
+fn render_field(field: &Field, values: &TemplateValues) -> Option<String> {
+    let text = |value: Option<&str>| {
+        value
+            .map(sanitize_component)
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    match field.placeholder {
+        Placeholder::Show => text(Some(&values.show)),
+        Placeholder::Season => Some(render_number(values.season, field.zero_pad)),
+        Placeholder::Episode => Some(render_number(values.episode, field.zero_pad)),
+        Placeholder::EpisodeEnd => values
+            .episode_end
+            .map(|value| render_number(value, field.zero_pad)),
+        Placeholder::EpisodeTitle => text(values.episode_title.as_deref()),
+        Placeholder::Absolute => values
+            .absolute
+            .map(|value| render_number(value, field.zero_pad)),
+        Placeholder::Year => values
+            .year
+            .map(|value| render_number(value, field.zero_pad)),
+        Placeholder::TmdbId => values
+            .tmdb_id
+            .map(|value| render_number(value, field.zero_pad)),
+        Placeholder::Group => text(values.group.as_deref()),
+        Placeholder::Resolution => text(values.resolution.as_deref()),
+        Placeholder::VideoCodec => text(values.video_codec.as_deref()),
+        Placeholder::BitDepth => text(values.bit_depth.as_deref()),
+        Placeholder::AudioCodec => text(values.audio_codec.as_deref()),
+        Placeholder::Source => text(values.source.as_deref()),
+        Placeholder::Subtitles => text(Some(&values.subtitles.join("&"))),
+        Placeholder::Crc32 => text(values.crc32.as_deref()),
+        Placeholder::Tags => {
+            let tags = values
+                .tags
+                .iter()
+                .map(|tag| format!("[{}]", sanitize_component(tag)))
+                .collect::<String>();
+            (!tags.is_empty()).then_some(tags)
+        }
+    }
+}
+
+/// 将路径分隔符和 Windows 保留字符替换为全角字符，避免字段值破坏目录结构
+pub fn sanitize_component(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            '/' => '／',
+            '\\' => '＼',
+            ':' => '：',
+            '*' => '＊',
+            '?' => '？',
+            '"' => '＂',
+            '<' => '＜',
+            '>' => '＞',
+            '|' => '｜',
+            other => other,
+        })
+        .collect()
+}
+
+fn tidy_component(component: &str) -> String {
+    component
+        .split(' ')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches('.')
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn sample_values() -> TemplateValues {
+        TemplateValues {
+            show: "孤独摇滚".to_string(),
+            season: 1,
+            episode: 3,
+            episode_end: None,
+            episode_title: None,
+            absolute: Some(3),
+            year: Some(2022),
+            tmdb_id: Some(119100),
+            group: Some("LoliHouse".to_string()),
+            resolution: Some("1080p".to_string()),
+            video_codec: Some("HEVC".to_string()),
+            bit_depth: Some("10bit".to_string()),
+            audio_codec: Some("AAC".to_string()),
+            source: Some("WebRip".to_string()),
+            subtitles: vec!["CHS".to_string(), "JPSC".to_string()],
+            crc32: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_default_template_matches_legacy_output() {
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        let mut values = sample_values();
+
+        assert_eq!(template.render(&values), Path::new("孤独摇滚 S01E03"));
+
+        values.tags = vec!["LoliHouse".to_string(), "1080p".to_string()];
+        assert_eq!(
+            template.render(&values),
+            Path::new("孤独摇滚 S01E03[LoliHouse][1080p]")
+        );
+    }
+
+    #[test]
+    fn test_default_template_renders_episode_range() {
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        let values = TemplateValues {
+            episode_end: Some(4),
+            ..sample_values()
+        };
+
+        assert_eq!(template.render(&values), Path::new("孤独摇滚 S01E03-E04"));
+    }
+
+    #[test]
+    fn test_template_supports_directories_and_padding() {
+        let template = Template::parse(
+            "{show} ({year}) [tmdbid={tmdb_id}]/Season {season:02}/{show} - {absolute:03}",
+        )
+        .unwrap();
+
+        assert!(template.has_directories());
+        assert_eq!(
+            template.render(&sample_values()),
+            Path::new("孤独摇滚 (2022) [tmdbid=119100]/Season 01/孤独摇滚 - 003")
+        );
+    }
+
+    #[test]
+    fn test_optional_segment_is_dropped_when_value_missing() {
+        let template =
+            Template::parse("{show} S{season:02}E{episode:02}< - {episode_title}>").unwrap();
+        let mut values = sample_values();
+
+        assert_eq!(template.render(&values), Path::new("孤独摇滚 S01E03"));
+
+        values.episode_title = Some("Be Right There".to_string());
+        assert_eq!(
+            template.render(&values),
+            Path::new("孤独摇滚 S01E03 - Be Right There")
+        );
+    }
+
+    #[test]
+    fn test_missing_values_do_not_leave_double_spaces() {
+        let template = Template::parse("{show} {group} E{episode}").unwrap();
+        let values = TemplateValues {
+            group: None,
+            ..sample_values()
+        };
+
+        assert_eq!(template.render(&values), Path::new("孤独摇滚 E3"));
+    }
+
+    #[test]
+    fn test_template_renders_release_info() {
+        let template = Template::parse(
+            "{show} E{episode:02} [{source} {resolution} {video_codec} {bit_depth} {audio_codec}][{subtitles}]<[{crc32}]>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template.render(&sample_values()),
+            Path::new("孤独摇滚 E03 [WebRip 1080p HEVC 10bit AAC][CHS&JPSC]")
+        );
+    }
+
+    #[test]
+    fn test_field_values_cannot_inject_path_separators() {
+        let template = Template::parse("{show}/{episode_title}").unwrap();
+        let values = TemplateValues {
+            show: "Fate/Zero".to_string(),
+            episode_title: Some("A: B?".to_string()),
+            ..sample_values()
+        };
+
+        assert_eq!(template.render(&values), Path::new("Fate／Zero/A： B？"));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(Template::parse("").is_err());
+        assert!(Template::parse("{unknown}").is_err());
+        assert!(Template::parse("{show:02}").is_err());
+        assert!(Template::parse("{show").is_err());
+        assert!(Template::parse("<{show}").is_err());
+        assert!(Template::parse("/abs/{show}").is_err());
+        assert!(Template::parse("../{show}").is_err());
+    }
+
+    #[test]
+    fn test_escaped_braces_are_literals() {
+        let template = Template::parse("{{{show}}}").unwrap();
+
+        assert_eq!(template.render(&sample_values()), Path::new("{孤独摇滚}"));
+    }
+}
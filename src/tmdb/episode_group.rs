@@ -0,0 +1,292 @@
+use super::{Episode, Season};
+use crate::parser::season_directory;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// TMDB 剧集组类型，数值与 API 返回的 `type` 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpisodeGroupType {
+    OriginalAirDate = 1,
+    Absolute = 2,
+    Dvd = 3,
+    Digital = 4,
+    StoryArc = 5,
+    Production = 6,
+    Tv = 7,
+}
+
+impl EpisodeGroupType {
+    const ALL: [(Self, &'static str); 7] = [
+        (Self::OriginalAirDate, "original"),
+        (Self::Absolute, "absolute"),
+        (Self::Dvd, "dvd"),
+        (Self::Digital, "digital"),
+        (Self::StoryArc, "story-arc"),
+        (Self::Production, "production"),
+        (Self::Tv, "tv"),
+    ];
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .map(|(kind, _)| *kind)
+            .find(|kind| *kind as u8 == code)
+    }
+
+    pub fn keyword(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(kind, _)| *kind == self)
+            .map(|(_, keyword)| *keyword)
+            .expect("所有剧集组类型都有关键字")
+    }
+}
+
+/// `--episode-group` 的取值：剧集组 ID，或按类型自动选择
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpisodeGroupSelector {
+    Id(String),
+    Type(EpisodeGroupType),
+}
+
+impl FromStr for EpisodeGroupSelector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let keyword = value.to_ascii_lowercase().replace('_', "-");
+        if let Some((kind, _)) = EpisodeGroupType::ALL
+            .iter()
+            .find(|(_, name)| *name == keyword)
+        {
+            return Ok(Self::Type(*kind));
+        }
+        if value.len() == 24 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(Self::Id(value.to_ascii_lowercase()));
+        }
+
+        let keywords = EpisodeGroupType::ALL
+            .iter()
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join("、");
+        Err(format!(
+            "无效的剧集组: {value}（应为 24 位剧集组 ID 或类型：{keywords}）"
+        ))
+    }
+}
+
+impl fmt::Display for EpisodeGroupSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => f.write_str(id),
+            Self::Type(kind) => f.write_str(kind.keyword()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct EpisodeGroupList {
+    pub(super) results: Vec<EpisodeGroupSummary>,
+}
+
+/// `/tv/{id}/episode_groups` 中的一项
+#[derive(Debug, Clone, Deserialize)]
+pub struct EpisodeGroupSummary {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub group_type: u8,
+    #[serde(default)]
+    pub episode_count: u32,
+}
+
+/// `/tv/episode_group/{id}`：按顺序排列的分组，每组对应整理后的一季
+#[derive(Debug, Clone, Deserialize)]
+pub struct EpisodeGroupDetails {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub groups: Vec<EpisodeGroup>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EpisodeGroup {
+    pub name: String,
+    #[serde(default)]
+    pub order: u32,
+    #[serde(default)]
+    pub episodes: Vec<GroupEpisode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupEpisode {
+    /// 在分组内的位置（从 0 开始）
+    #[serde(default)]
+    pub order: u32,
+    #[serde(flatten)]
+    pub episode: Episode,
+}
+
+impl EpisodeGroupDetails {
+    /// 各分组按 `order` 排列后对应的季号：名为 `Specials` 的分组为第 0 季，其余从 1 依次编号
+    fn numbered_groups(&self) -> Vec<(u32, &EpisodeGroup)> {
+        let mut groups: Vec<&EpisodeGroup> = self.groups.iter().collect();
+        groups.sort_by_key(|group| group.order);
+
+        let mut next = 1;
+        groups
+            .into_iter()
+            .map(|group| {
+                if season_directory(&group.name) == Some(0) {
+                    (0, group)
+                } else {
+                    next += 1;
+                    (next - 1, group)
+                }
+            })
+            .collect()
+    }
+
+    /// 剧集组中的各季，用于按绝对集数累加映射
+    pub fn seasons(&self) -> Vec<Season> {
+        self.numbered_groups()
+            .into_iter()
+            .map(|(season_number, group)| Season {
+                season_number,
+                episode_count: group.episodes.len() as u32,
+                name: group.name.clone(),
+                poster_path: None,
+            })
+            .collect()
+    }
+
+    /// 剧集组中的 (季, 集) → TMDB 单集；单集中的季号与集数仍为 TMDB 默认排序中的值
+    pub fn episode_map(&self) -> HashMap<(u32, u32), Episode> {
+        let mut map = HashMap::new();
+        for (season_number, group) in self.numbered_groups() {
+            let mut episodes: Vec<&GroupEpisode> = group.episodes.iter().collect();
+            episodes.sort_by_key(|episode| episode.order);
+            for (index, episode) in episodes.into_iter().enumerate() {
+                map.insert((season_number, index as u32 + 1), episode.episode.clone());
+            }
+        }
+        map
+    }
+}
+
+/// 按类型选择剧集组：同类型有多个时取集数最多的一个
+pub fn select_episode_group(
+    groups: &[EpisodeGroupSummary],
+    group_type: EpisodeGroupType,
+) -> Option<&EpisodeGroupSummary> {
+    groups
+        .iter()
+        .filter(|group| group.group_type == group_type as u8)
+        .max_by_key(|group| group.episode_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn group_episode(order: u32, season_number: u32, episode_number: u32) -> serde_json::Value {
+        json!({
+            "order": order,
+            "id": season_number * 1000 + episode_number,
+            "name": format!("S{season_number}E{episode_number}"),
+            "season_number": season_number,
+            "episode_number": episode_number,
+        })
+    }
+
+    fn make_group() -> EpisodeGroupDetails {
+        serde_json::from_value(json!({
+            "id": "5b11b4ab925141355f009c2d",
+            "name": "Absolute",
+            "groups": [
+                {"name": "Arc 2", "order": 2, "episodes": [group_episode(0, 2, 1)]},
+                {"name": "Specials", "order": 0, "episodes": [group_episode(0, 0, 1)]},
+                {"name": "Arc 1", "order": 1, "episodes": [
+                    group_episode(1, 1, 2),
+                    group_episode(0, 1, 1),
+                ]},
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_selector_parses_types_and_ids() {
+        assert_eq!(
+            "absolute".parse(),
+            Ok(EpisodeGroupSelector::Type(EpisodeGroupType::Absolute))
+        );
+        assert_eq!(
+            "Story_Arc".parse(),
+            Ok(EpisodeGroupSelector::Type(EpisodeGroupType::StoryArc))
+        );
+        assert_eq!(
+            "5B11B4AB925141355F009C2D".parse(),
+            Ok(EpisodeGroupSelector::Id(
+                "5b11b4ab925141355f009c2d".to_string()
+            ))
+        );
+        assert!("arc".parse::<EpisodeGroupSelector>().is_err());
+    }
+
+    #[test]
+    fn test_seasons_number_groups_by_order_with_specials_as_zero() {
+        let seasons = make_group().seasons();
+
+        let numbered: Vec<(u32, u32, &str)> = seasons
+            .iter()
+            .map(|season| {
+                (
+                    season.season_number,
+                    season.episode_count,
+                    season.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            numbered,
+            [(0, 1, "Specials"), (1, 2, "Arc 1"), (2, 1, "Arc 2")]
+        );
+    }
+
+    #[test]
+    fn test_episode_map_orders_episodes_within_each_group() {
+        let map = make_group().episode_map();
+
+        let lookup = |key| {
+            map.get(&key)
+                .map(|episode: &Episode| (episode.season_number, episode.episode_number))
+        };
+        assert_eq!(lookup((1, 1)), Some((1, 1)));
+        assert_eq!(lookup((1, 2)), Some((1, 2)));
+        assert_eq!(lookup((2, 1)), Some((2, 1)));
+        assert_eq!(lookup((0, 1)), Some((0, 1)));
+        assert_eq!(lookup((1, 3)), None);
+    }
+
+    #[test]
+    fn test_select_episode_group_prefers_most_episodes_of_type() {
+        let groups: Vec<EpisodeGroupSummary> = serde_json::from_value(json!([
+            {"id": "a", "name": "Absolute (short)", "type": 2, "episode_count": 10},
+            {"id": "b", "name": "DVD", "type": 3, "episode_count": 50},
+            {"id": "c", "name": "Absolute", "type": 2, "episode_count": 40},
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            select_episode_group(&groups, EpisodeGroupType::Absolute)
+                .map(|group| group.id.as_str()),
+            Some("c")
+        );
+        assert!(select_episode_group(&groups, EpisodeGroupType::StoryArc).is_none());
+    }
+}
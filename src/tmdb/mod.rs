@@ -1,5 +1,18 @@
-use anyhow::{Context, Result};
+mod episode_group;
+mod ranking;
+
+pub use episode_group::{
+    EpisodeGroupDetails, EpisodeGroupSelector, EpisodeGroupSummary, EpisodeGroupType,
+    select_episode_group,
+};
+pub use ranking::{RankedCandidate, is_confident, rank_candidates};
+
+use crate::cache::{CacheKind, MetadataCache};
+use crate::config::RetryConfig;
+use crate::retry::RetryPolicy;
+use anyhow::{Context, Result, bail};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
 use std::env;
 use std::path::Path;
 use std::sync::Arc;
@@ -21,7 +34,6 @@ pub struct SearchResult {
 pub struct TvShow {
     pub id: u32,
     pub name: String,
-    #[allow(dead_code)]
     pub original_name: String,
     pub first_air_date: Option<String>,
 }
@@ -87,6 +99,9 @@ pub struct SeasonDetails {
 #[derive(Debug, Deserialize, Clone)]
 pub struct Episode {
     pub id: u32,
+    /// 季详情中的单集可能省略季号，由调用方补全
+    #[serde(default)]
+    pub season_number: u32,
     pub episode_number: u32,
     pub name: String,
     #[serde(default)]
@@ -154,6 +169,8 @@ pub struct TmdbClient {
     api_key: String,
     base_url: String,
     semaphore: Arc<Semaphore>,
+    cache: MetadataCache,
+    retry: RetryPolicy,
 }
 
 impl TmdbClient {
@@ -163,82 +180,108 @@ impl TmdbClient {
             api_key: resolve_api_key(),
             base_url: resolve_base_url(),
             semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            cache: MetadataCache::disabled(),
+            retry: RetryPolicy::new("TMDB", &RetryConfig::default()),
         }
     }
 
-    pub async fn search_tv(&self, query: &str, language: &str) -> Result<Vec<TvShow>> {
-        let _permit = self.acquire_permit().await;
-        let url = self.build_url("/search/tv");
-
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("api_key", self.api_key.as_str()),
-                ("query", query),
-                ("language", language),
-            ])
-            .send()
-            .await
-            .context("TMDB 搜索请求发送失败")?
-            .error_for_status()
-            .context("TMDB 搜索请求返回错误状态")?;
+    pub fn with_cache(mut self, cache: MetadataCache) -> Self {
+        self.cache = cache;
+        self
+    }
 
-        let search_result: SearchResult = response.json().await.context("TMDB 搜索响应解析失败")?;
+    pub fn with_retry(mut self, config: &RetryConfig) -> Self {
+        self.retry = RetryPolicy::new("TMDB", config);
+        self
+    }
 
-        Ok(search_result.results)
+    pub fn is_offline(&self) -> bool {
+        self.cache.is_offline()
     }
 
-    pub async fn get_tv_details(&self, tv_id: u32, language: &str) -> Result<TvDetails> {
-        let _permit = self.acquire_permit().await;
-        let url = self.build_url(&format!("/tv/{tv_id}"));
+    /// 本客户端（及其克隆）累计的重试次数
+    pub fn retry_count(&self) -> usize {
+        self.retry.retry_count()
+    }
 
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("api_key", self.api_key.as_str()), ("language", language)])
-            .send()
-            .await
-            .context("TMDB 详情请求发送失败")?
-            .error_for_status()
-            .context("TMDB 详情请求返回错误状态")?;
+    pub async fn search_tv(&self, query: &str, language: &str) -> Result<Vec<TvShow>> {
+        let search_result: SearchResult = self
+            .get_json(
+                CacheKind::Search,
+                "/search/tv",
+                &[("query", query), ("language", language)],
+                "搜索",
+            )
+            .await?;
 
-        let details: TvDetails = response.json().await.context("TMDB 详情响应解析失败")?;
+        Ok(search_result.results)
+    }
 
-        Ok(details)
+    pub async fn get_tv_details(&self, tv_id: u32, language: &str) -> Result<TvDetails> {
+        self.get_json(
+            CacheKind::Show,
+            &format!("/tv/{tv_id}"),
+            &[("language", language)],
+            "详情",
+        )
+        .await
     }
 
-    #[allow(dead_code)]
     pub async fn get_season_details(
         &self,
         tv_id: u32,
         season_number: u32,
         language: &str,
     ) -> Result<SeasonDetails> {
-        let _permit = self.acquire_permit().await;
-        let url = self.build_url(&format!("/tv/{tv_id}/season/{season_number}"));
-
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("api_key", self.api_key.as_str()), ("language", language)])
-            .send()
-            .await
-            .context("TMDB 季详情请求发送失败")?
-            .error_for_status()
-            .context("TMDB 季详情请求返回错误状态")?;
+        self.get_json(
+            CacheKind::Season,
+            &format!("/tv/{tv_id}/season/{season_number}"),
+            &[("language", language)],
+            "季详情",
+        )
+        .await
+    }
 
-        let season: SeasonDetails = response.json().await.context("TMDB 季详情响应解析失败")?;
+    pub async fn get_episode_groups(
+        &self,
+        tv_id: u32,
+        language: &str,
+    ) -> Result<Vec<EpisodeGroupSummary>> {
+        let list: episode_group::EpisodeGroupList = self
+            .get_json(
+                CacheKind::Show,
+                &format!("/tv/{tv_id}/episode_groups"),
+                &[("language", language)],
+                "剧集组列表",
+            )
+            .await?;
+
+        Ok(list.results)
+    }
 
-        Ok(season)
+    pub async fn get_episode_group(
+        &self,
+        group_id: &str,
+        language: &str,
+    ) -> Result<EpisodeGroupDetails> {
+        self.get_json(
+            CacheKind::Season,
+            &format!("/tv/episode_group/{group_id}"),
+            &[("language", language)],
+            "剧集组",
+        )
+        .await
     }
 
     pub async fn download_image(&self, file_path: &str) -> Result<Vec<u8>> {
+        if self.is_offline() {
+            bail!("离线模式下无法下载 TMDB 图片: {file_path}");
+        }
         let _permit = self.acquire_permit().await;
+        let url = self.build_image_url(file_path);
         let response = self
-            .client
-            .get(self.build_image_url(file_path))
-            .send()
+            .retry
+            .send(|| self.client.get(&url))
             .await
             .context("TMDB 图片下载请求发送失败")?
             .error_for_status()
@@ -256,27 +299,13 @@ impl TmdbClient {
         episode_number: u32,
         language: &str,
     ) -> Result<EpisodeCredits> {
-        let _permit = self.acquire_permit().await;
-        let url = self.build_url(&format!(
-            "/tv/{tv_id}/season/{season_number}/episode/{episode_number}/credits"
-        ));
-
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("api_key", self.api_key.as_str()), ("language", language)])
-            .send()
-            .await
-            .context("TMDB 单集演职员请求发送失败")?
-            .error_for_status()
-            .context("TMDB 单集演职员请求返回错误状态")?;
-
-        let credits: EpisodeCredits = response
-            .json()
-            .await
-            .context("TMDB 单集演职员响应解析失败")?;
-
-        Ok(credits)
+        self.get_json(
+            CacheKind::Episode,
+            &format!("/tv/{tv_id}/season/{season_number}/episode/{episode_number}/credits"),
+            &[("language", language)],
+            "单集演职员",
+        )
+        .await
     }
 
     pub async fn get_episode_external_ids(
@@ -285,27 +314,58 @@ impl TmdbClient {
         season_number: u32,
         episode_number: u32,
     ) -> Result<EpisodeExternalIds> {
+        self.get_json(
+            CacheKind::Episode,
+            &format!("/tv/{tv_id}/season/{season_number}/episode/{episode_number}/external_ids"),
+            &[],
+            "单集外部 ID",
+        )
+        .await
+    }
+
+    /// 发送 GET 请求并解析 JSON；先查本地缓存，缓存键不含 api_key
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        kind: CacheKind,
+        path: &str,
+        params: &[(&str, &str)],
+        what: &str,
+    ) -> Result<T> {
+        let url = self.build_url(path);
+        let key = cache_key(&url, params);
+
+        if let Some(body) = self.cache.get(kind, &key)
+            && let Ok(value) = serde_json::from_value(body)
+        {
+            return Ok(value);
+        }
+        if self.is_offline() {
+            bail!(
+                "离线模式下没有缓存的 TMDB {what}数据: {}",
+                offline_description(path, params)
+            );
+        }
+
         let _permit = self.acquire_permit().await;
-        let url = self.build_url(&format!(
-            "/tv/{tv_id}/season/{season_number}/episode/{episode_number}/external_ids"
-        ));
+        let mut query = vec![("api_key", self.api_key.as_str())];
+        query.extend_from_slice(params);
 
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("api_key", self.api_key.as_str())])
-            .send()
+        let body: serde_json::Value = self
+            .retry
+            .send(|| self.client.get(&url).query(&query))
             .await
-            .context("TMDB 单集外部 ID 请求发送失败")?
+            .with_context(|| format!("TMDB {what}请求发送失败"))?
             .error_for_status()
-            .context("TMDB 单集外部 ID 请求返回错误状态")?;
-
-        let external_ids: EpisodeExternalIds = response
+            .with_context(|| format!("TMDB {what}请求返回错误状态"))?
             .json()
             .await
-            .context("TMDB 单集外部 ID 响应解析失败")?;
+            .with_context(|| format!("TMDB {what}响应解析失败"))?;
+
+        let value = serde_json::from_value(body.clone())
+            .with_context(|| format!("TMDB {what}响应解析失败"))?;
+        self.cache.put(kind, &key, &body);
 
-        Ok(external_ids)
+        Ok(value)
     }
 
     fn build_url(&self, path: &str) -> String {
@@ -325,6 +385,27 @@ impl TmdbClient {
     }
 }
 
+fn offline_description(path: &str, params: &[(&str, &str)]) -> String {
+    if params.is_empty() {
+        return path.to_string();
+    }
+    let params = params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{path} ({params})")
+}
+
+fn cache_key(url: &str, params: &[(&str, &str)]) -> String {
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{url}?{query}")
+}
+
 fn build_http_client() -> reqwest::Client {
     reqwest::Client::builder()
         .timeout(Duration::from_secs(HTTP_TIMEOUT_SECONDS))
@@ -370,6 +451,9 @@ pub fn image_extension(file_path: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::CacheMode;
+    use crate::config::CacheConfig;
+    use crate::test_support::TestDir;
 
     #[test]
     fn test_resolve_api_key_falls_back_to_default() {
@@ -405,6 +489,32 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_offline_miss_names_endpoint_without_network() {
+        let dir = TestDir::new("tmdb_offline");
+        let cache = MetadataCache::new(
+            dir.path().to_path_buf(),
+            &CacheConfig::default(),
+            CacheMode::Offline,
+        );
+        let client = TmdbClient::new().with_cache(cache);
+
+        let error = client
+            .get_season_details(1234, 2, "zh-CN")
+            .await
+            .unwrap_err();
+        let image_error = client.download_image("/poster.jpg").await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "离线模式下没有缓存的 TMDB 季详情数据: /tv/1234/season/2 (language=zh-CN)"
+        );
+        assert_eq!(
+            image_error.to_string(),
+            "离线模式下无法下载 TMDB 图片: /poster.jpg"
+        );
+    }
+
     #[test]
     fn test_build_url_appends_fixed_api_version() {
         let client = TmdbClient {
@@ -412,6 +522,8 @@ mod tests {
             api_key: "key".to_string(),
             base_url: "https://example.com/tmdb".to_string(),
             semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            cache: MetadataCache::disabled(),
+            retry: RetryPolicy::new("TMDB", &RetryConfig::default()),
         };
 
         assert_eq!(
@@ -427,6 +539,8 @@ mod tests {
             api_key: "key".to_string(),
             base_url: resolve_base_url_from_env(Some("https://example.com/tmdb/3".to_string())),
             semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            cache: MetadataCache::disabled(),
+            retry: RetryPolicy::new("TMDB", &RetryConfig::default()),
         };
 
         assert_eq!(
@@ -556,6 +670,8 @@ mod tests {
             api_key: "key".to_string(),
             base_url: "https://example.com/tmdb".to_string(),
             semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            cache: MetadataCache::disabled(),
+            retry: RetryPolicy::new("TMDB", &RetryConfig::default()),
         };
 
         assert_eq!(
@@ -0,0 +1,182 @@
+use super::TvShow;
+use std::collections::HashSet;
+
+/// 最佳候选达到该分数且明显领先第二名时自动采用
+const AUTO_ACCEPT_SCORE: f64 = 0.85;
+const AUTO_ACCEPT_MARGIN: f64 = 0.1;
+const YEAR_MATCH_BONUS: f64 = 0.1;
+const YEAR_MISMATCH_PENALTY: f64 = 0.2;
+
+#[derive(Debug)]
+pub struct RankedCandidate<'a> {
+    pub show: &'a TvShow,
+    pub score: f64,
+}
+
+/// 按标题相似度（名称与原名取较高者）和首播年份为搜索结果打分，按分数降序排列；
+/// 分数相同时保持 TMDB 的原始顺序
+pub fn rank_candidates<'a>(
+    results: &'a [TvShow],
+    query: &str,
+    year: Option<u32>,
+) -> Vec<RankedCandidate<'a>> {
+    let mut ranked: Vec<RankedCandidate<'a>> = results
+        .iter()
+        .map(|show| RankedCandidate {
+            show,
+            score: candidate_score(show, query, year),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+/// 最佳候选是否足够可信，可以不经确认直接采用
+pub fn is_confident(ranked: &[RankedCandidate]) -> bool {
+    match ranked {
+        [] => false,
+        [best] => best.score >= AUTO_ACCEPT_SCORE,
+        [best, second, ..] => {
+            best.score >= AUTO_ACCEPT_SCORE && best.score - second.score >= AUTO_ACCEPT_MARGIN
+        }
+    }
+}
+
+/// 首播日期中的年份
+fn air_year(show: &TvShow) -> Option<u32> {
+    show.first_air_date
+        .as_deref()
+        .and_then(|date| date.get(..4))
+        .and_then(|year| year.parse().ok())
+}
+
+fn candidate_score(show: &TvShow, query: &str, year: Option<u32>) -> f64 {
+    let similarity =
+        title_similarity(query, &show.name).max(title_similarity(query, &show.original_name));
+
+    match (year, air_year(show)) {
+        (Some(expected), Some(actual)) if expected == actual => similarity + YEAR_MATCH_BONUS,
+        // 年末开播的番剧常被标为下一年
+        (Some(expected), Some(actual)) if expected.abs_diff(actual) == 1 => similarity,
+        (Some(_), Some(_)) => similarity - YEAR_MISMATCH_PENALTY,
+        _ => similarity,
+    }
+}
+
+fn normalize_title(title: &str) -> Vec<char> {
+    title
+        .chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 标题相似度（0.0 ~ 1.0）：忽略大小写与标点后完全相同为 1.0，
+/// 包含关系按长度比例计分，其余使用字符二元组的 Dice 系数
+fn title_similarity(left: &str, right: &str) -> f64 {
+    let left = normalize_title(left);
+    let right = normalize_title(right);
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    if left == right {
+        return 1.0;
+    }
+
+    let (shorter, longer) = if left.len() <= right.len() {
+        (&left, &right)
+    } else {
+        (&right, &left)
+    };
+    let contains = longer
+        .windows(shorter.len())
+        .any(|window| window == shorter.as_slice());
+    let containment = if contains {
+        0.7 + 0.25 * shorter.len() as f64 / longer.len() as f64
+    } else {
+        0.0
+    };
+
+    containment.max(dice_coefficient(&left, &right))
+}
+
+fn dice_coefficient(left: &[char], right: &[char]) -> f64 {
+    if left.len() < 2 || right.len() < 2 {
+        return 0.0;
+    }
+
+    let left_bigrams: HashSet<(char, char)> = left.windows(2).map(|w| (w[0], w[1])).collect();
+    let right_bigrams: HashSet<(char, char)> = right.windows(2).map(|w| (w[0], w[1])).collect();
+    let shared = left_bigrams.intersection(&right_bigrams).count();
+
+    2.0 * shared as f64 / (left_bigrams.len() + right_bigrams.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(id: u32, name: &str, original_name: &str, first_air_date: &str) -> TvShow {
+        TvShow {
+            id,
+            name: name.to_string(),
+            original_name: original_name.to_string(),
+            first_air_date: Some(first_air_date.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rank_candidates_prefers_exact_title_over_tmdb_order() {
+        let results = vec![
+            show(
+                1,
+                "Frieren: Beyond Journey's End Recap",
+                "葬送のフリーレン 総集編",
+                "2024-01-01",
+            ),
+            show(
+                2,
+                "Frieren: Beyond Journey's End",
+                "葬送のフリーレン",
+                "2023-09-29",
+            ),
+        ];
+
+        let ranked = rank_candidates(&results, "葬送のフリーレン", None);
+
+        assert_eq!(ranked[0].show.id, 2);
+        assert!(is_confident(&ranked));
+    }
+
+    #[test]
+    fn test_rank_candidates_uses_year_to_break_ties() {
+        let results = vec![
+            show(1, "Fruits Basket", "フルーツバスケット", "2001-07-05"),
+            show(2, "Fruits Basket", "フルーツバスケット", "2019-04-06"),
+        ];
+
+        let without_year = rank_candidates(&results, "Fruits Basket", None);
+        assert!(!is_confident(&without_year));
+
+        let with_year = rank_candidates(&results, "Fruits Basket", Some(2019));
+        assert_eq!(with_year[0].show.id, 2);
+        assert!(is_confident(&with_year));
+    }
+
+    #[test]
+    fn test_is_confident_rejects_weak_single_match() {
+        let results = vec![show(1, "Another Show", "Another Show", "2020-01-01")];
+
+        let ranked = rank_candidates(&results, "Frieren", None);
+
+        assert!(!is_confident(&ranked));
+        assert!(!is_confident(&[]));
+    }
+
+    #[test]
+    fn test_title_similarity_ignores_case_and_punctuation() {
+        assert_eq!(title_similarity("Oshi no Ko", "【推しの子】"), 0.0);
+        assert_eq!(title_similarity("oshi-no-ko", "Oshi no Ko"), 1.0);
+        assert!(title_similarity("Bocchi the Rock", "Bocchi the Rock!") > 0.99);
+    }
+}